@@ -1,85 +1,469 @@
-use std::env::VarError;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use dotenv::dotenv;
-use solana_client::client_error::ClientError;
-use solana_client::rpc_client::RpcClient;
-use solana_client::rpc_config::RpcRequestAirdropConfig;
+use std::time::Duration;
+use clap::{Args, Parser, Subcommand};
 use solana_program::native_token::LAMPORTS_PER_SOL;
-use solana_program::pubkey::{ParsePubkeyError, Pubkey};
-use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
-use solana_sdk::signature::{Keypair, Signer};
-use thiserror::Error;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
 
+use practice_2::{
+    AirdropConfig, AirdropError, Amount, ClusterConfig, Error, KeypairSource, Keystore, SignerSource,
+};
+use practice_2::airdrop::airdrop;
+use practice_2::amount::format_sol;
+use practice_2::config::ClusterArgs;
+use practice_2::grind::{GrindEvent, Grinder, VanityPattern};
+use practice_2::keypair::write_keypair_file;
+use practice_2::keystore::{read_new_password, read_password};
+use practice_2::mnemonic::{
+    format_path, generate_mnemonic, parse_mnemonic, DerivationTemplate, Mnemonic, SeedWallet,
+};
+use practice_2::watch::{hook_command, watch, Source, Threshold, WatchConfig, WatchEvent};
 
-#[derive(Debug, Error)]
-enum Error {
-    #[error(transparent)]
-    ParsePubkey (#[from] ParsePubkeyError),
-    #[error(transparent)]
-    Client (#[from] ClientError),
-    #[error(transparent)]
-    Var (#[from] VarError),
-    #[error(transparent)]
-    BadKeypair (#[from] ed25519_dalek::SignatureError),
-    #[error(transparent)]
-    Other (#[from] serde_json::Error)
+/// Practice 1: keypairs and balances.
+/// Without a subcommand runs the whole practice: generate, load, check balance.
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    cluster: ClusterArgs,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-fn check_balance() -> Result<(), Error> {
-    const CLUSTER_URI: &str = "https://api.devnet.solana.com";
-    let client = RpcClient::new(CLUSTER_URI);
+#[derive(Subcommand)]
+enum Command {
+    /// Generates a new BIP39 seed phrase and derives keypairs from it
+    Generate {
+        /// Number of words in the seed phrase, 12 or 24
+        #[arg(long, default_value_t = 12)]
+        words: usize,
+        #[command(flatten)]
+        derive: DeriveArgs,
+    },
+    /// Recovers keypairs from an existing BIP39 seed phrase
+    Recover {
+        /// Seed phrase; asked for interactively when omitted
+        #[arg(long)]
+        phrase: Option<String>,
+        #[command(flatten)]
+        derive: DeriveArgs,
+    },
+    /// Searches for keypairs with a vanity address on every CPU core
+    Grind {
+        /// Address prefix
+        #[arg(long, required_unless_present = "ends_with")]
+        starts_with: Option<String>,
+        /// Address suffix
+        #[arg(long)]
+        ends_with: Option<String>,
+        /// Match prefix and suffix case-insensitively
+        #[arg(long)]
+        ignore_case: bool,
+        /// Stop after this many matches
+        #[arg(long, default_value_t = 1)]
+        count: usize,
+        /// Worker threads, defaults to the number of CPU cores
+        #[arg(long)]
+        threads: Option<usize>,
+        /// Directory to write `<address>.json` keypair files to
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+    },
+    /// Manages password-encrypted keystore files
+    #[command(subcommand)]
+    Keystore(KeystoreCommand),
+    /// Loads a keypair and prints its public and secret key
+    Load {
+        /// Signer source (path, `env:NAME`, `-`, `cli-config`, ...). Only the
+        /// public key is printed for signers that do not give the secret key.
+        /// Defaults to `KEYPAIR`/`SECRET_KEY`/Solana CLI config
+        #[arg(long, short = 'k')]
+        keypair: Option<SignerSource>,
+    },
+    /// Airdrops 1 SOL to the local wallet and prints its balance
+    Balance,
+    /// Streams SOL and token balance changes of accounts over websockets
+    Watch {
+        /// Wallets or token accounts, e.g. the vault of an escrow offer
+        #[arg(required = true)]
+        addresses: Vec<Pubkey>,
+        /// Alert when a balance rises above this amount, in SOL or tokens
+        #[arg(long)]
+        above: Option<Amount>,
+        /// Alert when a balance drops below this amount, in SOL or tokens
+        #[arg(long)]
+        below: Option<Amount>,
+        /// Shell command to run on an alert, with `WATCH_ADDRESS`,
+        /// `WATCH_THRESHOLD`, `WATCH_PREVIOUS` and `WATCH_BALANCE` set
+        #[arg(long)]
+        hook: Option<String>,
+        /// Seconds between polls of accounts without a live subscription
+        #[arg(long, default_value_t = 5)]
+        poll_interval: u64,
+    },
+    /// Airdrops SOL to a list of wallets, waiting for each airdrop to land
+    Airdrop {
+        /// Wallet addresses
+        recipients: Vec<Pubkey>,
+        /// File with one wallet address per line
+        #[arg(long, required_unless_present = "recipients")]
+        file: Option<PathBuf>,
+        /// SOL for each wallet
+        #[arg(long, default_value = "1")]
+        sol: Amount,
+        /// Most SOL per faucet request, larger amounts are split; defaults
+        /// to the cap of the devnet or testnet faucet
+        #[arg(long)]
+        max_per_request: Option<Amount>,
+        /// Only airdrop what a wallet lacks to hold `--sol`
+        #[arg(long)]
+        top_up: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeystoreCommand {
+    /// Encrypts a keypair into a new keystore file
+    Import {
+        /// Keypair source (path, `env:NAME`, `-`, `cli-config`), see `KEYPAIR`
+        #[arg(long)]
+        keypair: Option<KeypairSource>,
+        /// Keystore file to create
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Decrypts a keystore into a plain Solana CLI keypair file
+    Export {
+        /// Keystore file
+        keystore: PathBuf,
+        /// Keypair file to write, prints to stdout when omitted
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Re-encrypts a keystore with a new password
+    ChangePassword {
+        /// Keystore file
+        keystore: PathBuf,
+    },
+}
+
+#[derive(Args)]
+struct DeriveArgs {
+    /// Ask for a BIP39 passphrase
+    #[arg(long)]
+    passphrase: bool,
+    /// SLIP-10 derivation path, `n` is replaced with the account index
+    #[arg(long, default_value_t = DerivationTemplate::default())]
+    path: DerivationTemplate,
+    /// Number of accounts to derive
+    #[arg(long, default_value_t = 1)]
+    accounts: u32,
+    /// Directory to write `account-<n>.json` keypair files to
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+}
+
+impl Default for DeriveArgs {
+    fn default() -> Self {
+        DeriveArgs {
+            passphrase: false,
+            path: DerivationTemplate::default(),
+            accounts: 1,
+            out_dir: None,
+        }
+    }
+}
 
-    println!("⚡️ Connected to devnet");
+fn check_balance(cluster: &ClusterArgs) -> Result<(), Error> {
+    let cluster = ClusterConfig::resolve(cluster)?;
+    let client = cluster.rpc_client();
+
+    println!("⚡️ Connected to {}", cluster.url);
 
     let local_wallet_pubkey =
         Pubkey::from_str("Dw1qLN2zozkt9NEwDjHa6eVxLJFA6jgE3ENPrV8YEbei")?;
 
-    let _signature = client.request_airdrop_with_config(
-        &local_wallet_pubkey,
-        LAMPORTS_PER_SOL,
-        RpcRequestAirdropConfig {
-            recent_blockhash: None,
-            commitment: Some(CommitmentConfig { commitment: CommitmentLevel::Confirmed }),
-        })?;
+    let airdrop = airdrop(&client, &local_wallet_pubkey, LAMPORTS_PER_SOL, &AirdropConfig::for_url(&cluster.url))?;
+
+    let balance_in_sol = format_sol(airdrop.balance);
+
+    println!("💰 The balance for the wallet at address {} is: {} SOL", local_wallet_pubkey, balance_in_sol);
+
+    Ok(())
+}
+
+/// Prints balance changes of `addresses` until interrupted, running `hook`
+/// when one crosses a threshold.
+fn watch_balances(
+    cluster: &ClusterArgs,
+    addresses: &[Pubkey],
+    thresholds: Vec<Threshold>,
+    hook: Option<&str>,
+    poll_interval: Duration,
+) -> Result<(), Error> {
+    let cluster = ClusterConfig::resolve(cluster)?;
+    let client = cluster.rpc_client();
+    let config = WatchConfig {
+        poll_interval,
+        thresholds,
+        ..WatchConfig::new(&cluster)
+    };
+
+    println!("⚡️ Watching {} accounts on {}", addresses.len(), cluster.url);
+
+    watch(&client, addresses, &config, |event| {
+        match event {
+            WatchEvent::Balance { address, balance, source: Source::Websocket } => println!("💰 {address}: {balance}"),
+            WatchEvent::Balance { address, balance, source: Source::Poll } => println!("💰 {address}: {balance} (polled)"),
+            WatchEvent::Subscribed(address) => println!("🔌 Subscribed to {address}"),
+            WatchEvent::Unsubscribed { address, error } => {
+                eprintln!("⚠️ Subscription to {address} dropped ({error}), polling until it reconnects");
+            }
+            WatchEvent::Crossed(crossing) => {
+                println!(
+                    "🚨 {} went {}: {} -> {}",
+                    crossing.address, crossing.threshold, crossing.previous, crossing.balance
+                );
+                if let Some(hook) = hook {
+                    match hook_command(hook, &crossing).status() {
+                        Ok(status) if status.success() => {}
+                        Ok(status) => eprintln!("⚠️ Hook exited with {status}"),
+                        Err(error) => eprintln!("⚠️ Hook failed to start: {error}"),
+                    }
+                }
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    })
+}
+
+/// Airdrops `sol` to every wallet of `recipients` and of the `file` list.
+fn fund_wallets(
+    cluster: &ClusterArgs,
+    mut recipients: Vec<Pubkey>,
+    file: Option<&Path>,
+    sol: Amount,
+    max_per_request: Option<Amount>,
+    top_up: bool,
+) -> Result<(), Error> {
+    let cluster = ClusterConfig::resolve(cluster)?;
+    let client = cluster.rpc_client();
+
+    if let Some(file) = file {
+        recipients.extend(read_wallet_list(file)?);
+    }
+
+    let mut config = AirdropConfig::for_url(&cluster.url);
+    if let Some(max) = max_per_request {
+        config.max_per_request = Some(max.to_lamports()?);
+    }
+    config.top_up = top_up;
+
+    println!("⚡️ Connected to {}", cluster.url);
+
+    let mut failed = 0;
+    for (recipient, result) in practice_2::airdrop::fund_wallets(&client, &recipients, sol.to_lamports()?, &config) {
+        match result {
+            Ok(airdrop) if airdrop.signatures.is_empty() => {
+                println!("✅ {recipient} already has {} SOL", format_sol(airdrop.balance));
+            }
+            Ok(airdrop) => {
+                println!(
+                    "🪂 {recipient} received {} SOL in {} airdrop(s), balance {} SOL",
+                    format_sol(airdrop.lamports), airdrop.signatures.len(), format_sol(airdrop.balance)
+                );
+            }
+            Err(error) => {
+                failed += 1;
+                eprintln!("❌ {recipient}: {error}");
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(AirdropError::Unfunded { failed, total: recipients.len() }.into());
+    }
+
+    Ok(())
+}
+
+/// Reads one address per line; blank lines and `#` comments are skipped.
+fn read_wallet_list(path: &Path) -> Result<Vec<Pubkey>, Error> {
+    let mut wallets = Vec::new();
+    for line in std::fs::read_to_string(path)?.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if !line.is_empty() {
+            wallets.push(Pubkey::from_str(line)?);
+        }
+    }
+
+    Ok(wallets)
+}
+
+fn generate_keypair(words: usize, derive: &DeriveArgs) -> Result<(), Error> {
+    let mnemonic = generate_mnemonic(words)?;
+
+    println!("🔑 Seed phrase (write it down, it is not stored anywhere):");
+    println!("{}", mnemonic.phrase());
+
+    derive_keypairs(&mnemonic, derive)
+}
+
+fn recover_keypair(phrase: Option<String>, derive: &DeriveArgs) -> Result<(), Error> {
+    let phrase = match phrase {
+        Some(phrase) => phrase,
+        None => rpassword::prompt_password("Seed phrase: ")?,
+    };
+
+    let mnemonic = parse_mnemonic(&phrase)?;
+
+    derive_keypairs(&mnemonic, derive)
+}
+
+/// Derives `derive.accounts` keypairs and prints their public keys,
+/// optionally saving them as Solana CLI keypair files.
+fn derive_keypairs(mnemonic: &Mnemonic, derive: &DeriveArgs) -> Result<(), Error> {
+    let passphrase = if derive.passphrase {
+        rpassword::prompt_password("BIP39 passphrase: ")?
+    } else {
+        String::new()
+    };
 
-    let balance_lamports = client.get_balance(&local_wallet_pubkey)?;
+    let wallet = SeedWallet::new(mnemonic, &passphrase);
 
-    let balance_in_sol = balance_lamports / LAMPORTS_PER_SOL;
+    if let Some(out_dir) = &derive.out_dir {
+        std::fs::create_dir_all(out_dir)?;
+    }
 
-    println!("💰 The balance for the wallet at address ${} is: ${}", local_wallet_pubkey, balance_in_sol);
+    for (index, (path, keypair)) in wallet.accounts(&derive.path, derive.accounts)?.iter().enumerate() {
+        println!("{} {}", format_path(path), keypair.pubkey());
+
+        if let Some(out_dir) = &derive.out_dir {
+            write_keypair_file(keypair, &out_dir.join(format!("account-{index}.json")))?;
+        }
+    }
 
     Ok(())
 }
 
-fn generate_keypair() {
-    let keypair = Keypair::new();
-    println!("The public key is: {}", keypair.pubkey());
-    println!("The secret key is: {:?}", keypair.secret());
+fn grind_keypairs(pattern: VanityPattern, count: usize, threads: Option<usize>, out_dir: &Path) -> Result<(), Error> {
+    let mut grinder = Grinder::new(pattern);
+    if let Some(threads) = threads {
+        grinder = grinder.threads(threads);
+    }
+
+    std::fs::create_dir_all(out_dir)?;
+
+    let mut progress_line = false;
+    grinder.grind(count, |event| {
+        match event {
+            GrindEvent::Progress(progress) => {
+                eprint!(
+                    "\r⛏️ {} attempts, {:.0}/s, {}/{} found",
+                    progress.attempts, progress.rate(), progress.found, count
+                );
+                progress_line = true;
+            }
+            GrindEvent::Match(keypair) => {
+                let path = out_dir.join(format!("{}.json", keypair.pubkey()));
+                write_keypair_file(keypair, &path)?;
+                if std::mem::take(&mut progress_line) {
+                    eprintln!();
+                }
+                println!("✅ Found {}, saved to {}", keypair.pubkey(), path.display());
+            }
+        }
+        Ok(())
+    })
 }
 
-fn load_keypair() -> Result<(), Error> {
-    dotenv().ok();
-    let secret_key = std::env::var("SECRET_KEY")?;
+fn run_keystore_command(command: KeystoreCommand) -> Result<(), Error> {
+    match command {
+        KeystoreCommand::Import { keypair, out } => {
+            let keypair = match keypair {
+                Some(source) => source.load()?,
+                None => practice_2::load_keypair()?,
+            };
+
+            let password = read_new_password("New keystore password: ")?;
+            Keystore::encrypt(&keypair, &password)?.write(&out)?;
+
+            println!("🔐 Saved {} to {}", keypair.pubkey(), out.display());
+        }
+        KeystoreCommand::Export { keystore, out } => {
+            let keystore = Keystore::read(&keystore)?;
+            let password = read_password(&format!("Password for {}: ", keystore.pubkey))?;
+            let keypair = keystore.decrypt(&password)?;
+
+            match out {
+                Some(out) => {
+                    write_keypair_file(&keypair, &out)?;
+                    println!("🔓 Saved {} to {}", keypair.pubkey(), out.display());
+                }
+                None => println!("{}", serde_json::to_string(&keypair.to_bytes().to_vec())?),
+            }
+        }
+        KeystoreCommand::ChangePassword { keystore: path } => {
+            let keystore = Keystore::read(&path)?;
+            let password = read_password(&format!("Current password for {}: ", keystore.pubkey))?;
+            let keypair = keystore.decrypt(&password)?;
 
-    let secret_key_bytes: Vec<u8> = serde_json::from_str(&secret_key)?;
+            let new_password = read_new_password("New keystore password: ")?;
+            Keystore::encrypt(&keypair, &new_password)?.write(&path)?;
 
-    let secret_key_array: [u8; 64] = secret_key_bytes.try_into().expect("Expected 64 bytes");
+            println!("🔐 Changed password of {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn load_keypair(source: Option<&SignerSource>) -> Result<(), Error> {
+    let keypair = match source {
+        None => practice_2::load_keypair()?,
+        Some(SignerSource::Keypair(source)) => source.load()?,
+        Some(source) => {
+            println!("Public key: {}", source.signer()?.pubkey());
+            return Ok(());
+        }
+    };
 
-    let keypair = Keypair::from_bytes(&secret_key_array)?;
-    
     println!("Public key: {}", keypair.pubkey());
     println!("Secret key: {:?}", keypair.secret());
-    
+
     Ok(())
 }
 
-fn main() {
-    // Demonstrating how generating keypair can be done.
-    generate_keypair();
+fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Generate { words, derive }) => generate_keypair(words, &derive),
+        Some(Command::Recover { phrase, derive }) => recover_keypair(phrase, &derive),
+        Some(Command::Grind { starts_with, ends_with, ignore_case, count, threads, out_dir }) => {
+            let pattern = VanityPattern::new(starts_with, ends_with, ignore_case)?;
+            grind_keypairs(pattern, count, threads, &out_dir)
+        }
+        Some(Command::Keystore(command)) => run_keystore_command(command),
+        Some(Command::Load { keypair }) => load_keypair(keypair.as_ref()),
+        Some(Command::Balance) => check_balance(&cli.cluster),
+        Some(Command::Watch { addresses, above, below, hook, poll_interval }) => {
+            let thresholds = above.map(Threshold::Above).into_iter().chain(below.map(Threshold::Below)).collect();
+            watch_balances(&cli.cluster, &addresses, thresholds, hook.as_deref(), Duration::from_secs(poll_interval))
+        }
+        Some(Command::Airdrop { recipients, file, sol, max_per_request, top_up }) => {
+            fund_wallets(&cli.cluster, recipients, file.as_deref(), sol, max_per_request, top_up)
+        }
+        None => {
+            // Demonstrating how generating keypair can be done.
+            generate_keypair(12, &DeriveArgs::default())?;
 
-    // Loading Keypair from a file
-    load_keypair().unwrap();
+            // Loading Keypair (`KEYPAIR`, `SECRET_KEY` or Solana CLI keypair file)
+            load_keypair(None)?;
 
-    // Checking balance on a local machine wallet (devnet)
-    check_balance().unwrap();
+            // Checking balance on a local machine wallet
+            check_balance(&cli.cluster)
+        }
+    }
 }
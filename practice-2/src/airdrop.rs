@@ -0,0 +1,245 @@
+//! Airdrops from the faucet of devnet, testnet or a local validator.
+//!
+//! `requestAirdrop` only hands the request to the faucet: it returns the
+//! signature of the faucet transfer before it lands. [`airdrop`] waits
+//! until every transfer is confirmed at the client's commitment, so the
+//! balance read afterwards includes it.
+//!
+//! Public faucets cap what one request may ask for and how often requests
+//! may come. Amounts above [`AirdropConfig::max_per_request`] are split
+//! into several requests, and refused requests are retried with
+//! exponential backoff.
+
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcError;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use thiserror::Error;
+
+use crate::sender::{backoff, classify, status_response, ErrorClass, POLL_INTERVAL};
+
+#[derive(Debug, Error)]
+pub enum AirdropError {
+    #[error("the faucet refused {attempts} airdrop requests, try again later")]
+    RateLimited { attempts: u32 },
+    #[error("airdrop {signature} failed: {error}")]
+    Failed {
+        signature: Signature,
+        error: solana_sdk::transaction::TransactionError,
+    },
+    #[error("airdrop {0} was not confirmed in time")]
+    NotConfirmed(Signature),
+    #[error("{failed} of {total} wallets were not funded")]
+    Unfunded { failed: usize, total: usize },
+}
+
+/// Limits of [`airdrop`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AirdropConfig {
+    /// Most lamports one request may ask for; `None` for a faucet without
+    /// a cap, as that of `solana-test-validator`.
+    pub max_per_request: Option<u64>,
+    /// Only request what is missing from the balance to reach the amount.
+    pub top_up: bool,
+    /// Requests of one part before giving up on a refusing faucet.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// How long to wait for one faucet transfer to be confirmed.
+    pub confirm_timeout: Duration,
+}
+
+impl Default for AirdropConfig {
+    fn default() -> Self {
+        AirdropConfig {
+            max_per_request: None,
+            top_up: false,
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(30),
+            confirm_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+impl AirdropConfig {
+    /// Defaults with the per-request cap of the public faucet at `url`.
+    pub fn for_url(url: &str) -> Self {
+        let max_per_request = if url.contains("devnet") {
+            Some(2 * LAMPORTS_PER_SOL)
+        } else if url.contains("testnet") {
+            Some(LAMPORTS_PER_SOL)
+        } else {
+            None
+        };
+
+        AirdropConfig {
+            max_per_request,
+            ..AirdropConfig::default()
+        }
+    }
+
+    /// Wait after failed request number `attempt`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        backoff(self.initial_backoff, self.max_backoff, attempt)
+    }
+}
+
+/// A confirmed airdrop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Airdrop {
+    pub recipient: Pubkey,
+    /// Lamports requested, `0` when a top-up found the balance sufficient.
+    pub lamports: u64,
+    /// One faucet transfer per part of the amount.
+    pub signatures: Vec<Signature>,
+    /// Balance of the recipient after the airdrop.
+    pub balance: u64,
+}
+
+/// Splits `lamports` into parts of at most `max_per_request`.
+pub fn split_amount(lamports: u64, max_per_request: Option<u64>) -> Vec<u64> {
+    match max_per_request {
+        Some(max) if max > 0 && lamports > max => {
+            let mut parts = vec![max; (lamports / max) as usize];
+            if !lamports.is_multiple_of(max) {
+                parts.push(lamports % max);
+            }
+            parts
+        }
+        _ if lamports == 0 => Vec::new(),
+        _ => vec![lamports],
+    }
+}
+
+/// Whether the faucet refused a request because of its rate limit (or
+/// because it ran dry, which public faucets report the same way). Other
+/// internal errors of the node are not retried here.
+pub fn is_rate_limited(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Reqwest(error) => {
+            error.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS)
+        }
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, .. }) => {
+            let message = message.to_lowercase();
+            *code == 429
+                || message.contains("rate limit")
+                || message.contains("airdrop limit")
+                || message.contains("run dry")
+                || message.contains("too many requests")
+        }
+        ClientErrorKind::RpcError(RpcError::ForUser(message)) => message.contains("429"),
+        _ => false,
+    }
+}
+
+/// Airdrops `lamports` to `recipient` and waits until every faucet
+/// transfer is confirmed.
+pub fn airdrop(
+    client: &RpcClient,
+    recipient: &Pubkey,
+    lamports: u64,
+    config: &AirdropConfig,
+) -> Result<Airdrop, crate::Error> {
+    let lamports = if config.top_up {
+        lamports.saturating_sub(client.get_balance(recipient)?)
+    } else {
+        lamports
+    };
+
+    let mut signatures = Vec::new();
+    for part in split_amount(lamports, config.max_per_request) {
+        let signature = request_part(client, recipient, part, config)?;
+        confirm(client, &signature, config.confirm_timeout)?;
+        signatures.push(signature);
+    }
+
+    Ok(Airdrop {
+        recipient: *recipient,
+        lamports,
+        signatures,
+        balance: client.get_balance(recipient)?,
+    })
+}
+
+/// Airdrops `lamports` to each of `recipients` in turn. A failed airdrop
+/// does not stop the others.
+pub fn fund_wallets(
+    client: &RpcClient,
+    recipients: &[Pubkey],
+    lamports: u64,
+    config: &AirdropConfig,
+) -> Vec<(Pubkey, Result<Airdrop, crate::Error>)> {
+    recipients
+        .iter()
+        .map(|recipient| (*recipient, airdrop(client, recipient, lamports, config)))
+        .collect()
+}
+
+/// Requests one part, retrying while the faucet refuses or the node is
+/// unreachable.
+fn request_part(
+    client: &RpcClient,
+    recipient: &Pubkey,
+    lamports: u64,
+    config: &AirdropConfig,
+) -> Result<Signature, crate::Error> {
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+        let error = match client.request_airdrop(recipient, lamports) {
+            Ok(signature) => return Ok(signature),
+            Err(error) => error,
+        };
+
+        let retry = is_rate_limited(&error) || classify(&error) == ErrorClass::Retry;
+        if !retry {
+            return Err(error.into());
+        }
+        if attempt >= config.max_attempts {
+            return Err(AirdropError::RateLimited { attempts: attempt }.into());
+        }
+
+        sleep(config.backoff(attempt));
+    }
+}
+
+/// Waits until the faucet transfer `signature` is confirmed at the
+/// client's commitment.
+fn confirm(
+    client: &RpcClient,
+    signature: &Signature,
+    timeout: Duration,
+) -> Result<(), crate::Error> {
+    let commitment = client.commitment();
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let statuses = client
+            .get_signature_statuses(&[*signature])
+            .map(|statuses| statuses.value);
+        if let Some(Some(status)) = status_response(statuses)? {
+            if let Some(error) = status.err {
+                return Err(AirdropError::Failed {
+                    signature: *signature,
+                    error,
+                }
+                .into());
+            }
+            if status.satisfies_commitment(commitment) {
+                return Ok(());
+            }
+        }
+
+        if Instant::now() >= deadline {
+            return Err(AirdropError::NotConfirmed(*signature).into());
+        }
+        sleep(POLL_INTERVAL);
+    }
+}
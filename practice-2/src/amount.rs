@@ -0,0 +1,192 @@
+//! Decimal amounts of SOL and tokens.
+//!
+//! Amounts are given as decimal strings like `1.5` and kept as an integer
+//! with a number of fractional digits, never as `f64`, so converting them
+//! into base units (lamports, or the smallest unit of a mint) is exact:
+//! an amount with more fractional digits than the mint has decimals, or
+//! one that does not fit into a `u64` of base units, is an error instead
+//! of being rounded or wrapped.
+
+use std::fmt;
+use std::str::FromStr;
+
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use spl_token_2022::extension::StateWithExtensions;
+use thiserror::Error;
+
+/// Decimals of SOL: a lamport is 10^-9 SOL.
+pub const SOL_DECIMALS: u8 = 9;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AmountError {
+    #[error("`{0}` is not a decimal amount")]
+    Invalid(String),
+    #[error("{0} is too large")]
+    TooLarge(String),
+    #[error("{amount} has more than {decimals} decimal places")]
+    TooPrecise { amount: Amount, decimals: u8 },
+    #[error("{amount} is too large for {decimals} decimals")]
+    Overflow { amount: Amount, decimals: u8 },
+    #[error("account {0} does not exist")]
+    NotFound(Pubkey),
+    #[error("{0} is not a token mint")]
+    InvalidMint(Pubkey),
+}
+
+/// A non-negative decimal amount, before the decimals it is counted in
+/// are known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Amount {
+    /// The digits without the decimal point.
+    digits: u128,
+    /// Digits after the decimal point, trailing zeros dropped.
+    scale: u8,
+}
+
+impl Amount {
+    /// The amount of `base_units` counted with `decimals`.
+    pub fn from_base_units(base_units: u64, decimals: u8) -> Self {
+        Amount {
+            digits: base_units.into(),
+            scale: decimals,
+        }
+        .normalized()
+    }
+
+    /// The amount in base units of `decimals`.
+    pub fn to_base_units(self, decimals: u8) -> Result<u64, AmountError> {
+        let shift = decimals
+            .checked_sub(self.scale)
+            .ok_or(AmountError::TooPrecise {
+                amount: self,
+                decimals,
+            })?;
+
+        10u128
+            .checked_pow(shift.into())
+            .and_then(|factor| self.digits.checked_mul(factor))
+            .and_then(|base_units| u64::try_from(base_units).ok())
+            .ok_or(AmountError::Overflow {
+                amount: self,
+                decimals,
+            })
+    }
+
+    /// The amount in lamports.
+    pub fn to_lamports(self) -> Result<u64, AmountError> {
+        self.to_base_units(SOL_DECIMALS)
+    }
+
+    fn normalized(mut self) -> Self {
+        while self.scale > 0 && self.digits.is_multiple_of(10) {
+            self.digits /= 10;
+            self.scale -= 1;
+        }
+
+        self
+    }
+}
+
+impl FromStr for Amount {
+    type Err = AmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || AmountError::Invalid(s.to_string());
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+        let is_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+        if whole.is_empty() && fraction.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+            return Err(invalid());
+        }
+
+        // Trailing zeros do not make an amount more precise.
+        let fraction = fraction.trim_end_matches('0');
+        let scale = u8::try_from(fraction.len()).map_err(|_| invalid())?;
+        let digits = format!("{whole}{fraction}");
+        let digits = match digits.trim_start_matches('0') {
+            "" => 0,
+            digits => digits
+                .parse()
+                .map_err(|_| AmountError::TooLarge(s.to_string()))?,
+        };
+
+        Ok(Amount { digits, scale })
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = format!(
+            "{:0>width$}",
+            self.digits,
+            width = usize::from(self.scale) + 1
+        );
+        let (whole, fraction) = digits.split_at(digits.len() - usize::from(self.scale));
+
+        if fraction.is_empty() {
+            write!(f, "{whole}")
+        } else {
+            write!(f, "{whole}.{fraction}")
+        }
+    }
+}
+
+/// Parses `amount` into base units of `decimals`.
+pub fn parse_amount(amount: &str, decimals: u8) -> Result<u64, AmountError> {
+    amount.parse::<Amount>()?.to_base_units(decimals)
+}
+
+/// Formats `base_units` of `decimals` with every significant digit.
+pub fn format_amount(base_units: u64, decimals: u8) -> String {
+    Amount::from_base_units(base_units, decimals).to_string()
+}
+
+/// Formats a change of base units of `decimals` with its sign, e.g.
+/// `+1.5` or `-0.000005`.
+pub fn format_delta(delta: i128, decimals: u8) -> String {
+    let sign = if delta < 0 { '-' } else { '+' };
+    let digits = Amount {
+        digits: delta.unsigned_abs(),
+        scale: decimals,
+    }
+    .normalized();
+
+    format!("{sign}{digits}")
+}
+
+/// Parses an amount of SOL into lamports.
+pub fn parse_sol(amount: &str) -> Result<u64, AmountError> {
+    parse_amount(amount, SOL_DECIMALS)
+}
+
+/// Formats lamports as SOL.
+pub fn format_sol(lamports: u64) -> String {
+    format_amount(lamports, SOL_DECIMALS)
+}
+
+/// Decimals of `mint`, read from the mint account of either token
+/// program.
+pub fn mint_decimals(client: &RpcClient, mint: &Pubkey) -> Result<u8, crate::Error> {
+    let account = client
+        .get_account_with_commitment(mint, client.commitment())?
+        .value
+        .ok_or(AmountError::NotFound(*mint))?;
+    if account.owner != spl_token::id() && account.owner != spl_token_2022::id() {
+        return Err(AmountError::InvalidMint(*mint).into());
+    }
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data)
+        .map_err(|_| AmountError::InvalidMint(*mint))?;
+
+    Ok(state.base.decimals)
+}
+
+/// `amount` of `mint` in its base units, with the decimals of the mint.
+pub fn token_amount(
+    client: &RpcClient,
+    mint: &Pubkey,
+    amount: Amount,
+) -> Result<u64, crate::Error> {
+    let decimals = mint_decimals(client, mint)?;
+
+    Ok(amount.to_base_units(decimals)?)
+}
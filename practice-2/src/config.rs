@@ -0,0 +1,223 @@
+//! Which cluster to talk to.
+//!
+//! Every setting is resolved on its own, from the first source that has it:
+//! 1. command line flags ([`ClusterArgs`]),
+//! 2. the `RPC_URL`, `WEBSOCKET_URL` and `COMMITMENT` variables,
+//! 3. the selected profile of the project `cluster.toml`,
+//! 4. the Solana CLI `config.yml`,
+//! 5. devnet with `confirmed` commitment.
+//!
+//! URLs can be given as the monikers `localnet`, `devnet`, `testnet` and
+//! `mainnet-beta` (or their first letters, as with `solana -u`).
+//!
+//! `cluster.toml` looks like:
+//!
+//! ```toml
+//! default = "ci"
+//!
+//! [profiles.ci]
+//! url = "localnet"
+//! commitment = "processed"
+//!
+//! [profiles.dev]
+//! url = "devnet"
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use clap::Args;
+use serde::Deserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use thiserror::Error;
+
+pub const RPC_URL_VAR: &str = "RPC_URL";
+pub const WEBSOCKET_URL_VAR: &str = "WEBSOCKET_URL";
+pub const COMMITMENT_VAR: &str = "COMMITMENT";
+
+/// Variable naming the `cluster.toml` profile to use.
+pub const PROFILE_VAR: &str = "CLUSTER_PROFILE";
+
+/// Variable with the path of the project profile file.
+pub const PROFILE_FILE_VAR: &str = "CLUSTER_CONFIG";
+
+/// Project profile file looked up in the current directory.
+pub const DEFAULT_PROFILE_FILE: &str = "cluster.toml";
+
+const DEFAULT_URL: &str = "https://api.devnet.solana.com";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("unknown commitment `{0}`, expected processed, confirmed or finalized")]
+    Commitment(String),
+    #[error("unknown message version `{0}`, expected legacy or v0")]
+    MessageVersion(String),
+    #[error("profile `{profile}` is not defined in {}", path.display())]
+    UnknownProfile { profile: String, path: PathBuf },
+    #[error("profile `{0}` was asked for, but there is no cluster.toml")]
+    NoProfileFile(String),
+    #[error("failed to read {}: {error}", path.display())]
+    Read {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    #[error("invalid {}: {error}", path.display())]
+    Toml {
+        path: PathBuf,
+        error: toml::de::Error,
+    },
+}
+
+/// Command line flags for the cluster, to `#[command(flatten)]` into a
+/// clap parser.
+#[derive(Debug, Clone, Default, Args)]
+pub struct ClusterArgs {
+    /// RPC URL or moniker: localnet, devnet, testnet, mainnet-beta
+    #[arg(long, short = 'u', global = true)]
+    pub url: Option<String>,
+    /// Websocket URL, derived from the RPC URL if not given
+    #[arg(long, global = true)]
+    pub ws_url: Option<String>,
+    /// Commitment: processed, confirmed or finalized
+    #[arg(long, global = true)]
+    pub commitment: Option<String>,
+    /// Profile from cluster.toml
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProfileFile {
+    default: Option<String>,
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Profile {
+    url: Option<String>,
+    ws_url: Option<String>,
+    commitment: Option<String>,
+}
+
+/// A resolved cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterConfig {
+    pub url: String,
+    pub websocket_url: String,
+    pub commitment: CommitmentConfig,
+}
+
+impl ClusterConfig {
+    /// Resolves the cluster without command line flags.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::resolve(&ClusterArgs::default())
+    }
+
+    /// Resolves the cluster from `args` and the sources after them.
+    pub fn resolve(args: &ClusterArgs) -> Result<Self, ConfigError> {
+        dotenv::dotenv().ok();
+
+        let env = |var| std::env::var(var).ok();
+        let profile = project_profile(args.profile.clone().or_else(|| env(PROFILE_VAR)))?;
+        let cli_config = solana_cli_config::CONFIG_FILE
+            .as_ref()
+            .filter(|path| Path::new(path).exists())
+            .and_then(|path| solana_cli_config::Config::load(path).ok());
+
+        let url = args
+            .url
+            .clone()
+            .or_else(|| env(RPC_URL_VAR))
+            .or(profile.url)
+            .or_else(|| cli_config.as_ref().map(|config| config.json_rpc_url.clone()))
+            .map(|url| normalize_url(&url))
+            .unwrap_or_else(|| DEFAULT_URL.to_string());
+
+        // The CLI config websocket URL belongs to its RPC URL, so it is only
+        // used when that RPC URL was picked.
+        let cli_websocket_url = cli_config
+            .as_ref()
+            .filter(|config| normalize_url(&config.json_rpc_url) == url)
+            .map(|config| config.websocket_url.clone())
+            .filter(|websocket_url| !websocket_url.is_empty());
+        let websocket_url = args
+            .ws_url
+            .clone()
+            .or_else(|| env(WEBSOCKET_URL_VAR))
+            .or(profile.ws_url)
+            .or(cli_websocket_url)
+            .unwrap_or_else(|| solana_cli_config::Config::compute_websocket_url(&url));
+
+        let commitment = args
+            .commitment
+            .clone()
+            .or_else(|| env(COMMITMENT_VAR))
+            .or(profile.commitment)
+            .or_else(|| cli_config.map(|config| config.commitment))
+            .map(|commitment| {
+                CommitmentConfig::from_str(&commitment)
+                    .map_err(|_| ConfigError::Commitment(commitment))
+            })
+            .transpose()?
+            .unwrap_or_else(CommitmentConfig::confirmed);
+
+        Ok(ClusterConfig {
+            url,
+            websocket_url,
+            commitment,
+        })
+    }
+
+    /// An RPC client for the cluster with its commitment.
+    pub fn rpc_client(&self) -> RpcClient {
+        RpcClient::new_with_commitment(self.url.clone(), self.commitment)
+    }
+}
+
+/// Expands a cluster moniker to its RPC URL; anything else is returned
+/// as is.
+pub fn normalize_url(url: &str) -> String {
+    match url {
+        "localnet" | "localhost" | "l" => "http://127.0.0.1:8899",
+        "devnet" | "d" => "https://api.devnet.solana.com",
+        "testnet" | "t" => "https://api.testnet.solana.com",
+        "mainnet-beta" | "mainnet" | "m" => "https://api.mainnet-beta.solana.com",
+        url => url,
+    }
+    .to_string()
+}
+
+/// Reads the selected profile of the project profile file. Without a file
+/// or a selected profile, every setting is left unset.
+fn project_profile(requested: Option<String>) -> Result<Profile, ConfigError> {
+    let path = std::env::var_os(PROFILE_FILE_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_PROFILE_FILE));
+
+    if !path.exists() {
+        return match requested {
+            Some(profile) => Err(ConfigError::NoProfileFile(profile)),
+            None => Ok(Profile::default()),
+        };
+    }
+
+    let contents = std::fs::read_to_string(&path).map_err(|error| ConfigError::Read {
+        path: path.clone(),
+        error,
+    })?;
+    let mut file: ProfileFile = toml::from_str(&contents).map_err(|error| ConfigError::Toml {
+        path: path.clone(),
+        error,
+    })?;
+
+    match requested.or(file.default) {
+        Some(profile) => file
+            .profiles
+            .remove(&profile)
+            .ok_or(ConfigError::UnknownProfile { profile, path }),
+        None => Ok(Profile::default()),
+    }
+}
@@ -0,0 +1,577 @@
+//! Cost estimates of transactions before they are sent.
+//!
+//! [`estimate_cost`] reads a built transaction and adds up what it costs:
+//!
+//! - the signature fee, what `getFeeForMessage` charges less the priority
+//!   fee, or [`LAMPORTS_PER_SIGNATURE`] per required signature when the
+//!   node does not know the blockhash of the message;
+//! - the priority fee, the compute unit price times the compute unit limit
+//!   set by its `ComputeBudget` instructions;
+//! - the rent deposits of the accounts it creates: mints and other
+//!   `create_account`s of the System program, associated token accounts,
+//!   Metaplex metadata accounts and the offers and vaults of the escrow
+//!   programs ([`crate::escrow`]). Accounts that already exist are left
+//!   out, since the idempotent instructions skip them;
+//! - fees charged by programs, which is [`METADATA_CREATE_FEE`].
+//!
+//! With [`crate::TransactionConfig::estimate`] set, the helpers build the
+//! transaction and return its [`CostEstimate`] instead of sending it.
+//! Nothing else, like lamports moved by a transfer, is counted.
+
+use std::fmt;
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::Response;
+use solana_program::instruction::CompiledInstruction;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::system_instruction::SystemInstruction;
+use solana_program::{system_program, sysvar};
+use solana_sdk::account::{from_account, Account};
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::borsh1::try_from_slice_unchecked;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::nonce::State;
+use solana_sdk::transaction::VersionedTransaction;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use thiserror::Error;
+
+use crate::amount::format_sol;
+use crate::escrow::{anchor_discriminator, EscrowProgram};
+use crate::fees::MAX_COMPUTE_UNIT_LIMIT;
+use crate::lookup_table::account_keys;
+
+/// Variable that turns on cost estimates when set to anything but `0` or
+/// `false`.
+pub const ESTIMATE_VAR: &str = "ESTIMATE";
+
+/// Base fee of a signature, counted when the cluster cannot price a
+/// message.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Compute units an instruction may use without a compute unit limit.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// Size the Token Metadata program allocates for a metadata account.
+pub const METADATA_LEN: usize = 679;
+
+/// Fee the Token Metadata program takes on top of the rent when it creates
+/// a metadata account.
+pub const METADATA_CREATE_FEE: u64 = 10_000_000;
+
+/// Instruction tag of `CreateMetadataAccountV3`.
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CostError {
+    #[error("the message has no fee payer")]
+    NoFeePayer,
+    #[error("instruction #{0} names a program outside the account keys")]
+    InvalidProgramIndex(usize),
+    #[error("expected {expected} accounts for the estimate, got {actual}")]
+    AccountCount { expected: usize, actual: usize },
+}
+
+/// What kind of account a transaction creates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountKind {
+    Mint,
+    TokenAccount,
+    Metadata,
+    NonceAccount,
+    Offer,
+    Vault,
+    /// Any other account created with the System program.
+    Account,
+}
+
+impl AccountKind {
+    /// Short name, used in the JSON reports.
+    pub fn name(self) -> &'static str {
+        match self {
+            AccountKind::Mint => "mint",
+            AccountKind::TokenAccount => "token_account",
+            AccountKind::Metadata => "metadata",
+            AccountKind::NonceAccount => "nonce_account",
+            AccountKind::Offer => "offer",
+            AccountKind::Vault => "vault",
+            AccountKind::Account => "account",
+        }
+    }
+}
+
+/// Lamports a transaction puts into an account it creates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RentDeposit {
+    pub address: Pubkey,
+    pub kind: AccountKind,
+    /// Bytes of data.
+    pub space: usize,
+    pub lamports: u64,
+    /// Who pays the deposit, usually the fee payer.
+    pub funder: Pubkey,
+}
+
+/// What a transaction costs, in lamports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostEstimate {
+    pub fee_payer: Pubkey,
+    /// Lamports the fee payer holds now.
+    pub fee_payer_balance: u64,
+    pub signatures: u64,
+    pub signature_fee: u64,
+    pub priority_fee: u64,
+    pub rent: Vec<RentDeposit>,
+    /// Fees taken by programs, like [`METADATA_CREATE_FEE`].
+    pub program_fees: u64,
+}
+
+impl CostEstimate {
+    /// Signature, priority and program fees, which the fee payer pays.
+    pub fn fees(&self) -> u64 {
+        self.signature_fee + self.priority_fee + self.program_fees
+    }
+
+    /// Everything the transaction costs, whoever pays it.
+    pub fn total(&self) -> u64 {
+        self.fees()
+            + self
+                .rent
+                .iter()
+                .map(|deposit| deposit.lamports)
+                .sum::<u64>()
+    }
+
+    /// What the fee payer pays: the fees and the deposits it funds.
+    pub fn fee_payer_cost(&self) -> u64 {
+        let deposits = self
+            .rent
+            .iter()
+            .filter(|deposit| deposit.funder == self.fee_payer)
+            .map(|deposit| deposit.lamports);
+
+        self.fees() + deposits.sum::<u64>()
+    }
+
+    /// Whether the fee payer holds enough to pay [`CostEstimate::fee_payer_cost`].
+    pub fn is_covered(&self) -> bool {
+        self.fee_payer_balance >= self.fee_payer_cost()
+    }
+}
+
+impl fmt::Display for CostEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "estimated cost: {} SOL ({} lamports)",
+            format_sol(self.total()),
+            self.total()
+        )?;
+        write!(
+            f,
+            "\n  signature fee: {} lamports for {} signatures",
+            self.signature_fee, self.signatures
+        )?;
+        write!(f, "\n  priority fee: {} lamports", self.priority_fee)?;
+        for deposit in &self.rent {
+            write!(
+                f,
+                "\n  rent: {} lamports for {} {} ({} bytes)",
+                deposit.lamports,
+                deposit.kind.name().replace('_', " "),
+                deposit.address,
+                deposit.space,
+            )?;
+            if deposit.funder != self.fee_payer {
+                write!(f, ", paid by {}", deposit.funder)?;
+            }
+        }
+        if self.program_fees > 0 {
+            write!(f, "\n  program fees: {} lamports", self.program_fees)?;
+        }
+
+        write!(
+            f,
+            "\nFee payer {} holds {} SOL",
+            self.fee_payer,
+            format_sol(self.fee_payer_balance)
+        )?;
+        if !self.is_covered() {
+            write!(
+                f,
+                "\n⚠️ The fee payer cannot cover the {} SOL it pays",
+                format_sol(self.fee_payer_cost())
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Estimates what `transaction` costs, resolving the accounts it loads
+/// from `lookup_tables`.
+pub fn estimate_cost(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<CostEstimate, crate::Error> {
+    let plan = CostPlan::new(&transaction.message, lookup_tables)?;
+    let accounts = client
+        .get_multiple_accounts_with_commitment(&plan.addresses(), client.commitment())?
+        .value;
+    let fee = client
+        .send::<Response<Option<u64>>>(
+            RpcRequest::GetFeeForMessage,
+            fee_for_message_params(&transaction.message, client.commitment()),
+        )?
+        .value;
+
+    Ok(plan.estimate(accounts, fee)?)
+}
+
+/// Parameters of the `getFeeForMessage` request for `message`, whose
+/// answer [`CostPlan::estimate`] takes. Unlike
+/// [`RpcClient::get_fee_for_message`], the request takes v0 messages too.
+pub fn fee_for_message_params(
+    message: &VersionedMessage,
+    commitment: CommitmentConfig,
+) -> serde_json::Value {
+    serde_json::json!([BASE64_STANDARD.encode(message.serialize()), commitment])
+}
+
+/// How big an account about to be created is.
+#[derive(Debug, Clone)]
+enum Size {
+    /// `create_account`, which names the lamports too.
+    Funded {
+        space: usize,
+        lamports: u64,
+    },
+    Space(usize),
+    /// Associated token account, sized after the extensions of the mint.
+    TokenAccount {
+        mint: Pubkey,
+        token_program: Pubkey,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Creation {
+    address: Pubkey,
+    kind: AccountKind,
+    funder: Pubkey,
+    size: Size,
+}
+
+/// What [`estimate_cost`] reads from a message, before fetching the
+/// accounts it needs. Shared by the blocking and the
+/// [`crate::nonblocking`] version; callers with other ways to fetch
+/// accounts can use it too.
+pub struct CostPlan {
+    fee_payer: Pubkey,
+    signatures: u64,
+    priority_fee: u64,
+    program_fees: u64,
+    creations: Vec<Creation>,
+}
+
+impl CostPlan {
+    pub fn new(
+        message: &VersionedMessage,
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<Self, CostError> {
+        let static_keys = message.static_account_keys();
+        let keys = account_keys(message, lookup_tables).unwrap_or_else(|| static_keys.to_vec());
+
+        let mut plan = CostPlan {
+            fee_payer: *static_keys.first().ok_or(CostError::NoFeePayer)?,
+            signatures: u64::from(message.header().num_required_signatures),
+            priority_fee: 0,
+            program_fees: 0,
+            creations: Vec::new(),
+        };
+
+        let mut price = 0u64;
+        let mut limit = None;
+        let mut instructions = 0u32;
+        for (index, ix) in message.instructions().iter().enumerate() {
+            let program = *keys
+                .get(usize::from(ix.program_id_index))
+                .ok_or(CostError::InvalidProgramIndex(index))?;
+            if program == compute_budget::id() {
+                match try_from_slice_unchecked(&ix.data) {
+                    Ok(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => limit = Some(units),
+                    Ok(ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports)) => {
+                        price = micro_lamports
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            instructions += 1;
+
+            let accounts: Option<Vec<Pubkey>> = ix
+                .accounts
+                .iter()
+                .map(|&index| keys.get(usize::from(index)).copied())
+                .collect();
+            if let Some(accounts) = accounts {
+                plan.add_creations(&program, ix, &accounts);
+            }
+        }
+
+        let limit = limit
+            .unwrap_or(instructions.saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT))
+            .min(MAX_COMPUTE_UNIT_LIMIT);
+        plan.priority_fee = (u128::from(price) * u128::from(limit)).div_ceil(1_000_000) as u64;
+
+        Ok(plan)
+    }
+
+    /// Records the accounts the instruction `ix` of `program` creates.
+    fn add_creations(&mut self, program: &Pubkey, ix: &CompiledInstruction, accounts: &[Pubkey]) {
+        let account = |index: usize| accounts.get(index).copied();
+        let mut create = |address: Option<Pubkey>, kind, funder: Option<Pubkey>, size| {
+            if let (Some(address), Some(funder)) = (address, funder) {
+                self.creations.push(Creation {
+                    address,
+                    kind,
+                    funder,
+                    size,
+                });
+            }
+        };
+        let token_account = |address: usize, mint: usize, token_program: usize| {
+            (
+                account(address),
+                account(mint)
+                    .zip(account(token_program))
+                    .map(|(mint, token_program)| Size::TokenAccount {
+                        mint,
+                        token_program,
+                    }),
+            )
+        };
+
+        if *program == system_program::id() {
+            let (lamports, space, owner) = match bincode::deserialize(&ix.data) {
+                Ok(SystemInstruction::CreateAccount {
+                    lamports,
+                    space,
+                    owner,
+                }) => (lamports, space, owner),
+                Ok(SystemInstruction::CreateAccountWithSeed {
+                    lamports,
+                    space,
+                    owner,
+                    ..
+                }) => (lamports, space, owner),
+                _ => return,
+            };
+            let space = space as usize;
+            let kind = if is_token_program(&owner) {
+                // Only a guess for Token-2022 accounts with extensions.
+                if space == spl_token::state::Account::LEN {
+                    AccountKind::TokenAccount
+                } else {
+                    AccountKind::Mint
+                }
+            } else if owner == system_program::id() && space == State::size() {
+                AccountKind::NonceAccount
+            } else {
+                AccountKind::Account
+            };
+
+            create(
+                account(1),
+                kind,
+                account(0),
+                Size::Funded { space, lamports },
+            );
+        } else if *program == spl_associated_token_account::id() {
+            // `Create` (empty data or 0) and `CreateIdempotent` (1).
+            if ix.data.first().copied().unwrap_or(0) <= 1 {
+                let (address, size) = token_account(1, 3, 5);
+                if let Some(size) = size {
+                    create(address, AccountKind::TokenAccount, account(0), size);
+                }
+            }
+        } else if *program == mpl_token_metadata::ID {
+            if ix.data.first() == Some(&CREATE_METADATA_ACCOUNT_V3) {
+                create(
+                    account(0),
+                    AccountKind::Metadata,
+                    account(3),
+                    Size::Space(METADATA_LEN),
+                );
+                self.program_fees += METADATA_CREATE_FEE;
+            }
+        } else if let Some(escrow) = EscrowProgram::from_id(program) {
+            let signer = account(0);
+            if ix
+                .data
+                .starts_with(&anchor_discriminator("global:make_offer"))
+            {
+                create(
+                    account(4),
+                    AccountKind::Offer,
+                    signer,
+                    Size::Space(escrow.offer_len()),
+                );
+                if escrow == EscrowProgram::Escrow {
+                    let (vault, size) = token_account(5, 1, 7);
+                    if let Some(size) = size {
+                        create(vault, AccountKind::Vault, signer, size);
+                    }
+                }
+            } else if ix
+                .data
+                .starts_with(&anchor_discriminator("global:take_offer"))
+            {
+                // The token accounts of the taker for mint A and of the
+                // maker for mint B are created if needed.
+                let maker_token_account_b = match escrow {
+                    EscrowProgram::Escrow => 6,
+                    EscrowProgram::EscrowApprove => 7,
+                };
+                for (address, mint) in [(4, 2), (maker_token_account_b, 3)] {
+                    let (address, size) = token_account(address, mint, 10);
+                    if let Some(size) = size {
+                        create(address, AccountKind::TokenAccount, signer, size);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Accounts to fetch for [`CostPlan::estimate`]: the rent sysvar, the
+    /// fee payer, the accounts to be created and the mints of the token
+    /// accounts among them.
+    pub fn addresses(&self) -> Vec<Pubkey> {
+        let mut addresses = vec![sysvar::rent::id(), self.fee_payer];
+        addresses.extend(self.creations.iter().map(|creation| creation.address));
+        addresses.extend(
+            self.creations
+                .iter()
+                .filter_map(|creation| match creation.size {
+                    Size::TokenAccount { mint, .. } => Some(mint),
+                    _ => None,
+                }),
+        );
+
+        addresses
+    }
+
+    /// The estimate, with `accounts` fetched from [`CostPlan::addresses`]
+    /// and `fee`, what `getFeeForMessage` answers for the message: `None`
+    /// when the node does not know its blockhash, e.g. a durable nonce,
+    /// counts [`LAMPORTS_PER_SIGNATURE`] per signature.
+    pub fn estimate(
+        self,
+        accounts: Vec<Option<Account>>,
+        fee: Option<u64>,
+    ) -> Result<CostEstimate, CostError> {
+        let expected = self.addresses().len();
+        if accounts.len() != expected {
+            return Err(CostError::AccountCount {
+                expected,
+                actual: accounts.len(),
+            });
+        }
+        let [rent, fee_payer, rest @ ..] = accounts.as_slice() else {
+            return Err(CostError::AccountCount {
+                expected,
+                actual: accounts.len(),
+            });
+        };
+        let (existing, mints) = rest.split_at(self.creations.len());
+        let rent: Rent = rent.as_ref().and_then(from_account).unwrap_or_default();
+        let fee_payer_balance = fee_payer.as_ref().map_or(0, |account| account.lamports);
+        let mints: Vec<_> = self
+            .creations
+            .iter()
+            .filter(|creation| matches!(creation.size, Size::TokenAccount { .. }))
+            .zip(mints)
+            .map(|(creation, mint)| (creation.address, mint.as_ref()))
+            .collect();
+
+        let mut deposits: Vec<RentDeposit> = Vec::new();
+        for (creation, existing) in self.creations.into_iter().zip(existing) {
+            let exists = existing
+                .as_ref()
+                .is_some_and(|account| account.lamports > 0);
+            if exists
+                || deposits
+                    .iter()
+                    .any(|deposit| deposit.address == creation.address)
+            {
+                continue;
+            }
+
+            let (space, lamports) = match creation.size {
+                Size::Funded { space, lamports } => (space, lamports),
+                Size::Space(space) => (space, rent.minimum_balance(space)),
+                Size::TokenAccount { token_program, .. } => {
+                    let mint = mints
+                        .iter()
+                        .find(|(address, _)| *address == creation.address)
+                        .and_then(|(_, mint)| *mint);
+                    let space = token_account_len(&token_program, mint);
+                    (space, rent.minimum_balance(space))
+                }
+            };
+            deposits.push(RentDeposit {
+                address: creation.address,
+                kind: creation.kind,
+                space,
+                lamports,
+                funder: creation.funder,
+            });
+        }
+
+        let signature_fee = match fee {
+            Some(fee) => fee.saturating_sub(self.priority_fee),
+            None => self.signatures * LAMPORTS_PER_SIGNATURE,
+        };
+
+        Ok(CostEstimate {
+            fee_payer: self.fee_payer,
+            fee_payer_balance,
+            signatures: self.signatures,
+            signature_fee,
+            priority_fee: self.priority_fee,
+            rent: deposits,
+            program_fees: self.program_fees,
+        })
+    }
+}
+
+fn is_token_program(program: &Pubkey) -> bool {
+    *program == spl_token::id() || *program == spl_token_2022::id()
+}
+
+/// Size of an associated token account of `token_program` for `mint`: a
+/// Token-2022 account has the immutable owner extension and the ones the
+/// extensions of the mint require.
+fn token_account_len(token_program: &Pubkey, mint: Option<&Account>) -> usize {
+    if *token_program != spl_token_2022::id() {
+        return spl_token::state::Account::LEN;
+    }
+
+    let mut extensions = mint
+        .and_then(|mint| {
+            StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint.data).ok()
+        })
+        .and_then(|mint| mint.get_extension_types().ok())
+        .map(|types| ExtensionType::get_required_init_account_extensions(&types))
+        .unwrap_or_default();
+    extensions.push(ExtensionType::ImmutableOwner);
+
+    ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&extensions)
+        .unwrap_or(spl_token::state::Account::LEN)
+}
@@ -0,0 +1,722 @@
+//! Readable form of the instructions of a transaction, to check what it
+//! does before co-signing or sending it.
+//!
+//! Instruction data of the System, Compute Budget, Associated Token
+//! Account, SPL Token, Token-2022 and memo programs is decoded, as are
+//! Metaplex `CreateMetadataAccountV3` and the `make_offer` and
+//! `take_offer` instructions of the escrow programs, told apart by their
+//! Anchor discriminators. Other instructions are shown with their raw
+//! data.
+
+use std::fmt;
+use std::str::FromStr;
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use borsh::BorshDeserialize;
+use mpl_token_metadata::instructions::CreateMetadataAccountV3InstructionArgs;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_program::program_option::COption;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::SystemInstruction;
+use solana_sdk::bs58;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::UiTransactionEncoding;
+use spl_token_2022::instruction::TokenInstruction;
+use thiserror::Error;
+
+use crate::amount::{format_amount, format_sol};
+use crate::escrow::{anchor_discriminator, EscrowProgram};
+use crate::lookup_table::{account_keys, fetch_lookup_tables};
+use crate::programs::{program_name, KnownProgram};
+
+/// First byte of `CreateMetadataAccountV3` data.
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error("expected a signature or a base64 or base58 encoded transaction")]
+    InvalidInput,
+    #[error("transaction {0} could not be decoded")]
+    Undecodable(Signature),
+}
+
+/// What to decode: a transaction on chain or one in wire format.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionSource {
+    Signature(Signature),
+    Wire(VersionedTransaction),
+}
+
+impl FromStr for TransactionSource {
+    type Err = DecodeError;
+
+    /// A signature, or a transaction in base64 or base58 wire format, as
+    /// written by `--sign-only` and shown by explorers.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        if let Ok(signature) = input.parse() {
+            return Ok(TransactionSource::Signature(signature));
+        }
+
+        BASE64_STANDARD
+            .decode(input)
+            .ok()
+            .or_else(|| bs58::decode(input).into_vec().ok())
+            .and_then(|bytes| bincode::deserialize::<VersionedTransaction>(&bytes).ok())
+            .filter(|transaction| transaction.sanitize().is_ok())
+            .map(TransactionSource::Wire)
+            .ok_or(DecodeError::InvalidInput)
+    }
+}
+
+/// An account passed to an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionAccount {
+    /// What the program uses the account for, if known.
+    pub role: Option<&'static str>,
+    /// `None` if loaded from a lookup table that was not fetched.
+    pub address: Option<Pubkey>,
+}
+
+/// An instruction in readable form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub program_id: Pubkey,
+    pub program: Option<KnownProgram>,
+    /// Name of the instruction as its program calls it; `None` for memos
+    /// and undecoded data.
+    pub name: Option<String>,
+    /// Whether the data was understood; if not, [`data`](Self::data) is
+    /// all there is.
+    pub decoded: bool,
+    pub args: Vec<(&'static str, String)>,
+    pub accounts: Vec<InstructionAccount>,
+    pub data: Vec<u8>,
+}
+
+impl DecodedInstruction {
+    /// Decodes an instruction of `program_id` with `accounts` in the order
+    /// the instruction lists them.
+    pub fn new(program_id: Pubkey, accounts: Vec<Option<Pubkey>>, data: &[u8]) -> Self {
+        let program = KnownProgram::from_id(&program_id);
+        let (decoded, name, args, roles) =
+            match program.and_then(|program| decode_data(program, data)) {
+                Some(Decoded { name, args, roles }) => (true, name, args, roles),
+                None => (false, None, Vec::new(), &[][..]),
+            };
+        let accounts = accounts
+            .into_iter()
+            .enumerate()
+            .map(|(index, address)| InstructionAccount {
+                role: roles.get(index).copied(),
+                address,
+            })
+            .collect();
+
+        DecodedInstruction {
+            program_id,
+            program,
+            decoded,
+            name,
+            args,
+            accounts,
+            data: data.to_vec(),
+        }
+    }
+}
+
+impl fmt::Display for DecodedInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", program_name(&self.program_id))?;
+        match (&self.name, self.decoded) {
+            (Some(name), _) => write!(f, ": {name}")?,
+            (None, false) => write!(f, ": unknown instruction")?,
+            (None, true) => {}
+        }
+
+        for (name, value) in &self.args {
+            write!(f, "\n    {name}: {value}")?;
+        }
+        for (index, account) in self.accounts.iter().enumerate() {
+            let role = account
+                .role
+                .map(str::to_string)
+                .unwrap_or(format!("account {index}"));
+            match account.address {
+                Some(address) => write!(f, "\n    {role}: {address}")?,
+                None => write!(f, "\n    {role}: (from a lookup table)")?,
+            }
+        }
+        if !self.decoded && !self.data.is_empty() {
+            write!(f, "\n    data: {}", bs58::encode(&self.data).into_string())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Decodes the top level instructions of `message`. `keys` are the
+/// accounts of the message as by [`account_keys`]; accounts past them are
+/// left unresolved.
+pub fn decode_instructions(message: &VersionedMessage, keys: &[Pubkey]) -> Vec<DecodedInstruction> {
+    message
+        .instructions()
+        .iter()
+        .map(|instruction| {
+            let key = |index: u8| keys.get(usize::from(index)).copied();
+            DecodedInstruction::new(
+                key(instruction.program_id_index).unwrap_or_default(),
+                instruction
+                    .accounts
+                    .iter()
+                    .map(|index| key(*index))
+                    .collect(),
+                &instruction.data,
+            )
+        })
+        .collect()
+}
+
+/// Fetches the transaction of `source` if it is a signature, and the
+/// accounts its instructions refer to, lookup tables included.
+pub fn load_transaction(
+    client: &RpcClient,
+    source: &TransactionSource,
+) -> Result<(VersionedTransaction, Vec<Pubkey>), crate::Error> {
+    match source {
+        TransactionSource::Signature(signature) => {
+            // `getTransaction` does not serve `processed` transactions.
+            let commitment = if client.commitment() == CommitmentConfig::processed() {
+                CommitmentConfig::confirmed()
+            } else {
+                client.commitment()
+            };
+            let confirmed = client.get_transaction_with_config(
+                signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(commitment),
+                    max_supported_transaction_version: Some(0),
+                },
+            )?;
+            let transaction = confirmed
+                .transaction
+                .transaction
+                .decode()
+                .ok_or(DecodeError::Undecodable(*signature))?;
+
+            let mut keys = transaction.message.static_account_keys().to_vec();
+            if let Some(OptionSerializer::Some(loaded)) =
+                confirmed.transaction.meta.map(|meta| meta.loaded_addresses)
+            {
+                keys.extend(
+                    loaded
+                        .writable
+                        .iter()
+                        .chain(&loaded.readonly)
+                        .filter_map(|key| key.parse::<Pubkey>().ok()),
+                );
+            }
+
+            Ok((transaction, keys))
+        }
+        TransactionSource::Wire(transaction) => {
+            let tables: Vec<_> = transaction
+                .message
+                .address_table_lookups()
+                .unwrap_or_default()
+                .iter()
+                .map(|lookup| lookup.account_key)
+                .collect();
+            let keys = account_keys(&transaction.message, &fetch_lookup_tables(client, &tables)?)
+                .unwrap_or_else(|| transaction.message.static_account_keys().to_vec());
+
+            Ok((transaction.clone(), keys))
+        }
+    }
+}
+
+/// Decoded data: the instruction name, its arguments and the roles of
+/// its accounts in order.
+struct Decoded {
+    name: Option<String>,
+    args: Vec<(&'static str, String)>,
+    roles: &'static [&'static str],
+}
+
+impl Decoded {
+    fn new(
+        name: &str,
+        args: Vec<(&'static str, String)>,
+        roles: &'static [&'static str],
+    ) -> Option<Self> {
+        Some(Decoded {
+            name: Some(name.to_string()),
+            args,
+            roles,
+        })
+    }
+}
+
+fn decode_data(program: KnownProgram, data: &[u8]) -> Option<Decoded> {
+    match program {
+        KnownProgram::System => decode_system(bincode::deserialize(data).ok()?),
+        KnownProgram::ComputeBudget => decode_compute_budget(data),
+        KnownProgram::AssociatedToken => decode_associated_token(data),
+        KnownProgram::Token | KnownProgram::Token2022 => {
+            decode_token(TokenInstruction::unpack(data).ok()?)
+        }
+        KnownProgram::Memo | KnownProgram::MemoV1 => Some(Decoded {
+            name: None,
+            args: vec![("text", String::from_utf8_lossy(data).into_owned())],
+            roles: &[],
+        }),
+        KnownProgram::TokenMetadata => decode_metadata(data),
+        KnownProgram::Escrow => decode_escrow(EscrowProgram::Escrow, data),
+        KnownProgram::EscrowApprove => decode_escrow(EscrowProgram::EscrowApprove, data),
+        KnownProgram::AddressLookupTable => None,
+    }
+}
+
+fn decode_system(instruction: SystemInstruction) -> Option<Decoded> {
+    let sol = |lamports| format!("{} SOL", format_sol(lamports));
+
+    match instruction {
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => Decoded::new(
+            "CreateAccount",
+            vec![
+                ("lamports", sol(lamports)),
+                ("space", space.to_string()),
+                ("owner", program_name(&owner)),
+            ],
+            &["funding account", "new account"],
+        ),
+        SystemInstruction::Assign { owner } => Decoded::new(
+            "Assign",
+            vec![("owner", program_name(&owner))],
+            &["account"],
+        ),
+        SystemInstruction::Transfer { lamports } => Decoded::new(
+            "Transfer",
+            vec![("lamports", sol(lamports))],
+            &["from", "to"],
+        ),
+        SystemInstruction::CreateAccountWithSeed {
+            base,
+            seed,
+            lamports,
+            space,
+            owner,
+        } => Decoded::new(
+            "CreateAccountWithSeed",
+            vec![
+                ("base", base.to_string()),
+                ("seed", seed),
+                ("lamports", sol(lamports)),
+                ("space", space.to_string()),
+                ("owner", program_name(&owner)),
+            ],
+            &["funding account", "new account", "base"],
+        ),
+        SystemInstruction::AdvanceNonceAccount => Decoded::new(
+            "AdvanceNonceAccount",
+            Vec::new(),
+            &[
+                "nonce account",
+                "recent blockhashes sysvar",
+                "nonce authority",
+            ],
+        ),
+        SystemInstruction::WithdrawNonceAccount(lamports) => Decoded::new(
+            "WithdrawNonceAccount",
+            vec![("lamports", sol(lamports))],
+            &[
+                "nonce account",
+                "recipient",
+                "recent blockhashes sysvar",
+                "rent sysvar",
+                "nonce authority",
+            ],
+        ),
+        SystemInstruction::InitializeNonceAccount(authority) => Decoded::new(
+            "InitializeNonceAccount",
+            vec![("authority", authority.to_string())],
+            &["nonce account", "recent blockhashes sysvar", "rent sysvar"],
+        ),
+        SystemInstruction::AuthorizeNonceAccount(authority) => Decoded::new(
+            "AuthorizeNonceAccount",
+            vec![("new authority", authority.to_string())],
+            &["nonce account", "nonce authority"],
+        ),
+        SystemInstruction::Allocate { space } => {
+            Decoded::new("Allocate", vec![("space", space.to_string())], &["account"])
+        }
+        SystemInstruction::AllocateWithSeed {
+            base,
+            seed,
+            space,
+            owner,
+        } => Decoded::new(
+            "AllocateWithSeed",
+            vec![
+                ("base", base.to_string()),
+                ("seed", seed),
+                ("space", space.to_string()),
+                ("owner", program_name(&owner)),
+            ],
+            &["account", "base"],
+        ),
+        SystemInstruction::AssignWithSeed { base, seed, owner } => Decoded::new(
+            "AssignWithSeed",
+            vec![
+                ("base", base.to_string()),
+                ("seed", seed),
+                ("owner", program_name(&owner)),
+            ],
+            &["account", "base"],
+        ),
+        SystemInstruction::TransferWithSeed {
+            lamports,
+            from_seed,
+            from_owner,
+        } => Decoded::new(
+            "TransferWithSeed",
+            vec![
+                ("lamports", sol(lamports)),
+                ("from seed", from_seed),
+                ("from owner", from_owner.to_string()),
+            ],
+            &["from", "base", "to"],
+        ),
+        SystemInstruction::UpgradeNonceAccount => {
+            Decoded::new("UpgradeNonceAccount", Vec::new(), &["nonce account"])
+        }
+    }
+}
+
+fn decode_compute_budget(data: &[u8]) -> Option<Decoded> {
+    let (tag, rest) = data.split_first()?;
+    let u32_arg = || Some(u32::from_le_bytes(rest.get(..4)?.try_into().ok()?).to_string());
+
+    match tag {
+        1 => Decoded::new("RequestHeapFrame", vec![("bytes", u32_arg()?)], &[]),
+        2 => Decoded::new("SetComputeUnitLimit", vec![("units", u32_arg()?)], &[]),
+        3 => {
+            let price = u64::from_le_bytes(rest.get(..8)?.try_into().ok()?);
+            Decoded::new(
+                "SetComputeUnitPrice",
+                vec![("micro-lamports per unit", price.to_string())],
+                &[],
+            )
+        }
+        4 => Decoded::new(
+            "SetLoadedAccountsDataSizeLimit",
+            vec![("bytes", u32_arg()?)],
+            &[],
+        ),
+        _ => None,
+    }
+}
+
+fn decode_associated_token(data: &[u8]) -> Option<Decoded> {
+    const CREATE: &[&str] = &[
+        "funding account",
+        "associated token account",
+        "wallet",
+        "mint",
+        "system program",
+        "token program",
+    ];
+
+    match data.first() {
+        None | Some(0) => Decoded::new("Create", Vec::new(), CREATE),
+        Some(1) => Decoded::new("CreateIdempotent", Vec::new(), CREATE),
+        Some(2) => Decoded::new(
+            "RecoverNested",
+            Vec::new(),
+            &[
+                "nested associated token account",
+                "nested mint",
+                "destination associated token account",
+                "owner associated token account",
+                "owner mint",
+                "wallet",
+                "token program",
+            ],
+        ),
+        _ => None,
+    }
+}
+
+// Plain `Transfer` is deprecated in favour of `TransferChecked`, but
+// transactions still use it.
+#[allow(deprecated)]
+fn decode_token(instruction: TokenInstruction) -> Option<Decoded> {
+    let pubkey = |key: COption<Pubkey>| match key {
+        COption::Some(key) => key.to_string(),
+        COption::None => "none".to_string(),
+    };
+    let checked = |amount, decimals| {
+        vec![
+            ("amount", format_amount(amount, decimals)),
+            ("decimals", decimals.to_string()),
+        ]
+    };
+    let amount = |amount: u64| vec![("amount", format!("{amount} base units"))];
+
+    match instruction {
+        TokenInstruction::InitializeMint {
+            decimals,
+            mint_authority,
+            freeze_authority,
+        } => Decoded::new(
+            "InitializeMint",
+            vec![
+                ("decimals", decimals.to_string()),
+                ("mint authority", mint_authority.to_string()),
+                ("freeze authority", pubkey(freeze_authority)),
+            ],
+            &["mint", "rent sysvar"],
+        ),
+        TokenInstruction::InitializeMint2 {
+            decimals,
+            mint_authority,
+            freeze_authority,
+        } => Decoded::new(
+            "InitializeMint2",
+            vec![
+                ("decimals", decimals.to_string()),
+                ("mint authority", mint_authority.to_string()),
+                ("freeze authority", pubkey(freeze_authority)),
+            ],
+            &["mint"],
+        ),
+        TokenInstruction::InitializeAccount => Decoded::new(
+            "InitializeAccount",
+            Vec::new(),
+            &["account", "mint", "owner", "rent sysvar"],
+        ),
+        TokenInstruction::InitializeAccount2 { owner } => Decoded::new(
+            "InitializeAccount2",
+            vec![("owner", owner.to_string())],
+            &["account", "mint", "rent sysvar"],
+        ),
+        TokenInstruction::InitializeAccount3 { owner } => Decoded::new(
+            "InitializeAccount3",
+            vec![("owner", owner.to_string())],
+            &["account", "mint"],
+        ),
+        TokenInstruction::Transfer { amount: value } => Decoded::new(
+            "Transfer",
+            amount(value),
+            &["source", "destination", "authority"],
+        ),
+        TokenInstruction::TransferChecked { amount, decimals } => Decoded::new(
+            "TransferChecked",
+            checked(amount, decimals),
+            &["source", "mint", "destination", "authority"],
+        ),
+        TokenInstruction::Approve { amount: value } => {
+            Decoded::new("Approve", amount(value), &["source", "delegate", "owner"])
+        }
+        TokenInstruction::ApproveChecked { amount, decimals } => Decoded::new(
+            "ApproveChecked",
+            checked(amount, decimals),
+            &["source", "mint", "delegate", "owner"],
+        ),
+        TokenInstruction::Revoke => Decoded::new("Revoke", Vec::new(), &["source", "owner"]),
+        TokenInstruction::SetAuthority {
+            authority_type,
+            new_authority,
+        } => Decoded::new(
+            "SetAuthority",
+            vec![
+                ("authority type", format!("{authority_type:?}")),
+                ("new authority", pubkey(new_authority)),
+            ],
+            &["account", "authority"],
+        ),
+        TokenInstruction::MintTo { amount: value } => Decoded::new(
+            "MintTo",
+            amount(value),
+            &["mint", "destination", "mint authority"],
+        ),
+        TokenInstruction::MintToChecked { amount, decimals } => Decoded::new(
+            "MintToChecked",
+            checked(amount, decimals),
+            &["mint", "destination", "mint authority"],
+        ),
+        TokenInstruction::Burn { amount: value } => {
+            Decoded::new("Burn", amount(value), &["account", "mint", "authority"])
+        }
+        TokenInstruction::BurnChecked { amount, decimals } => Decoded::new(
+            "BurnChecked",
+            checked(amount, decimals),
+            &["account", "mint", "authority"],
+        ),
+        TokenInstruction::CloseAccount => Decoded::new(
+            "CloseAccount",
+            Vec::new(),
+            &["account", "destination", "authority"],
+        ),
+        TokenInstruction::FreezeAccount => Decoded::new(
+            "FreezeAccount",
+            Vec::new(),
+            &["account", "mint", "freeze authority"],
+        ),
+        TokenInstruction::ThawAccount => Decoded::new(
+            "ThawAccount",
+            Vec::new(),
+            &["account", "mint", "freeze authority"],
+        ),
+        TokenInstruction::SyncNative => Decoded::new("SyncNative", Vec::new(), &["account"]),
+        instruction => {
+            // Extension instructions, named as the program names them.
+            let debug = format!("{instruction:?}");
+            let name = debug
+                .split(|c: char| !c.is_alphanumeric())
+                .next()
+                .unwrap_or_default();
+            let args = match debug[name.len()..].trim() {
+                "" => Vec::new(),
+                rest => vec![("args", rest.to_string())],
+            };
+            Decoded::new(name, args, &[])
+        }
+    }
+}
+
+fn decode_metadata(data: &[u8]) -> Option<Decoded> {
+    let (&CREATE_METADATA_ACCOUNT_V3, mut rest) = data.split_first()? else {
+        return None;
+    };
+    let args = CreateMetadataAccountV3InstructionArgs::deserialize(&mut rest).ok()?;
+
+    let mut decoded = vec![
+        ("name", args.data.name),
+        ("symbol", args.data.symbol),
+        ("uri", args.data.uri),
+        (
+            "seller fee basis points",
+            args.data.seller_fee_basis_points.to_string(),
+        ),
+        ("mutable", args.is_mutable.to_string()),
+    ];
+    for creator in args.data.creators.unwrap_or_default() {
+        decoded.push((
+            "creator",
+            format!(
+                "{} ({}% share{})",
+                creator.address,
+                creator.share,
+                if creator.verified { ", verified" } else { "" }
+            ),
+        ));
+    }
+    if let Some(collection) = args.data.collection {
+        decoded.push(("collection", collection.key.to_string()));
+    }
+
+    Decoded::new(
+        "CreateMetadataAccountV3",
+        decoded,
+        &[
+            "metadata",
+            "mint",
+            "mint authority",
+            "payer",
+            "update authority",
+            "system program",
+            "rent sysvar",
+        ],
+    )
+}
+
+fn decode_escrow(program: EscrowProgram, data: &[u8]) -> Option<Decoded> {
+    let u64_at = |offset: usize| {
+        let bytes = data.get(8 + offset..8 + offset + 8)?;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?).to_string())
+    };
+
+    if data.starts_with(&anchor_discriminator("global:make_offer")) {
+        let roles: &[&str] = match program {
+            EscrowProgram::Escrow => &[
+                "maker",
+                "token mint A",
+                "token mint B",
+                "maker token account A",
+                "offer",
+                "vault",
+                "associated token program",
+                "token program",
+                "system program",
+            ],
+            EscrowProgram::EscrowApprove => &[
+                "maker",
+                "token mint A",
+                "token mint B",
+                "maker token account A",
+                "offer",
+                "associated token program",
+                "token program",
+                "system program",
+            ],
+        };
+
+        return Decoded::new(
+            "make_offer",
+            vec![
+                ("id", u64_at(0)?),
+                ("token A offered amount", u64_at(8)?),
+                ("token B wanted amount", u64_at(16)?),
+            ],
+            roles,
+        );
+    }
+
+    if data.starts_with(&anchor_discriminator("global:take_offer")) {
+        let roles: &[&str] = match program {
+            EscrowProgram::Escrow => &[
+                "taker",
+                "maker",
+                "token mint A",
+                "token mint B",
+                "taker token account A",
+                "taker token account B",
+                "maker token account B",
+                "offer",
+                "vault",
+                "associated token program",
+                "token program",
+                "system program",
+            ],
+            EscrowProgram::EscrowApprove => &[
+                "taker",
+                "maker",
+                "token mint A",
+                "token mint B",
+                "taker token account A",
+                "taker token account B",
+                "maker token account A",
+                "maker token account B",
+                "offer",
+                "associated token program",
+                "token program",
+                "system program",
+            ],
+        };
+
+        return Decoded::new("take_offer", Vec::new(), roles);
+    }
+
+    None
+}
@@ -0,0 +1,273 @@
+use std::fmt;
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+use crate::history::{format_time, get_history, HistoryConfig};
+
+/// Start of every encrypted memo, followed by the envelope version.
+pub const ENVELOPE_PREFIX: &str = "enc:";
+
+/// Version of the envelope written by [`encrypt_memo`].
+pub const ENVELOPE_VERSION: u8 = 1;
+
+const KEY_DOMAIN: &[u8] = b"practice-2 encrypted memo x25519-xchacha20poly1305";
+const NONCE_LEN: usize = 24;
+const TAG_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MemoError {
+    #[error("{0} is not an ed25519 public key, e.g. a program derived address")]
+    NotEd25519(Pubkey),
+    #[error("not an encrypted memo")]
+    NotEnvelope,
+    #[error("unsupported encrypted memo version `{0}`")]
+    UnsupportedVersion(String),
+    #[error("malformed encrypted memo: {0}")]
+    Malformed(String),
+    #[error("the memo is not for this keypair or was altered")]
+    Decrypt,
+    #[error("encrypted memos need the secret key, {0} does not give it")]
+    NeedsKeypair(String),
+}
+
+/// Whether `memo` looks like an envelope written by [`encrypt_memo`], of any
+/// version.
+pub fn is_encrypted(memo: &str) -> bool {
+    memo.starts_with(ENVELOPE_PREFIX)
+}
+
+/// Encrypts `memo` so that only `recipient` can read it, with
+/// [`decrypt_memo`], and `sender`, with [`decrypt_sent_memo`].
+///
+/// Both ed25519 keys are converted to X25519, and the Diffie-Hellman shared
+/// secret, hashed together with both public keys, is the key of
+/// XChaCha20-Poly1305 with a random nonce. The envelope is
+/// `enc:1:<base64 of nonce and ciphertext>`; the version and both public
+/// keys are authenticated, so the memo cannot be replayed between other
+/// parties. The length of the text is not hidden.
+pub fn encrypt_memo(sender: &Keypair, recipient: &Pubkey, memo: &str) -> Result<String, MemoError> {
+    let key = shared_key(sender, &sender.pubkey(), recipient, recipient)?;
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let ciphertext = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: memo.as_bytes(),
+                aad: &associated_data(ENVELOPE_VERSION, &sender.pubkey(), recipient),
+            },
+        )
+        .expect("encryption of a memo cannot fail");
+
+    let mut body = nonce.to_vec();
+    body.extend(ciphertext);
+
+    Ok(format!(
+        "{ENVELOPE_PREFIX}{ENVELOPE_VERSION}:{}",
+        BASE64_STANDARD.encode(body)
+    ))
+}
+
+/// Decrypts a memo that `sender` wrote with [`encrypt_memo`] for the
+/// `recipient` keypair.
+pub fn decrypt_memo(
+    recipient: &Keypair,
+    sender: &Pubkey,
+    envelope: &str,
+) -> Result<String, MemoError> {
+    open_envelope(recipient, sender, &recipient.pubkey(), sender, envelope)
+}
+
+/// Decrypts a memo that the `sender` keypair wrote with [`encrypt_memo`]
+/// for `recipient`, to read back what was sent.
+pub fn decrypt_sent_memo(
+    sender: &Keypair,
+    recipient: &Pubkey,
+    envelope: &str,
+) -> Result<String, MemoError> {
+    open_envelope(sender, &sender.pubkey(), recipient, recipient, envelope)
+}
+
+/// Decrypts `envelope` between `sender` and `recipient` with `keypair`,
+/// one of the two, whose counterpart is `peer`.
+fn open_envelope(
+    keypair: &Keypair,
+    sender: &Pubkey,
+    recipient: &Pubkey,
+    peer: &Pubkey,
+    envelope: &str,
+) -> Result<String, MemoError> {
+    let (version, body) = envelope
+        .strip_prefix(ENVELOPE_PREFIX)
+        .and_then(|envelope| envelope.split_once(':'))
+        .ok_or(MemoError::NotEnvelope)?;
+    if version != ENVELOPE_VERSION.to_string() {
+        return Err(MemoError::UnsupportedVersion(version.to_string()));
+    }
+    let body = BASE64_STANDARD
+        .decode(body)
+        .map_err(|error| MemoError::Malformed(error.to_string()))?;
+    if body.len() < NONCE_LEN + TAG_LEN {
+        return Err(MemoError::Malformed(format!(
+            "{} bytes is too short",
+            body.len()
+        )));
+    }
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+
+    let key = shared_key(keypair, sender, recipient, peer)?;
+    let memo = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: &associated_data(ENVELOPE_VERSION, sender, recipient),
+            },
+        )
+        .map_err(|_| MemoError::Decrypt)?;
+
+    String::from_utf8(memo).map_err(|error| MemoError::Malformed(error.to_string()))
+}
+
+/// Key of the memo between `sender` and `recipient`, computed from the
+/// secret key of one of them and the public key of the other, `peer`.
+fn shared_key(
+    keypair: &Keypair,
+    sender: &Pubkey,
+    recipient: &Pubkey,
+    peer: &Pubkey,
+) -> Result<Zeroizing<[u8; 32]>, MemoError> {
+    let shared = Zeroizing::new((x25519_public(peer)? * x25519_secret(keypair)).to_bytes());
+    // An all zero secret means a peer key of small order: everyone knows it.
+    if shared.iter().all(|byte| *byte == 0) {
+        return Err(MemoError::NotEd25519(*peer));
+    }
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(
+        &Sha256::new()
+            .chain_update(KEY_DOMAIN)
+            .chain_update(shared.as_ref())
+            .chain_update(sender)
+            .chain_update(recipient)
+            .finalize(),
+    );
+    Ok(key)
+}
+
+/// The X25519 secret of an ed25519 keypair: the clamped first half of the
+/// SHA-512 of the seed, the same scalar ed25519 signs with.
+fn x25519_secret(keypair: &Keypair) -> Scalar {
+    let seed = Zeroizing::new(keypair.to_bytes());
+    let hash: Zeroizing<[u8; 64]> = Zeroizing::new(Sha512::digest(&seed[..32]).into());
+
+    let mut bytes = Zeroizing::new([0u8; 32]);
+    bytes.copy_from_slice(&hash[..32]);
+    bytes[0] &= 248;
+    bytes[31] &= 127;
+    bytes[31] |= 64;
+    Scalar::from_bits(*bytes)
+}
+
+/// The X25519 public key of an ed25519 public key: the Edwards point mapped
+/// to its Montgomery form.
+fn x25519_public(pubkey: &Pubkey) -> Result<MontgomeryPoint, MemoError> {
+    CompressedEdwardsY(pubkey.to_bytes())
+        .decompress()
+        .filter(|point| !point.is_small_order())
+        .map(|point| point.to_montgomery())
+        .ok_or(MemoError::NotEd25519(*pubkey))
+}
+
+fn associated_data(version: u8, sender: &Pubkey, recipient: &Pubkey) -> Vec<u8> {
+    let mut data = vec![version];
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(recipient.as_ref());
+    data
+}
+
+/// An encrypted memo found in the history of its sender or recipient.
+#[derive(Debug, Clone)]
+pub struct ReceivedMemo {
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    /// The text, or why it could not be decrypted.
+    pub memo: Result<String, MemoError>,
+}
+
+impl fmt::Display for ReceivedMemo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = self
+            .block_time
+            .map_or_else(|| "unknown time".to_string(), format_time);
+        writeln!(f, "{} (slot {}, {time})", self.signature, self.slot)?;
+        write!(f, "  from {} to {}: ", self.sender, self.recipient)?;
+        match &self.memo {
+            Ok(memo) => write!(f, "{memo:?}"),
+            Err(error) => write!(f, "cannot decrypt: {error}"),
+        }
+    }
+}
+
+/// Decrypts the encrypted memos in the recent transactions of `keypair`,
+/// newest first, as listed by [`get_history`] with `config`: the ones it
+/// received, and the ones it sent.
+///
+/// The sender is taken to be the fee payer and the recipient the
+/// destination of the SOL transfer, as with `sol transfer --encrypt`.
+/// Memos sent without a transfer are skipped, and memos that fail to
+/// decrypt are kept with the error.
+pub fn decrypt_history(
+    client: &RpcClient,
+    keypair: &Keypair,
+    config: &HistoryConfig,
+) -> Result<Vec<ReceivedMemo>, crate::Error> {
+    let entries = get_history(client, &keypair.pubkey(), config)?;
+
+    Ok(entries
+        .iter()
+        .filter_map(|entry| {
+            if entry.fee_payer != keypair.pubkey() {
+                return Some((entry, entry.fee_payer, keypair.pubkey()));
+            }
+            entry
+                .transfer_recipient
+                .map(|recipient| (entry, keypair.pubkey(), recipient))
+        })
+        .flat_map(|(entry, sender, recipient)| {
+            entry
+                .memos
+                .iter()
+                .filter(|memo| is_encrypted(memo))
+                .map(move |memo| ReceivedMemo {
+                    signature: entry.signature,
+                    slot: entry.slot,
+                    block_time: entry.block_time,
+                    sender,
+                    recipient,
+                    memo: if sender == keypair.pubkey() {
+                        decrypt_sent_memo(keypair, &recipient, memo)
+                    } else {
+                        decrypt_memo(keypair, &sender, memo)
+                    },
+                })
+        })
+        .collect())
+}
@@ -0,0 +1,310 @@
+//! Client for the escrow programs of practice 3.
+//!
+//! A maker offers tokens of mint A in exchange for tokens of mint B; a
+//! taker pays the wanted tokens to the maker, receives the offered ones
+//! and the offer is closed. The two programs differ in where the offered
+//! tokens wait:
+//!
+//! - [`EscrowProgram::Escrow`] moves them into a vault, the associated
+//!   token account of the offer;
+//! - [`EscrowProgram::EscrowApprove`] leaves them with the maker and only
+//!   approves the offer as delegate.
+//!
+//! Both are Anchor programs, so instruction data starts with the first 8
+//! bytes of `sha256("global:<instruction>")` and account data with those
+//! of `sha256("account:<Account>")`. Mints of either token program work.
+
+use clap::ValueEnum;
+use solana_client::rpc_client::RpcClient;
+use solana_program::hash::hashv;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::{pubkey, system_program};
+use solana_sdk::signature::Signer;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use thiserror::Error;
+
+use crate::transaction::process_transaction;
+use crate::{TransactionConfig, TransactionOutcome};
+
+pub const ESCROW_PROGRAM_ID: Pubkey = pubkey!("8mtEyFoPPp47tAccHJaa272CEFGczzDQJNqvNCcUUWjo");
+
+pub const ESCROW_APPROVE_PROGRAM_ID: Pubkey =
+    pubkey!("C1cUvDnDKvN64HwAJp7Awfrb2LMiLQZywqfShFF73XcN");
+
+#[derive(Debug, Error)]
+pub enum EscrowError {
+    #[error("account {0} does not exist")]
+    NotFound(Pubkey),
+    #[error("{0} is not an offer of the escrow program")]
+    InvalidOffer(Pubkey),
+    #[error("{0} is not a token mint")]
+    InvalidMint(Pubkey),
+}
+
+/// Which of the escrow programs to use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum EscrowProgram {
+    /// Offered tokens wait in a vault owned by the offer.
+    #[default]
+    Escrow,
+    /// Offered tokens stay with the maker, the offer is their delegate.
+    EscrowApprove,
+}
+
+impl EscrowProgram {
+    pub fn id(self) -> Pubkey {
+        match self {
+            EscrowProgram::Escrow => ESCROW_PROGRAM_ID,
+            EscrowProgram::EscrowApprove => ESCROW_APPROVE_PROGRAM_ID,
+        }
+    }
+
+    /// The program with the address `id`, if it is one of the two.
+    pub fn from_id(id: &Pubkey) -> Option<Self> {
+        [EscrowProgram::Escrow, EscrowProgram::EscrowApprove]
+            .into_iter()
+            .find(|program| program.id() == *id)
+    }
+
+    /// Size of an offer account, discriminator included.
+    pub fn offer_len(self) -> usize {
+        match self {
+            EscrowProgram::Escrow => 8 + 8 + 32 * 3 + 8 + 1,
+            EscrowProgram::EscrowApprove => 8 + 8 + 32 * 3 + 8 + 8 + 1,
+        }
+    }
+}
+
+/// What a maker offers and wants in return.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfferTerms {
+    /// Chosen by the maker, tells apart their offers.
+    pub id: u64,
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    /// Base units of mint A.
+    pub token_a_offered_amount: u64,
+    /// Base units of mint B.
+    pub token_b_wanted_amount: u64,
+}
+
+/// An open offer, as stored by the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Offer {
+    pub id: u64,
+    pub maker: Pubkey,
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    /// Only stored by [`EscrowProgram::EscrowApprove`]; for
+    /// [`EscrowProgram::Escrow`] it is the balance of the vault.
+    pub token_a_offered_amount: Option<u64>,
+    pub token_b_wanted_amount: u64,
+    pub bump: u8,
+}
+
+/// First 8 bytes of the sha256 of `preimage`, as Anchor computes them.
+pub fn anchor_discriminator(preimage: &str) -> [u8; 8] {
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&hashv(&[preimage.as_bytes()]).to_bytes()[..8]);
+
+    discriminator
+}
+
+/// Address of the offer `id` of `maker`.
+pub fn offer_address(program: EscrowProgram, maker: &Pubkey, id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"offer", maker.as_ref(), &id.to_le_bytes()],
+        &program.id(),
+    )
+    .0
+}
+
+/// Token account holding the offered tokens of [`EscrowProgram::Escrow`].
+pub fn vault_address(offer: &Pubkey, token_mint_a: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(offer, token_mint_a, token_program)
+}
+
+/// Instruction of [`make_offer`]. `token_program` owns both mints.
+pub fn make_offer_instruction(
+    program: EscrowProgram,
+    maker: &Pubkey,
+    token_program: &Pubkey,
+    terms: &OfferTerms,
+) -> Instruction {
+    let offer = offer_address(program, maker, terms.id);
+    let ata =
+        |owner, mint| get_associated_token_address_with_program_id(owner, mint, token_program);
+
+    let mut accounts = vec![
+        AccountMeta::new(*maker, true),
+        AccountMeta::new_readonly(terms.token_mint_a, false),
+        AccountMeta::new_readonly(terms.token_mint_b, false),
+        AccountMeta::new(ata(maker, &terms.token_mint_a), false),
+        AccountMeta::new(offer, false),
+    ];
+    if program == EscrowProgram::Escrow {
+        accounts.push(AccountMeta::new(
+            vault_address(&offer, &terms.token_mint_a, token_program),
+            false,
+        ));
+    }
+    accounts.extend([
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(*token_program, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ]);
+
+    let mut data = anchor_discriminator("global:make_offer").to_vec();
+    data.extend(terms.id.to_le_bytes());
+    data.extend(terms.token_a_offered_amount.to_le_bytes());
+    data.extend(terms.token_b_wanted_amount.to_le_bytes());
+
+    Instruction {
+        program_id: program.id(),
+        accounts,
+        data,
+    }
+}
+
+/// Instruction of [`take_offer`]. `token_program` owns both mints.
+pub fn take_offer_instruction(
+    program: EscrowProgram,
+    taker: &Pubkey,
+    offer: &Offer,
+    token_program: &Pubkey,
+) -> Instruction {
+    let address = offer_address(program, &offer.maker, offer.id);
+    let ata =
+        |owner, mint| get_associated_token_address_with_program_id(owner, mint, token_program);
+    let (mint_a, mint_b) = (&offer.token_mint_a, &offer.token_mint_b);
+
+    let mut accounts = match program {
+        EscrowProgram::Escrow => vec![
+            AccountMeta::new(*taker, true),
+            AccountMeta::new(offer.maker, false),
+            AccountMeta::new_readonly(*mint_a, false),
+            AccountMeta::new_readonly(*mint_b, false),
+            AccountMeta::new(ata(taker, mint_a), false),
+            AccountMeta::new(ata(taker, mint_b), false),
+            AccountMeta::new(ata(&offer.maker, mint_b), false),
+            AccountMeta::new(address, false),
+            AccountMeta::new(vault_address(&address, mint_a, token_program), false),
+        ],
+        EscrowProgram::EscrowApprove => vec![
+            AccountMeta::new(*taker, true),
+            AccountMeta::new(offer.maker, false),
+            AccountMeta::new(*mint_a, false),
+            AccountMeta::new(*mint_b, false),
+            AccountMeta::new(ata(taker, mint_a), false),
+            AccountMeta::new(ata(taker, mint_b), false),
+            AccountMeta::new(ata(&offer.maker, mint_a), false),
+            AccountMeta::new(ata(&offer.maker, mint_b), false),
+            AccountMeta::new(address, false),
+        ],
+    };
+
+    accounts.extend([
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(*token_program, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ]);
+
+    Instruction {
+        program_id: program.id(),
+        accounts,
+        data: anchor_discriminator("global:take_offer").to_vec(),
+    }
+}
+
+/// Decodes the data of an offer account of `program`.
+pub fn decode_offer(program: EscrowProgram, data: &[u8]) -> Option<Offer> {
+    let data = data.strip_prefix(&anchor_discriminator("account:Offer"))?;
+    let u64_at = |offset: usize| {
+        let bytes = data.get(offset..offset + 8)?;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    };
+    let pubkey_at = |offset: usize| Pubkey::try_from(data.get(offset..offset + 32)?).ok();
+
+    // `token_a_offered_amount` sits between the mints and the wanted amount.
+    let (token_a_offered_amount, wanted_at) = match program {
+        EscrowProgram::Escrow => (None, 104),
+        EscrowProgram::EscrowApprove => (Some(u64_at(104)?), 112),
+    };
+
+    Some(Offer {
+        id: u64_at(0)?,
+        maker: pubkey_at(8)?,
+        token_mint_a: pubkey_at(40)?,
+        token_mint_b: pubkey_at(72)?,
+        token_a_offered_amount,
+        token_b_wanted_amount: u64_at(wanted_at)?,
+        bump: *data.get(wanted_at + 8)?,
+    })
+}
+
+/// Fetches and decodes the offer at `address`.
+pub fn get_offer(
+    client: &RpcClient,
+    program: EscrowProgram,
+    address: &Pubkey,
+) -> Result<Offer, crate::Error> {
+    let account = client
+        .get_account_with_commitment(address, client.commitment())?
+        .value
+        .ok_or(EscrowError::NotFound(*address))?;
+    if account.owner != program.id() {
+        return Err(EscrowError::InvalidOffer(*address).into());
+    }
+
+    Ok(decode_offer(program, &account.data).ok_or(EscrowError::InvalidOffer(*address))?)
+}
+
+/// The token program owning `mint`, SPL Token or Token-2022.
+pub fn mint_token_program(client: &RpcClient, mint: &Pubkey) -> Result<Pubkey, crate::Error> {
+    let account = client
+        .get_account_with_commitment(mint, client.commitment())?
+        .value
+        .ok_or(EscrowError::NotFound(*mint))?;
+    if account.owner != spl_token::id() && account.owner != spl_token_2022::id() {
+        return Err(EscrowError::InvalidMint(*mint).into());
+    }
+
+    Ok(account.owner)
+}
+
+/// Opens an offer of `maker` on `terms` and returns its address. With
+/// [`EscrowProgram::Escrow`] the offered tokens move into the vault.
+pub fn make_offer(
+    client: &RpcClient,
+    program: EscrowProgram,
+    maker: &dyn Signer,
+    terms: &OfferTerms,
+    config: &TransactionConfig,
+) -> Result<(Pubkey, TransactionOutcome), crate::Error> {
+    let token_program = mint_token_program(client, &terms.token_mint_a)?;
+    let ix = make_offer_instruction(program, &maker.pubkey(), &token_program, terms);
+
+    let outcome = process_transaction(client, &[ix], &[maker], config)?;
+
+    Ok((offer_address(program, &maker.pubkey(), terms.id), outcome))
+}
+
+/// Takes the offer at `offer`: `taker` pays the wanted tokens of mint B to
+/// the maker and receives the offered ones, creating the token accounts
+/// as needed. The rent of the offer goes back to the maker.
+pub fn take_offer(
+    client: &RpcClient,
+    program: EscrowProgram,
+    taker: &dyn Signer,
+    offer: &Pubkey,
+    config: &TransactionConfig,
+) -> Result<(Offer, TransactionOutcome), crate::Error> {
+    let offer = get_offer(client, program, offer)?;
+    let token_program = mint_token_program(client, &offer.token_mint_a)?;
+    let ix = take_offer_instruction(program, &taker.pubkey(), &offer, &token_program);
+
+    let outcome = process_transaction(client, &[ix], &[taker], config)?;
+
+    Ok((offer, outcome))
+}
@@ -0,0 +1,236 @@
+//! Priority fees and compute unit limits.
+//!
+//! With a [`FeeConfig`] other than the default, [`crate::transaction`]
+//! puts `ComputeBudget` instructions in front of the instructions of a
+//! transaction (after `advance_nonce_account`, which has to come first):
+//!
+//! - the compute unit price is [`PriorityFee::Fixed`], or a
+//!   [`PriorityFee::Percentile`] of the fees paid recently by transactions
+//!   writing the same accounts, from `getRecentPrioritizationFees`;
+//! - the compute unit limit is [`ComputeUnitLimit::Fixed`], or with
+//!   [`ComputeUnitLimit::Simulate`] the units the transaction used in a
+//!   simulation plus [`FeeConfig::compute_unit_margin`] percent.
+//!
+//! Since the fee is price times limit, a tight limit keeps a priority fee
+//! cheap.
+
+use std::fmt;
+use std::str::FromStr;
+
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_client::rpc_response::{RpcPrioritizationFee, RpcSimulateTransactionResult};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::transaction::TransactionError;
+use thiserror::Error;
+
+/// Variable with the [`PriorityFee`], e.g. `5000` or `p75`.
+pub const PRIORITY_FEE_VAR: &str = "PRIORITY_FEE";
+
+/// Variable with the [`ComputeUnitLimit`], e.g. `auto` or `200000`.
+pub const COMPUTE_UNIT_LIMIT_VAR: &str = "COMPUTE_UNIT_LIMIT";
+
+/// Compute units a transaction may use at most.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Default of [`FeeConfig::compute_unit_margin`].
+pub const DEFAULT_COMPUTE_UNIT_MARGIN: u32 = 10;
+
+#[derive(Debug, Error)]
+pub enum FeeError {
+    #[error("invalid priority fee `{0}`, expected none, micro-lamports per compute unit or a percentile like p75")]
+    PriorityFee(String),
+    #[error(
+        "invalid compute unit limit `{0}`, expected default, auto or a number of compute units"
+    )]
+    ComputeUnitLimit(String),
+    #[error("simulation failed: {error}")]
+    Simulation {
+        error: TransactionError,
+        logs: Vec<String>,
+    },
+    #[error("the simulation did not report the compute units used")]
+    NoUnitsConsumed,
+}
+
+/// Compute unit price of a transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PriorityFee {
+    /// No price, the transaction pays the base fee only.
+    #[default]
+    None,
+    /// Micro-lamports per compute unit.
+    Fixed(u64),
+    /// Percentile (0 to 100) of the recent prioritization fees of the
+    /// accounts the transaction writes.
+    Percentile(u8),
+}
+
+impl FromStr for PriorityFee {
+    type Err = FeeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || FeeError::PriorityFee(s.to_string());
+
+        if s == "none" {
+            return Ok(PriorityFee::None);
+        }
+        if let Some(percentile) = s.strip_prefix('p') {
+            return match percentile.parse() {
+                Ok(percentile) if percentile <= 100 => Ok(PriorityFee::Percentile(percentile)),
+                _ => Err(invalid()),
+            };
+        }
+
+        s.parse().map(PriorityFee::Fixed).map_err(|_| invalid())
+    }
+}
+
+impl fmt::Display for PriorityFee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriorityFee::None => write!(f, "none"),
+            PriorityFee::Fixed(price) => write!(f, "{price}"),
+            PriorityFee::Percentile(percentile) => write!(f, "p{percentile}"),
+        }
+    }
+}
+
+/// Compute unit limit of a transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ComputeUnitLimit {
+    /// No limit instruction: 200 000 units per instruction.
+    #[default]
+    Default,
+    Fixed(u32),
+    /// Simulate the transaction and add a margin to the units it used.
+    Simulate,
+}
+
+impl FromStr for ComputeUnitLimit {
+    type Err = FeeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(ComputeUnitLimit::Default),
+            "auto" => Ok(ComputeUnitLimit::Simulate),
+            _ => match s.parse() {
+                Ok(limit) if limit <= MAX_COMPUTE_UNIT_LIMIT => Ok(ComputeUnitLimit::Fixed(limit)),
+                _ => Err(FeeError::ComputeUnitLimit(s.to_string())),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ComputeUnitLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComputeUnitLimit::Default => write!(f, "default"),
+            ComputeUnitLimit::Fixed(limit) => write!(f, "{limit}"),
+            ComputeUnitLimit::Simulate => write!(f, "auto"),
+        }
+    }
+}
+
+/// Priority fee and compute unit limit of the transactions.
+///
+/// The default adds no `ComputeBudget` instructions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeConfig {
+    pub priority_fee: PriorityFee,
+    pub compute_unit_limit: ComputeUnitLimit,
+    /// Percent added to the simulated compute units.
+    pub compute_unit_margin: u32,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        FeeConfig {
+            priority_fee: PriorityFee::None,
+            compute_unit_limit: ComputeUnitLimit::Default,
+            compute_unit_margin: DEFAULT_COMPUTE_UNIT_MARGIN,
+        }
+    }
+}
+
+/// `ComputeBudget` instructions setting `limit` and `price` (in
+/// micro-lamports per compute unit).
+pub fn compute_budget_instructions(limit: Option<u32>, price: Option<u64>) -> Vec<Instruction> {
+    let mut instructions = Vec::with_capacity(2);
+    if let Some(limit) = limit {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(limit));
+    }
+    if let Some(price) = price {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+    }
+
+    instructions
+}
+
+/// The accounts `instructions` write, for `getRecentPrioritizationFees`.
+pub fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = Vec::new();
+    for meta in instructions.iter().flat_map(|ix| &ix.accounts) {
+        if meta.is_writable && !accounts.contains(&meta.pubkey) {
+            accounts.push(meta.pubkey);
+        }
+    }
+
+    accounts
+}
+
+/// The `percentile` (0 to 100) of `fees`, by the nearest-rank method; 0
+/// for no fees.
+pub fn percentile(fees: &[u64], percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+
+    let mut fees = fees.to_vec();
+    fees.sort_unstable();
+    let rank = (usize::from(percentile.min(100)) * fees.len()).div_ceil(100);
+
+    fees[rank.saturating_sub(1)]
+}
+
+/// `units` plus `margin` percent, capped at [`MAX_COMPUTE_UNIT_LIMIT`].
+pub fn limit_with_margin(units: u64, margin: u32) -> u32 {
+    let limit = units.saturating_mul(100 + u64::from(margin)).div_ceil(100);
+
+    limit.min(u64::from(MAX_COMPUTE_UNIT_LIMIT)) as u32
+}
+
+/// The `percentile` of the fees in a `getRecentPrioritizationFees`
+/// response.
+pub fn recent_fee_percentile(recent: &[RpcPrioritizationFee], percentile: u8) -> u64 {
+    let fees: Vec<u64> = recent.iter().map(|fee| fee.prioritization_fee).collect();
+
+    self::percentile(&fees, percentile)
+}
+
+/// Simulation of an unsigned transaction with the blockhash it will be
+/// signed with, which for a durable nonce must not be replaced.
+pub(crate) fn simulation_config(commitment: CommitmentConfig) -> RpcSimulateTransactionConfig {
+    RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: false,
+        commitment: Some(commitment),
+        ..RpcSimulateTransactionConfig::default()
+    }
+}
+
+/// The compute unit limit for a transaction that used the units in
+/// `result`, plus `margin` percent.
+pub fn simulated_limit(result: RpcSimulateTransactionResult, margin: u32) -> Result<u32, FeeError> {
+    if let Some(error) = result.err {
+        return Err(FeeError::Simulation {
+            error,
+            logs: result.logs.unwrap_or_default(),
+        });
+    }
+    let units = result.units_consumed.ok_or(FeeError::NoUnitsConsumed)?;
+
+    Ok(limit_with_margin(units, margin))
+}
@@ -0,0 +1,211 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use solana_sdk::signature::{Keypair, Signer};
+use thiserror::Error as ThisError;
+
+use crate::Error;
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// How often [`Grinder::grind`] reports progress.
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Attempts a worker makes before adding them to the shared counter.
+const ATTEMPTS_BATCH: u64 = 1_000;
+
+#[derive(Debug, ThisError)]
+pub enum GrindError {
+    #[error("at least one of prefix or suffix is required")]
+    EmptyPattern,
+    #[error("`{pattern}` can never match: `{ch}` is not a base58 character")]
+    NotBase58 { pattern: String, ch: char },
+}
+
+/// What a vanity address has to look like.
+#[derive(Debug, Clone)]
+pub struct VanityPattern {
+    prefix: Option<String>,
+    suffix: Option<String>,
+    ignore_case: bool,
+}
+
+impl VanityPattern {
+    /// Creates a pattern matching addresses that start with `prefix` and
+    /// end with `suffix`; at least one of them has to be given.
+    pub fn new(
+        prefix: Option<String>,
+        suffix: Option<String>,
+        ignore_case: bool,
+    ) -> Result<Self, GrindError> {
+        if prefix.is_none() && suffix.is_none() {
+            return Err(GrindError::EmptyPattern);
+        }
+
+        for pattern in prefix.iter().chain(suffix.iter()) {
+            if let Some(ch) = pattern.chars().find(|&ch| !is_base58(ch, ignore_case)) {
+                return Err(GrindError::NotBase58 {
+                    pattern: pattern.clone(),
+                    ch,
+                });
+            }
+        }
+
+        Ok(VanityPattern {
+            prefix,
+            suffix,
+            ignore_case,
+        })
+    }
+
+    /// Whether the base58 `address` matches.
+    pub fn matches(&self, address: &str) -> bool {
+        let eq = |a: &str, b: &str| {
+            if self.ignore_case {
+                a.eq_ignore_ascii_case(b)
+            } else {
+                a == b
+            }
+        };
+
+        let prefix_matches = self.prefix.as_deref().is_none_or(|prefix| {
+            address
+                .get(..prefix.len())
+                .is_some_and(|head| eq(head, prefix))
+        });
+        let suffix_matches = self.suffix.as_deref().is_none_or(|suffix| {
+            address
+                .len()
+                .checked_sub(suffix.len())
+                .and_then(|start| address.get(start..))
+                .is_some_and(|tail| eq(tail, suffix))
+        });
+
+        prefix_matches && suffix_matches
+    }
+}
+
+fn is_base58(ch: char, ignore_case: bool) -> bool {
+    if ignore_case {
+        BASE58_ALPHABET.contains(ch.to_ascii_lowercase())
+            || BASE58_ALPHABET.contains(ch.to_ascii_uppercase())
+    } else {
+        BASE58_ALPHABET.contains(ch)
+    }
+}
+
+/// Grinding statistics, reported every [`REPORT_INTERVAL`].
+#[derive(Debug, Clone, Copy)]
+pub struct GrindProgress {
+    pub attempts: u64,
+    pub elapsed: Duration,
+    pub found: usize,
+}
+
+impl GrindProgress {
+    /// Attempts per second since grinding started.
+    pub fn rate(&self) -> f64 {
+        self.attempts as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Something that happened while grinding.
+pub enum GrindEvent<'a> {
+    Progress(GrindProgress),
+    /// A keypair matching the pattern was found.
+    Match(&'a Keypair),
+}
+
+/// Searches for keypairs with a vanity address on several threads.
+pub struct Grinder {
+    pattern: VanityPattern,
+    threads: usize,
+}
+
+impl Grinder {
+    /// Creates a grinder using every available CPU core.
+    pub fn new(pattern: VanityPattern) -> Self {
+        let threads = std::thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1);
+
+        Grinder { pattern, threads }
+    }
+
+    /// Overrides the number of worker threads.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Grinds until `count` matches are found, calling `on_event` from the
+    /// calling thread for every match and progress report.
+    ///
+    /// Grinding stops early if `on_event` fails.
+    pub fn grind<F>(&self, count: usize, mut on_event: F) -> Result<(), Error>
+    where
+        F: FnMut(GrindEvent<'_>) -> Result<(), Error>,
+    {
+        let attempts = AtomicU64::new(0);
+        let done = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::channel();
+        let started = Instant::now();
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.threads {
+                let sender = sender.clone();
+                let (attempts, done) = (&attempts, &done);
+
+                scope.spawn(move || {
+                    let mut batch = 0;
+                    while !done.load(Ordering::Relaxed) {
+                        let keypair = Keypair::new();
+                        batch += 1;
+                        if batch == ATTEMPTS_BATCH {
+                            attempts.fetch_add(batch, Ordering::Relaxed);
+                            batch = 0;
+                        }
+
+                        if self.pattern.matches(&keypair.pubkey().to_string())
+                            && sender.send(keypair).is_err()
+                        {
+                            break;
+                        }
+                    }
+                    attempts.fetch_add(batch, Ordering::Relaxed);
+                });
+            }
+            drop(sender);
+
+            let mut found = 0;
+            let mut last_report = Instant::now();
+            let mut collect = || {
+                while found < count {
+                    match receiver.recv_timeout(REPORT_INTERVAL) {
+                        Ok(keypair) => {
+                            found += 1;
+                            on_event(GrindEvent::Match(&keypair))?;
+                        }
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+
+                    if last_report.elapsed() >= REPORT_INTERVAL {
+                        last_report = Instant::now();
+                        on_event(GrindEvent::Progress(GrindProgress {
+                            attempts: attempts.load(Ordering::Relaxed),
+                            elapsed: started.elapsed(),
+                            found,
+                        }))?;
+                    }
+                }
+                Ok(())
+            };
+            let result = collect();
+
+            done.store(true, Ordering::Relaxed);
+            result
+        })
+    }
+}
@@ -0,0 +1,419 @@
+//! Transaction history of an address.
+//!
+//! [`get_history`] pages through `getSignaturesForAddress`, newest first,
+//! and fetches every transaction. Each [`HistoryEntry`] has what the
+//! transaction did to the address: its SOL balance change, the changes of
+//! its token accounts, the memos attached (as by `send_sol_with_memo`)
+//! and the programs it invoked, inner instructions included.
+
+use std::fmt;
+use std::io;
+
+use serde::Serialize;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::SystemInstruction;
+use solana_sdk::bs58;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{UiInstruction, UiTransactionEncoding, UiTransactionStatusMeta};
+
+use crate::amount::{format_delta, SOL_DECIMALS};
+use crate::programs::{program_name, KnownProgram};
+
+/// Most signatures `getSignaturesForAddress` returns at once.
+pub const MAX_SIGNATURES_PER_PAGE: usize = 1000;
+
+/// Which part of the history to fetch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryConfig {
+    /// Most transactions to fetch.
+    pub limit: usize,
+    /// Start with the transactions before this one.
+    pub before: Option<Signature>,
+    /// Stop at this transaction.
+    pub until: Option<Signature>,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            limit: 20,
+            before: None,
+            until: None,
+        }
+    }
+}
+
+/// A change of the tokens held by a token account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenDelta {
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    pub decimals: u8,
+    /// Base units of the mint.
+    pub delta: i128,
+}
+
+/// A transaction, seen from one address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub signature: Signature,
+    pub slot: u64,
+    /// Unix time, if the node knows it.
+    pub block_time: Option<i64>,
+    pub error: Option<TransactionError>,
+    pub fee: u64,
+    pub fee_payer: Pubkey,
+    /// Change of the SOL balance of the address, fee included if it paid.
+    pub sol_delta: i128,
+    /// Changes of the address itself, if it is a token account, and of the
+    /// token accounts it owns.
+    pub token_deltas: Vec<TokenDelta>,
+    pub memos: Vec<String>,
+    /// Invoked programs, in order of first invocation.
+    pub programs: Vec<Pubkey>,
+    /// Destination of the first SOL transfer, the recipient of a `sol
+    /// transfer`.
+    pub transfer_recipient: Option<Pubkey>,
+}
+
+impl HistoryEntry {
+    /// What `transaction` with status `meta` did to `address`.
+    pub fn new(
+        address: &Pubkey,
+        signature: Signature,
+        slot: u64,
+        block_time: Option<i64>,
+        transaction: &VersionedTransaction,
+        meta: &UiTransactionStatusMeta,
+    ) -> Self {
+        let keys = transaction_keys(transaction, meta);
+        let sol_delta = keys
+            .iter()
+            .position(|key| key == address)
+            .and_then(|index| {
+                Some((
+                    meta.pre_balances.get(index)?,
+                    meta.post_balances.get(index)?,
+                ))
+            })
+            .map(|(pre, post)| i128::from(*post) - i128::from(*pre))
+            .unwrap_or_default();
+
+        let mut entry = HistoryEntry {
+            signature,
+            slot,
+            block_time,
+            error: meta.err.clone(),
+            fee: meta.fee,
+            fee_payer: keys.first().copied().unwrap_or_default(),
+            sol_delta,
+            token_deltas: token_deltas(address, &keys, meta),
+            memos: Vec::new(),
+            programs: Vec::new(),
+            transfer_recipient: None,
+        };
+
+        for ix in transaction.message.instructions() {
+            let Some(program) = keys.get(usize::from(ix.program_id_index)) else {
+                continue;
+            };
+            if entry.transfer_recipient.is_none()
+                && matches!(KnownProgram::from_id(program), Some(KnownProgram::System))
+                && matches!(
+                    bincode::deserialize(&ix.data),
+                    Ok(SystemInstruction::Transfer { .. })
+                )
+            {
+                entry.transfer_recipient = ix
+                    .accounts
+                    .get(1)
+                    .and_then(|index| keys.get(usize::from(*index)))
+                    .copied();
+            }
+            entry.add_instruction(program, &ix.data);
+        }
+        if let OptionSerializer::Some(inner) = &meta.inner_instructions {
+            for ix in inner.iter().flat_map(|inner| &inner.instructions) {
+                let UiInstruction::Compiled(ix) = ix else {
+                    continue;
+                };
+                let Some(program) = keys.get(usize::from(ix.program_id_index)) else {
+                    continue;
+                };
+                let data = bs58::decode(&ix.data).into_vec().unwrap_or_default();
+                entry.add_instruction(program, &data);
+            }
+        }
+
+        entry
+    }
+
+    fn add_instruction(&mut self, program: &Pubkey, data: &[u8]) {
+        if !self.programs.contains(program) {
+            self.programs.push(*program);
+        }
+        if KnownProgram::from_id(program).is_some_and(KnownProgram::is_memo) {
+            self.memos.push(String::from_utf8_lossy(data).into_owned());
+        }
+    }
+
+    /// Names of the invoked programs; unknown ones by address.
+    pub fn program_names(&self) -> Vec<String> {
+        self.programs.iter().map(program_name).collect()
+    }
+}
+
+impl fmt::Display for HistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.error.is_some() { "❌" } else { "✅" };
+        write!(f, "{status} {} slot {}", self.signature, self.slot)?;
+        if let Some(time) = self.block_time {
+            write!(f, ", {}", format_time(time))?;
+        }
+        if let Some(error) = &self.error {
+            write!(f, "\n  error: {error}")?;
+        }
+        write!(f, "\n  SOL: {}", format_delta(self.sol_delta, SOL_DECIMALS))?;
+        for token in &self.token_deltas {
+            write!(
+                f,
+                "\n  tokens: {} of {} in {}",
+                format_delta(token.delta, token.decimals),
+                token.mint,
+                token.account
+            )?;
+        }
+        for memo in &self.memos {
+            write!(f, "\n  memo: {memo:?}")?;
+        }
+        write!(f, "\n  programs: {}", self.program_names().join(", "))
+    }
+}
+
+/// Accounts of `transaction` in the order its instructions refer to them:
+/// the static keys, then the ones `meta` says were loaded from lookup
+/// tables.
+pub fn transaction_keys(
+    transaction: &VersionedTransaction,
+    meta: &UiTransactionStatusMeta,
+) -> Vec<Pubkey> {
+    let mut keys = transaction.message.static_account_keys().to_vec();
+    if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+        keys.extend(
+            loaded
+                .writable
+                .iter()
+                .chain(&loaded.readonly)
+                .filter_map(|key| key.parse::<Pubkey>().ok()),
+        );
+    }
+
+    keys
+}
+
+/// Token balance changes of `address` and the accounts it owns.
+fn token_deltas(
+    address: &Pubkey,
+    keys: &[Pubkey],
+    meta: &UiTransactionStatusMeta,
+) -> Vec<TokenDelta> {
+    fn balances<T>(balances: &OptionSerializer<Vec<T>>) -> &[T] {
+        match balances {
+            OptionSerializer::Some(balances) => balances,
+            _ => &[],
+        }
+    }
+
+    let mut deltas: Vec<(u8, TokenDelta, bool)> = Vec::new();
+    for (balance, sign) in balances(&meta.pre_token_balances)
+        .iter()
+        .map(|balance| (balance, -1))
+        .chain(
+            balances(&meta.post_token_balances)
+                .iter()
+                .map(|balance| (balance, 1)),
+        )
+    {
+        let (Some(account), Ok(mint), Ok(amount)) = (
+            keys.get(usize::from(balance.account_index)),
+            balance.mint.parse::<Pubkey>(),
+            balance.ui_token_amount.amount.parse::<u64>(),
+        ) else {
+            continue;
+        };
+        let owned = match &balance.owner {
+            OptionSerializer::Some(owner) => *owner == address.to_string(),
+            _ => false,
+        };
+
+        let index = match deltas
+            .iter()
+            .position(|(index, _, _)| *index == balance.account_index)
+        {
+            Some(index) => index,
+            None => {
+                let delta = TokenDelta {
+                    account: *account,
+                    mint,
+                    decimals: balance.ui_token_amount.decimals,
+                    delta: 0,
+                };
+                deltas.push((balance.account_index, delta, false));
+                deltas.len() - 1
+            }
+        };
+        let (_, delta, relevant) = &mut deltas[index];
+        delta.delta += sign * i128::from(amount);
+        *relevant |= owned || account == address;
+    }
+
+    deltas
+        .into_iter()
+        .filter(|(_, delta, relevant)| *relevant && delta.delta != 0)
+        .map(|(_, delta, _)| delta)
+        .collect()
+}
+
+/// `time` in UTC, as `2024-08-29 12:00:00`.
+pub fn format_time(time: i64) -> String {
+    match chrono::DateTime::from_timestamp(time, 0) {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        None => time.to_string(),
+    }
+}
+
+/// Fetches the transactions of `address`, newest first.
+pub fn get_history(
+    client: &RpcClient,
+    address: &Pubkey,
+    config: &HistoryConfig,
+) -> Result<Vec<HistoryEntry>, crate::Error> {
+    // Neither method serves `processed` transactions.
+    let commitment = if client.commitment() == CommitmentConfig::processed() {
+        CommitmentConfig::confirmed()
+    } else {
+        client.commitment()
+    };
+
+    let mut signatures = Vec::new();
+    let mut before = config.before;
+    while signatures.len() < config.limit {
+        let limit = (config.limit - signatures.len()).min(MAX_SIGNATURES_PER_PAGE);
+        let page = client.get_signatures_for_address_with_config(
+            address,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: config.until,
+                limit: Some(limit),
+                commitment: Some(commitment),
+            },
+        )?;
+        let last_page = page.len() < limit;
+
+        for status in page {
+            let signature: Signature = status.signature.parse().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "invalid signature from RPC")
+            })?;
+            before = Some(signature);
+            signatures.push(signature);
+        }
+        if last_page {
+            break;
+        }
+    }
+
+    let mut entries = Vec::new();
+    for signature in signatures {
+        let confirmed = client.get_transaction_with_config(
+            &signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(commitment),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        let (Some(transaction), Some(meta)) = (
+            confirmed.transaction.transaction.decode(),
+            confirmed.transaction.meta,
+        ) else {
+            continue;
+        };
+
+        entries.push(HistoryEntry::new(
+            address,
+            signature,
+            confirmed.slot,
+            confirmed.block_time,
+            &transaction,
+            &meta,
+        ));
+    }
+
+    Ok(entries)
+}
+
+/// A row of the CSV export.
+#[derive(Debug, Clone, Serialize)]
+struct CsvRow {
+    signature: String,
+    slot: u64,
+    time: String,
+    status: &'static str,
+    error: String,
+    fee: u64,
+    sol_delta: String,
+    /// `DELTA MINT` pairs, separated by `;`.
+    token_deltas: String,
+    /// Separated by `;`.
+    memos: String,
+    /// Separated by `;`.
+    programs: String,
+}
+
+/// Writes `entries` as CSV with a header row. Amounts are in SOL and
+/// tokens, with their sign.
+pub fn write_csv(entries: &[HistoryEntry], writer: impl io::Write) -> Result<(), crate::Error> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for entry in entries {
+        let row = CsvRow {
+            signature: entry.signature.to_string(),
+            slot: entry.slot,
+            time: entry.block_time.map(format_time).unwrap_or_default(),
+            status: if entry.error.is_some() {
+                "failed"
+            } else {
+                "ok"
+            },
+            error: entry
+                .error
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            fee: entry.fee,
+            sol_delta: format_delta(entry.sol_delta, SOL_DECIMALS),
+            token_deltas: entry
+                .token_deltas
+                .iter()
+                .map(|token| {
+                    format!(
+                        "{} {}",
+                        format_delta(token.delta, token.decimals),
+                        token.mint
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(";"),
+            memos: entry.memos.join(";"),
+            programs: entry.program_names().join(";"),
+        };
+        writer.serialize(row).map_err(io::Error::from)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
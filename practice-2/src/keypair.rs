@@ -0,0 +1,219 @@
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use solana_sdk::signature::Keypair;
+use thiserror::Error;
+
+use crate::keystore::{read_password, Keystore, KeystoreError};
+
+/// Name of the variable that selects a keypair source explicitly.
+pub const KEYPAIR_VAR: &str = "KEYPAIR";
+
+/// Name of the variable the bootcamp `.env` files keep the secret key in.
+pub const SECRET_KEY_VAR: &str = "SECRET_KEY";
+
+#[derive(Debug, Error)]
+pub enum KeypairError {
+    #[error("environment variable `{0}` is not set")]
+    MissingVar(String),
+    #[error("empty variable name in keypair source `{0}`")]
+    EmptyVarName(String),
+    #[error("failed to read keypair from {source_name}: {error}")]
+    Io {
+        source_name: String,
+        error: std::io::Error,
+    },
+    #[error("failed to write keypair file {path}: {error}")]
+    Write {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    #[error("keypair from {source_name} is not a JSON byte array: {error}")]
+    Format {
+        source_name: String,
+        error: serde_json::Error,
+    },
+    #[error("keypair from {source_name} has {len} bytes, expected 64")]
+    Length { source_name: String, len: usize },
+    #[error("keystore from {source_name}: {error}")]
+    Keystore {
+        source_name: String,
+        error: KeystoreError,
+    },
+    #[error("keypair from {source_name} is invalid: {error}")]
+    Invalid {
+        source_name: String,
+        error: ed25519_dalek::SignatureError,
+    },
+}
+
+/// Where a keypair is read from.
+///
+/// Every source holds a keypair in the Solana CLI format, a JSON array
+/// of the 64 secret key bytes, or an encrypted [`Keystore`]. The keystore
+/// password is read from `KEYSTORE_PASSWORD` or asked for.
+///
+/// A source is parsed from a string with [`FromStr`]:
+/// - `-` or `stdin` reads standard input,
+/// - `env:NAME` reads the `NAME` environment variable,
+/// - `cli-config` reads `keypair_path` from the Solana CLI config,
+/// - anything else is a path to a keypair file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeypairSource {
+    File(PathBuf),
+    Env(String),
+    Stdin,
+    CliConfig,
+}
+
+impl KeypairSource {
+    /// Picks a source when none was given explicitly.
+    ///
+    /// Lookup order:
+    /// 1. `KEYPAIR` variable, parsed as a [`KeypairSource`],
+    /// 2. `SECRET_KEY` variable,
+    /// 3. `keypair_path` from the Solana CLI config
+    ///    (`~/.config/solana/cli/config.yml`), falling back to
+    ///    `~/.config/solana/id.json` when there is no config.
+    ///
+    /// Variables are looked up after loading `.env` from the current
+    /// working directory.
+    pub fn discover() -> Result<Self, KeypairError> {
+        dotenv::dotenv().ok();
+
+        if let Ok(source) = std::env::var(KEYPAIR_VAR) {
+            return source.parse();
+        }
+
+        if std::env::var_os(SECRET_KEY_VAR).is_some() {
+            return Ok(KeypairSource::Env(SECRET_KEY_VAR.to_string()));
+        }
+
+        Ok(KeypairSource::CliConfig)
+    }
+
+    /// Reads and validates the keypair.
+    pub fn load(&self) -> Result<Keypair, KeypairError> {
+        let source_name = self.to_string();
+        let io_error = |error| KeypairError::Io {
+            source_name: source_name.clone(),
+            error,
+        };
+
+        let contents = match self {
+            KeypairSource::File(path) => std::fs::read_to_string(path).map_err(io_error)?,
+            KeypairSource::Env(name) => {
+                dotenv::dotenv().ok();
+                std::env::var(name).map_err(|_| KeypairError::MissingVar(name.clone()))?
+            }
+            KeypairSource::Stdin => {
+                let mut contents = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut contents)
+                    .map_err(io_error)?;
+                contents
+            }
+            KeypairSource::CliConfig => {
+                return KeypairSource::File(cli_config_keypair_path()).load();
+            }
+        };
+
+        parse_keypair(&contents, &source_name)
+    }
+}
+
+impl FromStr for KeypairSource {
+    type Err = KeypairError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "-" | "stdin" => Ok(KeypairSource::Stdin),
+            "cli-config" => Ok(KeypairSource::CliConfig),
+            _ => match s.strip_prefix("env:") {
+                Some("") => Err(KeypairError::EmptyVarName(s.to_string())),
+                Some(name) => Ok(KeypairSource::Env(name.to_string())),
+                None => Ok(KeypairSource::File(PathBuf::from(s))),
+            },
+        }
+    }
+}
+
+impl fmt::Display for KeypairSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeypairSource::File(path) => write!(f, "file {}", path.display()),
+            KeypairSource::Env(name) => write!(f, "variable `{name}`"),
+            KeypairSource::Stdin => write!(f, "stdin"),
+            KeypairSource::CliConfig => write!(f, "Solana CLI config"),
+        }
+    }
+}
+
+/// Writes `keypair` to `path` in the Solana CLI JSON format.
+/// On Unix the file is only readable by its owner.
+pub fn write_keypair_file(keypair: &Keypair, path: &Path) -> Result<(), KeypairError> {
+    let write_error = |error| KeypairError::Write {
+        path: path.to_path_buf(),
+        error,
+    };
+
+    let json = serde_json::to_string(&keypair.to_bytes().to_vec()).expect("byte array serializes");
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(json.as_bytes()))
+        .map_err(write_error)
+}
+
+/// Returns `keypair_path` from the Solana CLI config file, or the CLI's
+/// default keypair path if the config file cannot be read.
+fn cli_config_keypair_path() -> PathBuf {
+    let config = solana_cli_config::CONFIG_FILE
+        .as_ref()
+        .and_then(|path| solana_cli_config::Config::load(path).ok())
+        .unwrap_or_default();
+
+    PathBuf::from(config.keypair_path)
+}
+
+/// Parses a keypair in the Solana CLI JSON format, or decrypts a keystore.
+fn parse_keypair(contents: &str, source_name: &str) -> Result<Keypair, KeypairError> {
+    if Keystore::detect(contents) {
+        let keystore_error = |error| KeypairError::Keystore {
+            source_name: source_name.to_string(),
+            error,
+        };
+
+        let keystore = Keystore::from_json(contents).map_err(keystore_error)?;
+        let password = read_password(&format!("Password for {}: ", keystore.pubkey))
+            .map_err(keystore_error)?;
+
+        return keystore.decrypt(&password).map_err(keystore_error);
+    }
+
+    let bytes: Vec<u8> =
+        serde_json::from_str(contents.trim()).map_err(|error| KeypairError::Format {
+            source_name: source_name.to_string(),
+            error,
+        })?;
+
+    if bytes.len() != 64 {
+        return Err(KeypairError::Length {
+            source_name: source_name.to_string(),
+            len: bytes.len(),
+        });
+    }
+
+    Keypair::from_bytes(&bytes).map_err(|error| KeypairError::Invalid {
+        source_name: source_name.to_string(),
+        error,
+    })
+}
@@ -0,0 +1,284 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::{Keypair, Signer};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Variable the keystore password is read from instead of prompting,
+/// meant for CI.
+pub const KEYSTORE_PASSWORD_VAR: &str = "KEYSTORE_PASSWORD";
+
+/// Variable the new password is read from by [`read_new_password`].
+pub const KEYSTORE_NEW_PASSWORD_VAR: &str = "KEYSTORE_NEW_PASSWORD";
+
+const KEYSTORE_VERSION: u32 = 1;
+const KDF_NAME: &str = "argon2id";
+const CIPHER_NAME: &str = "xchacha20poly1305";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+// OWASP recommended argon2id parameters: 64 MiB, 3 passes.
+const ARGON2_M_COST: u32 = 64 * 1024;
+const ARGON2_T_COST: u32 = 3;
+const ARGON2_P_COST: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum KeystoreError {
+    #[error("unsupported keystore version {0}")]
+    UnsupportedVersion(u32),
+    #[error("unsupported keystore {kind} `{name}`")]
+    Unsupported { kind: &'static str, name: String },
+    #[error("invalid keystore {field}: {error}")]
+    Encoding {
+        field: &'static str,
+        error: base64::DecodeError,
+    },
+    #[error("key derivation failed: {0}")]
+    Kdf(argon2::Error),
+    #[error("wrong password or corrupted keystore")]
+    Decrypt,
+    #[error("keystore is for {expected}, but decrypts to {actual}")]
+    PubkeyMismatch { expected: String, actual: String },
+    #[error("passwords do not match")]
+    PasswordMismatch,
+    #[error("failed to read password: {0}")]
+    Password(std::io::Error),
+    #[error(transparent)]
+    Format(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub name: String,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub salt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub name: String,
+    pub nonce: String,
+}
+
+/// A keypair encrypted with a password.
+///
+/// The encryption key is derived from the password with argon2id, and the
+/// 64 secret key bytes are sealed with XChaCha20-Poly1305. The version and
+/// public key are authenticated as associated data, so they cannot be
+/// swapped without decryption failing.
+///
+/// Stored as JSON, which [`Keystore::detect`] tells apart from the plain
+/// Solana CLI byte array.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    pub pubkey: String,
+    pub kdf: KdfParams,
+    pub cipher: CipherParams,
+    pub ciphertext: String,
+}
+
+impl Keystore {
+    /// Encrypts `keypair` with `password`, using a fresh salt and nonce.
+    pub fn encrypt(keypair: &Keypair, password: &str) -> Result<Self, KeystoreError> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let kdf = KdfParams {
+            name: KDF_NAME.to_string(),
+            m_cost: ARGON2_M_COST,
+            t_cost: ARGON2_T_COST,
+            p_cost: ARGON2_P_COST,
+            salt: BASE64_STANDARD.encode(salt),
+        };
+        let pubkey = keypair.pubkey().to_string();
+        let key = derive_key(&kdf, password)?;
+        let secret = Zeroizing::new(keypair.to_bytes());
+
+        let ciphertext = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: secret.as_ref(),
+                    aad: &associated_data(KEYSTORE_VERSION, &pubkey),
+                },
+            )
+            .expect("encryption of a 64 byte message cannot fail");
+
+        Ok(Keystore {
+            version: KEYSTORE_VERSION,
+            pubkey,
+            kdf,
+            cipher: CipherParams {
+                name: CIPHER_NAME.to_string(),
+                nonce: BASE64_STANDARD.encode(nonce),
+            },
+            ciphertext: BASE64_STANDARD.encode(ciphertext),
+        })
+    }
+
+    /// Decrypts the keypair, checking it against the stored public key.
+    pub fn decrypt(&self, password: &str) -> Result<Keypair, KeystoreError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+        if self.cipher.name != CIPHER_NAME {
+            return Err(KeystoreError::Unsupported {
+                kind: "cipher",
+                name: self.cipher.name.clone(),
+            });
+        }
+
+        let nonce = decode("nonce", &self.cipher.nonce)?;
+        let ciphertext = decode("ciphertext", &self.ciphertext)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(KeystoreError::Decrypt);
+        }
+
+        let key = derive_key(&self.kdf, password)?;
+        let secret = Zeroizing::new(
+            XChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+                .decrypt(
+                    XNonce::from_slice(&nonce),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: &associated_data(self.version, &self.pubkey),
+                    },
+                )
+                .map_err(|_| KeystoreError::Decrypt)?,
+        );
+
+        let keypair = Keypair::from_bytes(&secret).map_err(|_| KeystoreError::Decrypt)?;
+        if keypair.pubkey().to_string() != self.pubkey {
+            return Err(KeystoreError::PubkeyMismatch {
+                expected: self.pubkey.clone(),
+                actual: keypair.pubkey().to_string(),
+            });
+        }
+
+        Ok(keypair)
+    }
+
+    /// Whether `contents` look like a keystore rather than a plain keypair.
+    pub fn detect(contents: &str) -> bool {
+        contents.trim_start().starts_with('{')
+    }
+
+    /// Parses a keystore from its JSON form.
+    pub fn from_json(contents: &str) -> Result<Self, KeystoreError> {
+        Ok(serde_json::from_str(contents)?)
+    }
+
+    /// Reads a keystore file.
+    pub fn read(path: &Path) -> Result<Self, KeystoreError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Writes the keystore to `path` as pretty-printed JSON.
+    ///
+    /// The file is written next to `path` and renamed over it, so that
+    /// replacing the only copy of a keystore never leaves it half written.
+    /// On Unix the file is only readable by its owner.
+    pub fn write(&self, path: &Path) -> Result<(), KeystoreError> {
+        let json = serde_json::to_string_pretty(self)?;
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        // A leftover from an interrupted write may have other permissions.
+        match std::fs::remove_file(&temporary) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => return Err(error.into()),
+            _ => {}
+        }
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(&temporary)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&temporary, path)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the password from `KEYSTORE_PASSWORD`, or asks for it.
+pub fn read_password(prompt: &str) -> Result<Zeroizing<String>, KeystoreError> {
+    password_from_env_or_prompt(KEYSTORE_PASSWORD_VAR, prompt, false)
+}
+
+/// Returns a new password from `KEYSTORE_NEW_PASSWORD`, or asks for it
+/// twice.
+pub fn read_new_password(prompt: &str) -> Result<Zeroizing<String>, KeystoreError> {
+    password_from_env_or_prompt(KEYSTORE_NEW_PASSWORD_VAR, prompt, true)
+}
+
+fn password_from_env_or_prompt(
+    var: &str,
+    prompt: &str,
+    confirm: bool,
+) -> Result<Zeroizing<String>, KeystoreError> {
+    if let Ok(password) = std::env::var(var) {
+        return Ok(Zeroizing::new(password));
+    }
+
+    let password = Zeroizing::new(
+        rpassword::prompt_password(prompt).map_err(KeystoreError::Password)?,
+    );
+    if confirm {
+        let again = Zeroizing::new(
+            rpassword::prompt_password("Repeat password: ").map_err(KeystoreError::Password)?,
+        );
+        if password != again {
+            return Err(KeystoreError::PasswordMismatch);
+        }
+    }
+
+    Ok(password)
+}
+
+fn derive_key(kdf: &KdfParams, password: &str) -> Result<Zeroizing<[u8; 32]>, KeystoreError> {
+    if kdf.name != KDF_NAME {
+        return Err(KeystoreError::Unsupported {
+            kind: "kdf",
+            name: kdf.name.clone(),
+        });
+    }
+
+    let salt = decode("salt", &kdf.salt)?;
+    let params =
+        Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, Some(32)).map_err(KeystoreError::Kdf)?;
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), &salt, key.as_mut())
+        .map_err(KeystoreError::Kdf)?;
+
+    Ok(key)
+}
+
+fn associated_data(version: u32, pubkey: &str) -> Vec<u8> {
+    format!("solana-keystore-v{version}:{pubkey}").into_bytes()
+}
+
+fn decode(field: &'static str, value: &str) -> Result<Vec<u8>, KeystoreError> {
+    BASE64_STANDARD
+        .decode(value)
+        .map_err(|error| KeystoreError::Encoding { field, error })
+}
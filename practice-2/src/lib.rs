@@ -1,37 +1,180 @@
 use std::env::VarError;
-use dotenv::dotenv;
 use solana_client::client_error::ClientError;
+use solana_program::hash::ParseHashError;
 use solana_program::pubkey::ParsePubkeyError;
-use solana_sdk::signature::Keypair;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
 use thiserror::Error;
 
+pub mod airdrop;
+pub mod amount;
+pub mod config;
+pub mod cost;
+pub mod decoder;
+pub mod encrypted_memo;
+pub mod escrow;
+pub mod fees;
+pub mod grind;
+pub mod history;
+pub mod keypair;
+pub mod keystore;
+pub mod lookup_table;
+pub mod metadata;
+pub mod mnemonic;
+pub mod nonblocking;
+pub mod nonce;
+pub mod offline;
+pub mod payout;
+pub mod portfolio;
+pub mod programs;
+pub mod remote_signer;
+pub mod report;
+pub mod sender;
+pub mod signer;
+pub mod simulation;
+pub mod sol;
+pub mod solana_pay;
+pub mod token;
+pub mod transaction;
+pub mod watch;
+
+pub use airdrop::{AirdropConfig, AirdropError};
+pub use amount::{Amount, AmountError};
+pub use config::{ClusterConfig, ConfigError};
+pub use cost::CostError;
+pub use decoder::DecodeError;
+pub use encrypted_memo::MemoError;
+pub use escrow::EscrowError;
+pub use fees::{FeeConfig, FeeError};
+pub use grind::GrindError;
+pub use keypair::{KeypairError, KeypairSource};
+pub use keystore::{Keystore, KeystoreError};
+pub use lookup_table::LookupTableError;
+pub use mnemonic::MnemonicError;
+pub use offline::OfflineError;
+pub use payout::PayoutError;
+pub use sender::{SendConfig, SendError};
+pub use signer::SignerSource;
+pub use solana_pay::PayError;
+pub use token::BatchError;
+pub use transaction::{TransactionConfig, TransactionOutcome};
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error(transparent)]
     ParsePubkey(#[from] ParsePubkeyError),
     #[error(transparent)]
-    Client(#[from] ClientError),
+    ParseHash(#[from] ParseHashError),
+    #[error(transparent)]
+    Client(Box<ClientError>),
+    #[error(transparent)]
+    Transaction(#[from] TransactionError),
     #[error(transparent)]
     Var(#[from] VarError),
     #[error(transparent)]
     BadKeypair(#[from] ed25519_dalek::SignatureError),
     #[error(transparent)]
+    Keypair(#[from] KeypairError),
+    #[error(transparent)]
+    Keystore(#[from] KeystoreError),
+    #[error(transparent)]
+    Signer(#[from] solana_sdk::signer::SignerError),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Grind(#[from] GrindError),
+    #[error(transparent)]
+    Mnemonic(#[from] MnemonicError),
+    #[error(transparent)]
+    Nonce(#[from] solana_client::nonce_utils::Error),
+    #[error(transparent)]
+    Offline(#[from] OfflineError),
+    #[error(transparent)]
+    LookupTable(#[from] LookupTableError),
+    #[error(transparent)]
+    Batch(#[from] BatchError),
+    #[error(transparent)]
+    Fee(#[from] FeeError),
+    #[error(transparent)]
+    Send(#[from] SendError),
+    #[error(transparent)]
+    Airdrop(#[from] AirdropError),
+    #[error(transparent)]
+    Amount(#[from] AmountError),
+    #[error(transparent)]
+    Escrow(#[from] EscrowError),
+    #[error(transparent)]
+    Cost(#[from] CostError),
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    #[error(transparent)]
+    Memo(#[from] MemoError),
+    #[error(transparent)]
+    Payout(#[from] PayoutError),
+    #[error(transparent)]
+    Pay(#[from] PayError),
+    #[error(transparent)]
     ProgramError(#[from] solana_sdk::program_error::ProgramError),
     #[error(transparent)]
     Serialization(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
 }
 
-/// Loads keypair from `.env` file.
-/// `.env` file is expected to be inside the current working directory.
-pub fn load_keypair() -> Result<Keypair, Error> {
-    dotenv().ok();
-    let secret_key = std::env::var("SECRET_KEY")?;
-
-    let secret_key_bytes: Vec<u8> = serde_json::from_str(&secret_key)?;
+impl Error {
+    /// A short code for the kind of error, stable across versions, for
+    /// the `--output json` error object.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::ParsePubkey(_) => "invalid_pubkey",
+            Error::ParseHash(_) => "invalid_blockhash",
+            Error::Client(_) => "rpc",
+            Error::Transaction(_) => "transaction",
+            Error::Var(_) => "missing_variable",
+            Error::BadKeypair(_) | Error::Keypair(_) => "keypair",
+            Error::Keystore(_) => "keystore",
+            Error::Signer(_) => "signer",
+            Error::Config(_) => "config",
+            Error::Grind(_) => "grind",
+            Error::Mnemonic(_) => "mnemonic",
+            Error::Nonce(_) => "nonce",
+            Error::Offline(_) => "offline_transaction",
+            Error::LookupTable(_) => "lookup_table",
+            Error::Batch(_) => "batch",
+            Error::Fee(_) => "fee",
+            Error::Send(_) => "send",
+            Error::Airdrop(_) => "airdrop",
+            Error::Amount(_) => "amount",
+            Error::Escrow(_) => "escrow",
+            Error::Cost(_) => "cost",
+            Error::Decode(_) => "decode",
+            Error::Memo(_) => "memo",
+            Error::Payout(_) => "payout",
+            Error::Pay(_) => "solana_pay",
+            Error::ProgramError(_) => "program",
+            Error::Serialization(_) => "serialization",
+            Error::Io(_) => "io",
+        }
+    }
+}
 
-    let secret_key_array: [u8; 64] = secret_key_bytes.try_into().expect("Expected 64 bytes");
+impl From<ClientError> for Error {
+    fn from(error: ClientError) -> Self {
+        Error::Client(Box::new(error))
+    }
+}
 
-    let keypair = Keypair::from_bytes(&secret_key_array)?;
+/// Loads keypair from the source picked by [`KeypairSource::discover`].
+/// With the bootcamp `.env` file in the current working directory this is
+/// the `SECRET_KEY` variable.
+pub fn load_keypair() -> Result<Keypair, Error> {
+    let keypair = KeypairSource::discover()?.load()?;
 
     Ok(keypair)
-}
\ No newline at end of file
+}
+
+/// Opens the signer picked by [`SignerSource::discover`]: a keypair as with
+/// [`load_keypair`], or a presigner or signing service given in `KEYPAIR`.
+pub fn load_signer() -> Result<Box<dyn Signer + Send + Sync>, Error> {
+    SignerSource::discover()?.signer()
+}
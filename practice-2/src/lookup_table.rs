@@ -0,0 +1,273 @@
+//! Address lookup tables.
+//!
+//! A v0 transaction can refer to accounts by a one byte index into a
+//! lookup table instead of by their 32 byte address, so far more accounts
+//! fit into one transaction. Helpers use tables through
+//! [`TransactionConfig::lookup_tables`] or
+//! [`crate::transaction::process_transaction_with_lookup_tables`].
+//!
+//! A table lives through these steps:
+//! 1. [`create_lookup_table`] creates an empty table;
+//! 2. [`extend_lookup_table`] appends addresses, which are usable from the
+//!    next slot on ([`wait_for_lookup_table`]);
+//! 3. [`deactivate_lookup_table`] stops it from being used;
+//! 4. [`close_lookup_table`] returns its rent once the deactivation has
+//!    cooled down, about 513 slots later.
+
+use std::thread::sleep;
+use std::time::Duration;
+
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use solana_sdk::address_lookup_table::instruction::{
+    close_lookup_table as close_lookup_table_instruction,
+    create_lookup_table as create_lookup_table_instruction,
+    deactivate_lookup_table as deactivate_lookup_table_instruction,
+    extend_lookup_table as extend_lookup_table_instruction,
+};
+use solana_sdk::address_lookup_table::state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES};
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::message::{CompileError, VersionedMessage};
+use solana_sdk::signature::Signer;
+use thiserror::Error;
+
+use crate::transaction::process_transaction;
+use crate::{TransactionConfig, TransactionOutcome};
+
+/// Addresses added by one extend transaction, which keeps it well below
+/// the packet size.
+pub const EXTEND_CHUNK: usize = 20;
+
+/// How often the slot is polled while waiting for a table to activate.
+const SLOT_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+#[derive(Debug, Error)]
+pub enum LookupTableError {
+    #[error("lookup table {0} does not exist")]
+    NotFound(Pubkey),
+    #[error("{address} is not a lookup table: {error}")]
+    Invalid {
+        address: Pubkey,
+        error: InstructionError,
+    },
+    #[error("a lookup table holds at most {LOOKUP_TABLE_MAX_ADDRESSES} addresses, {0} are needed")]
+    TooManyAddresses(usize),
+    #[error("failed to compile the v0 message: {0}")]
+    Compile(#[from] CompileError),
+    #[error("the transaction needs a lookup table, which cannot be created in sign-only mode")]
+    SignOnly,
+    #[error(
+        "{0} addresses take more than one extend transaction, which sign-only mode cannot \
+         write; extend by at most {EXTEND_CHUNK} at a time"
+    )]
+    SignOnlyChunks(usize),
+    #[error("the transaction needs a lookup table, which cannot be created in a dry run or an estimate")]
+    DryRun,
+}
+
+/// What a lookup table holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupTableInfo {
+    pub address: Pubkey,
+    /// `None` once the table is frozen.
+    pub authority: Option<Pubkey>,
+    pub addresses: Vec<Pubkey>,
+    /// Slot the table was deactivated in, `None` while it is active.
+    pub deactivation_slot: Option<u64>,
+    pub last_extended_slot: u64,
+}
+
+/// Creates an empty lookup table owned by `authority` and returns its
+/// address.
+pub fn create_lookup_table(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    authority: &Pubkey,
+    config: &TransactionConfig,
+) -> Result<(Pubkey, TransactionOutcome), crate::Error> {
+    // The table address is derived from a slot that has to be in the
+    // `SlotHashes` sysvar, which a finalized one always is.
+    let recent_slot = client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+    let (ix, address) = create_lookup_table_instruction(*authority, payer.pubkey(), recent_slot);
+
+    let outcome = process_transaction(client, &[ix], &[payer], config)?;
+
+    Ok((address, outcome))
+}
+
+/// Appends `addresses` to the table, [`EXTEND_CHUNK`] per transaction.
+///
+/// Sign-only mode writes a single transaction, so it is refused for more
+/// than [`EXTEND_CHUNK`] addresses.
+pub fn extend_lookup_table(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    table: &Pubkey,
+    authority: &dyn Signer,
+    addresses: &[Pubkey],
+    config: &TransactionConfig,
+) -> Result<Vec<TransactionOutcome>, crate::Error> {
+    if config.sign_only.is_some() && addresses.len() > EXTEND_CHUNK {
+        return Err(LookupTableError::SignOnlyChunks(addresses.len()).into());
+    }
+
+    addresses
+        .chunks(EXTEND_CHUNK)
+        .map(|chunk| {
+            let ix = extend_lookup_table_instruction(
+                *table,
+                authority.pubkey(),
+                Some(payer.pubkey()),
+                chunk.to_vec(),
+            );
+
+            process_transaction(client, &[ix], &[payer, authority], config)
+        })
+        .collect()
+}
+
+/// Deactivates the table. Transactions can no longer use it, and it can
+/// be closed once the deactivation has cooled down.
+pub fn deactivate_lookup_table(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    table: &Pubkey,
+    authority: &dyn Signer,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, crate::Error> {
+    let ix = deactivate_lookup_table_instruction(*table, authority.pubkey());
+
+    process_transaction(client, &[ix], &[payer, authority], config)
+}
+
+/// Closes a deactivated table, sending its rent to `recipient`.
+pub fn close_lookup_table(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    table: &Pubkey,
+    authority: &dyn Signer,
+    recipient: &Pubkey,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, crate::Error> {
+    let ix = close_lookup_table_instruction(*table, authority.pubkey(), *recipient);
+
+    process_transaction(client, &[ix], &[payer, authority], config)
+}
+
+/// Fetches and decodes the lookup table at `address`.
+pub fn get_lookup_table_info(
+    client: &RpcClient,
+    address: &Pubkey,
+) -> Result<LookupTableInfo, crate::Error> {
+    let account = client
+        .get_account_with_commitment(address, client.commitment())?
+        .value
+        .ok_or(LookupTableError::NotFound(*address))?;
+    let table = deserialize(address, &account)?;
+
+    Ok(LookupTableInfo {
+        address: *address,
+        authority: table.meta.authority,
+        addresses: table.addresses.to_vec(),
+        deactivation_slot: (table.meta.deactivation_slot != u64::MAX)
+            .then_some(table.meta.deactivation_slot),
+        last_extended_slot: table.meta.last_extended_slot,
+    })
+}
+
+/// Fetches the tables at `addresses`, to compile v0 messages with.
+pub fn fetch_lookup_tables(
+    client: &RpcClient,
+    addresses: &[Pubkey],
+) -> Result<Vec<AddressLookupTableAccount>, crate::Error> {
+    if addresses.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let accounts = client
+        .get_multiple_accounts_with_commitment(addresses, client.commitment())?
+        .value;
+
+    lookup_table_accounts(addresses, accounts)
+}
+
+/// Decodes the accounts fetched for the tables at `addresses`.
+pub(crate) fn lookup_table_accounts(
+    addresses: &[Pubkey],
+    accounts: Vec<Option<Account>>,
+) -> Result<Vec<AddressLookupTableAccount>, crate::Error> {
+    addresses
+        .iter()
+        .zip(accounts)
+        .map(|(address, account)| {
+            let account = account.ok_or(LookupTableError::NotFound(*address))?;
+
+            Ok(AddressLookupTableAccount {
+                key: *address,
+                addresses: deserialize(address, &account)?.addresses.to_vec(),
+            })
+        })
+        .collect()
+}
+
+/// All accounts of `message` in the order instructions refer to them:
+/// the static keys, then the writable and the readonly addresses loaded
+/// from `lookup_tables`. `None` if a table the message uses is missing or
+/// too short.
+pub fn account_keys(
+    message: &VersionedMessage,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Option<Vec<Pubkey>> {
+    let lookups = message.address_table_lookups().unwrap_or_default();
+    let loaded = |writable: bool| -> Option<Vec<Pubkey>> {
+        let mut addresses = Vec::new();
+        for lookup in lookups {
+            let table = lookup_tables
+                .iter()
+                .find(|table| table.key == lookup.account_key)?;
+            let indexes = if writable {
+                &lookup.writable_indexes
+            } else {
+                &lookup.readonly_indexes
+            };
+            for index in indexes {
+                addresses.push(*table.addresses.get(usize::from(*index))?);
+            }
+        }
+        Some(addresses)
+    };
+
+    Some(
+        [
+            message.static_account_keys().to_vec(),
+            loaded(true)?,
+            loaded(false)?,
+        ]
+        .concat(),
+    )
+}
+
+/// Waits until the addresses last added to the table can be used, which
+/// is from the slot after they were added.
+pub fn wait_for_lookup_table(client: &RpcClient, table: &Pubkey) -> Result<(), crate::Error> {
+    let last_extended_slot = get_lookup_table_info(client, table)?.last_extended_slot;
+
+    while client.get_slot()? <= last_extended_slot {
+        sleep(SLOT_POLL_INTERVAL);
+    }
+
+    Ok(())
+}
+
+fn deserialize<'a>(
+    address: &Pubkey,
+    account: &'a Account,
+) -> Result<AddressLookupTable<'a>, LookupTableError> {
+    AddressLookupTable::deserialize(&account.data).map_err(|error| LookupTableError::Invalid {
+        address: *address,
+        error,
+    })
+}
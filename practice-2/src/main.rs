@@ -0,0 +1,956 @@
+use std::cell::OnceCell;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use spl_associated_token_account::get_associated_token_address;
+
+use practice_2::amount::{format_sol, mint_decimals, token_amount};
+use practice_2::config::ClusterArgs;
+use practice_2::decoder::{decode_instructions, load_transaction, TransactionSource};
+use practice_2::encrypted_memo::{decrypt_history, MemoError};
+use practice_2::escrow::{
+    make_offer, mint_token_program, take_offer, vault_address, EscrowProgram, OfferTerms,
+};
+use practice_2::history::{get_history, write_csv, HistoryConfig};
+use practice_2::lookup_table::{
+    close_lookup_table, create_lookup_table, deactivate_lookup_table, extend_lookup_table,
+    get_lookup_table_info,
+};
+use practice_2::metadata::{create_token_metadata, metadata_address, TokenMetadata};
+use practice_2::payout::{read_payouts, send_payouts, PayoutEvent, DEFAULT_CONCURRENCY};
+use practice_2::portfolio::get_portfolio;
+use practice_2::report::{
+    DecodeReport, DecryptReport, ErrorReport, HistoryReport, LookupTableReport, OutputFormat,
+    PayRequestReport, PaymentReport, PayoutFileReport, PortfolioReport, Report,
+};
+use practice_2::sol::{send_sol_with_encrypted_memo, send_sol_with_memo};
+use practice_2::solana_pay::{find_payment, pay, TransferRequest};
+use practice_2::token::{
+    create_associated_token_account, create_token_mint, mint_tokens, mint_tokens_batch,
+};
+use practice_2::transaction::TransactionArgs;
+use practice_2::{
+    Amount, ClusterConfig, Error, PayoutError, SignerSource, TransactionConfig,
+    TransactionOutcome,
+};
+
+/// Practice 2: SOL transfers, SPL tokens, token metadata, lookup tables
+/// and the escrow programs of practice 3.
+#[derive(Parser)]
+struct Cli {
+    /// Fee payer and authority: a keypair file, `env:NAME`, `-`,
+    /// `cli-config`, a signing service URL or a bare pubkey for sign-only.
+    /// Defaults to `KEYPAIR` or the usual keypair lookup
+    #[arg(long, short = 'k', global = true)]
+    keypair: Option<SignerSource>,
+    /// Output format
+    #[arg(long, short = 'o', global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    #[command(flatten)]
+    cluster: ClusterArgs,
+    #[command(flatten)]
+    transaction: TransactionArgs,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// SOL transfers
+    #[command(subcommand)]
+    Sol(SolCommand),
+    /// SPL tokens
+    #[command(subcommand)]
+    Token(TokenCommand),
+    /// Address lookup tables for v0 transactions
+    #[command(subcommand)]
+    LookupTable(LookupTableCommand),
+    /// Token swaps through the escrow programs
+    #[command(subcommand)]
+    Escrow(EscrowCommand),
+    /// Solana Pay transfer requests
+    #[command(subcommand)]
+    Pay(PayCommand),
+    /// Lists SOL and every token account of an owner, the fee payer by
+    /// default, with token names, flags and closable empty accounts
+    Portfolio { owner: Option<Pubkey> },
+    /// Lists the recent transactions of an address, the fee payer by
+    /// default, with balance changes, memos and invoked programs
+    History {
+        address: Option<Pubkey>,
+        /// Most transactions to list
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Start with the transactions before this signature
+        #[arg(long)]
+        before: Option<Signature>,
+        /// Stop at this signature
+        #[arg(long)]
+        until: Option<Signature>,
+        /// Also write the transactions to this CSV file
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    /// Shows every instruction of a transaction in readable form: a
+    /// signature, or a base64 or base58 wire transaction
+    Decode { transaction: TransactionSource },
+    /// Decrypts the memos sent to and by the fee payer with `sol transfer
+    /// --encrypt`, found in its recent transactions
+    Decrypt {
+        /// Most transactions to scan
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Start with the transactions before this signature
+        #[arg(long)]
+        before: Option<Signature>,
+        /// Stop at this signature
+        #[arg(long)]
+        until: Option<Signature>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SolCommand {
+    /// Sends SOL, optionally with a memo
+    Transfer {
+        recipient: Pubkey,
+        /// Amount in SOL, e.g. 0.001
+        amount: Amount,
+        #[arg(long)]
+        memo: Option<String>,
+        /// Encrypt the memo so only the recipient can read it, see
+        /// `decrypt`. Needs a keypair, not a signing service
+        #[arg(long, requires = "memo")]
+        encrypt: bool,
+    },
+    /// Pays every row of a CSV file with the columns `recipient`, `amount`
+    /// (in SOL) and optionally `memo`, packing several transfers into each
+    /// transaction. An interrupted run resumes from the state file
+    Payout {
+        file: PathBuf,
+        /// Progress of the payouts, `<FILE>.state.json` by default
+        #[arg(long)]
+        state: Option<PathBuf>,
+        /// Transactions sent at the same time
+        #[arg(long, default_value_t = DEFAULT_CONCURRENCY)]
+        concurrency: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenCommand {
+    /// Creates a token mint with the fee payer as mint and freeze authority
+    CreateMint {
+        /// Signer for the mint account, e.g. a vanity keypair from
+        /// `practice-1 grind`. A fresh keypair is used if not given
+        #[arg(long)]
+        mint_keypair: Option<SignerSource>,
+        #[arg(long, default_value_t = 9)]
+        decimals: u8,
+    },
+    /// Mints tokens, to the fee payer's associated token account by default
+    Mint {
+        mint: Pubkey,
+        /// Amount in tokens, e.g. 1.5, counted with the decimals of the mint
+        amount: Amount,
+        /// Token account to mint to
+        #[arg(long)]
+        to: Option<Pubkey>,
+    },
+    /// Mints to the associated token accounts of many owners, creating
+    /// them as needed. Uses a new lookup table if the batch does not fit
+    /// into one transaction
+    MintBatch {
+        mint: Pubkey,
+        /// Owner and amount in tokens, as OWNER:AMOUNT
+        #[arg(required = true, value_parser = parse_recipient)]
+        recipients: Vec<(Pubkey, Amount)>,
+    },
+    /// Creates an associated token account, for the fee payer by default
+    CreateAccount {
+        mint: Pubkey,
+        #[arg(long)]
+        owner: Option<Pubkey>,
+    },
+    /// Token metadata
+    #[command(subcommand)]
+    Metadata(MetadataCommand),
+}
+
+#[derive(Subcommand)]
+enum MetadataCommand {
+    /// Creates the Metaplex metadata account of a mint
+    Create {
+        mint: Pubkey,
+        #[command(flatten)]
+        metadata: MetadataArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum LookupTableCommand {
+    /// Creates an empty lookup table
+    Create {
+        /// Authority of the table, the fee payer if not given
+        #[arg(long)]
+        authority: Option<Pubkey>,
+    },
+    /// Appends addresses to a lookup table
+    Extend {
+        table: Pubkey,
+        #[arg(required = true)]
+        addresses: Vec<Pubkey>,
+        #[command(flatten)]
+        authority: AuthorityArgs,
+    },
+    /// Deactivates a lookup table so it can be closed later
+    Deactivate {
+        table: Pubkey,
+        #[command(flatten)]
+        authority: AuthorityArgs,
+    },
+    /// Closes a deactivated lookup table, about 513 slots after deactivation
+    Close {
+        table: Pubkey,
+        /// Receives the rent, the fee payer if not given
+        #[arg(long)]
+        recipient: Option<Pubkey>,
+        #[command(flatten)]
+        authority: AuthorityArgs,
+    },
+    /// Shows the authority, state and addresses of a lookup table
+    Inspect { table: Pubkey },
+}
+
+#[derive(Subcommand)]
+enum EscrowCommand {
+    /// Offers tokens of one mint for tokens of another, as the fee payer
+    MakeOffer {
+        /// Mint of the offered tokens
+        token_mint_a: Pubkey,
+        /// Mint of the wanted tokens
+        token_mint_b: Pubkey,
+        /// Offered amount in tokens of mint A
+        #[arg(long)]
+        offered: Amount,
+        /// Wanted amount in tokens of mint B
+        #[arg(long)]
+        wanted: Amount,
+        /// Id of the offer, random if not given
+        #[arg(long)]
+        id: Option<u64>,
+        #[command(flatten)]
+        program: ProgramArgs,
+    },
+    /// Takes an offer as the fee payer, paying the wanted tokens
+    TakeOffer {
+        offer: Pubkey,
+        #[command(flatten)]
+        program: ProgramArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum PayCommand {
+    /// Prints a `solana:` URL asking for a payment to the recipient
+    Request {
+        recipient: Pubkey,
+        /// Amount in SOL, or in tokens with `--spl-token`
+        #[arg(long)]
+        amount: Option<Amount>,
+        /// Mint of the requested tokens, SOL if not given
+        #[arg(long)]
+        spl_token: Option<Pubkey>,
+        /// Key to find the payment by, may be repeated. A fresh one is
+        /// generated if not given
+        #[arg(long = "reference")]
+        references: Vec<Pubkey>,
+        /// Who asks for the payment
+        #[arg(long)]
+        label: Option<String>,
+        /// What the payment is for
+        #[arg(long)]
+        message: Option<String>,
+        /// Memo written on chain with the payment
+        #[arg(long)]
+        memo: Option<String>,
+    },
+    /// Pays a `solana:` URL as the fee payer
+    Send {
+        #[arg(value_name = "SOLANA_URL")]
+        request: TransferRequest,
+    },
+    /// Finds the transaction paying a `solana:` URL by its reference and
+    /// checks the recipient, amount, token and memo
+    Verify {
+        #[arg(value_name = "SOLANA_URL")]
+        request: TransferRequest,
+    },
+}
+
+#[derive(Args)]
+struct ProgramArgs {
+    /// Escrow program the offer belongs to
+    #[arg(long, value_enum, default_value_t = EscrowProgram::Escrow)]
+    program: EscrowProgram,
+}
+
+#[derive(Args)]
+struct AuthorityArgs {
+    /// Authority of the table, the fee payer if not given
+    #[arg(long)]
+    authority: Option<SignerSource>,
+}
+
+#[derive(Args)]
+struct MetadataArgs {
+    #[arg(long)]
+    name: String,
+    #[arg(long)]
+    symbol: String,
+    #[arg(long)]
+    uri: String,
+    /// Allow the update authority to change the metadata later
+    #[arg(long)]
+    mutable: bool,
+}
+
+impl Command {
+    /// The command as typed, for reports.
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Sol(SolCommand::Transfer { .. }) => "sol transfer",
+            Command::Sol(SolCommand::Payout { .. }) => "sol payout",
+            Command::Token(TokenCommand::CreateMint { .. }) => "token create-mint",
+            Command::Token(TokenCommand::Mint { .. }) => "token mint",
+            Command::Token(TokenCommand::MintBatch { .. }) => "token mint-batch",
+            Command::Token(TokenCommand::CreateAccount { .. }) => "token create-account",
+            Command::Token(TokenCommand::Metadata(MetadataCommand::Create { .. })) => {
+                "token metadata create"
+            }
+            Command::LookupTable(LookupTableCommand::Create { .. }) => "lookup-table create",
+            Command::LookupTable(LookupTableCommand::Extend { .. }) => "lookup-table extend",
+            Command::LookupTable(LookupTableCommand::Deactivate { .. }) => {
+                "lookup-table deactivate"
+            }
+            Command::LookupTable(LookupTableCommand::Close { .. }) => "lookup-table close",
+            Command::LookupTable(LookupTableCommand::Inspect { .. }) => "lookup-table inspect",
+            Command::Escrow(EscrowCommand::MakeOffer { .. }) => "escrow make-offer",
+            Command::Escrow(EscrowCommand::TakeOffer { .. }) => "escrow take-offer",
+            Command::Pay(PayCommand::Request { .. }) => "pay request",
+            Command::Pay(PayCommand::Send { .. }) => "pay send",
+            Command::Pay(PayCommand::Verify { .. }) => "pay verify",
+            Command::Portfolio { .. } => "portfolio",
+            Command::History { .. } => "history",
+            Command::Decode { .. } => "decode",
+            Command::Decrypt { .. } => "decrypt",
+        }
+    }
+}
+
+/// The fee payer, loaded when a command first needs it, so that read-only
+/// commands work without a wallet.
+struct Wallet<'a> {
+    source: Option<&'a SignerSource>,
+    loaded: OnceCell<(SignerSource, Payer)>,
+}
+
+enum Payer {
+    Keypair(Box<Keypair>),
+    Signer(Box<dyn Signer + Send + Sync>),
+}
+
+impl<'a> Wallet<'a> {
+    /// A wallet for `source`, discovered from the environment if `None`.
+    fn new(source: Option<&'a SignerSource>) -> Self {
+        Wallet {
+            source,
+            loaded: OnceCell::new(),
+        }
+    }
+
+    fn load(&self) -> Result<&(SignerSource, Payer), Error> {
+        if let Some(loaded) = self.loaded.get() {
+            return Ok(loaded);
+        }
+
+        let source = match self.source {
+            Some(source) => source.clone(),
+            None => SignerSource::discover()?,
+        };
+        // Loaded once, so that an encrypted keystore asks for its password once.
+        let payer = match &source {
+            SignerSource::Keypair(keypair) => Payer::Keypair(Box::new(keypair.load()?)),
+            _ => Payer::Signer(source.signer()?),
+        };
+        Ok(self.loaded.get_or_init(|| (source, payer)))
+    }
+
+    fn payer(&self) -> Result<&(dyn Signer + Send + Sync), Error> {
+        Ok(match self.load()? {
+            (_, Payer::Keypair(keypair)) => keypair.as_ref(),
+            (_, Payer::Signer(signer)) => signer.as_ref(),
+        })
+    }
+
+    /// The secret key itself, for commands that need more than a signer.
+    fn keypair(&self) -> Result<&Keypair, Error> {
+        match self.load()? {
+            (_, Payer::Keypair(keypair)) => Ok(keypair),
+            (source, Payer::Signer(_)) => Err(MemoError::NeedsKeypair(source.to_string()).into()),
+        }
+    }
+}
+
+/// A sent (or signed) transaction and the addresses it worked on, by role.
+type Sent = (TransactionOutcome, Vec<(&'static str, Pubkey)>);
+
+fn parse_recipient(recipient: &str) -> Result<(Pubkey, Amount), String> {
+    let (owner, amount) = recipient
+        .split_once(':')
+        .ok_or_else(|| format!("expected OWNER:AMOUNT, got `{recipient}`"))?;
+
+    Ok((
+        owner.parse().map_err(|error| format!("invalid owner: {error}"))?,
+        amount.parse::<Amount>().map_err(|error| error.to_string())?,
+    ))
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match (run(&cli), cli.output) {
+        (Ok(()), _) => return ExitCode::SUCCESS,
+        // The payout report already lists what was not paid.
+        (Err(Error::Payout(PayoutError::Incomplete { .. })), _) => {}
+        (Err(error), OutputFormat::Json) => {
+            match serde_json::to_string(&ErrorReport::new(cli.command.name(), &error)) {
+                Ok(report) => println!("{report}"),
+                Err(_) => eprintln!("Error: {error:?}"),
+            }
+        }
+        (Err(error), OutputFormat::Text) => eprintln!("Error: {error:?}"),
+    }
+
+    ExitCode::FAILURE
+}
+
+fn run(cli: &Cli) -> Result<(), Error> {
+    let client = ClusterConfig::resolve(&cli.cluster)?.rpc_client();
+    let config = TransactionConfig::resolve(&cli.transaction)?;
+    let wallet = Wallet::new(cli.keypair.as_ref());
+    let text = cli.output == OutputFormat::Text;
+
+    let sent: Vec<Sent> = match &cli.command {
+        Command::Sol(SolCommand::Transfer {
+            recipient,
+            amount,
+            memo,
+            encrypt,
+        }) => {
+            if text {
+                println!("💸 Attempting to send {amount} SOL to {recipient}...");
+            }
+
+            let outcome = match memo {
+                Some(memo) if *encrypt => send_sol_with_encrypted_memo(
+                    &client,
+                    wallet.keypair()?,
+                    recipient,
+                    amount.to_lamports()?,
+                    memo,
+                    &config,
+                )?,
+                _ => send_sol_with_memo(
+                    &client,
+                    wallet.payer()?,
+                    recipient,
+                    amount.to_lamports()?,
+                    memo.as_deref(),
+                    &config,
+                )?,
+            };
+
+            vec![(outcome, vec![("recipient", *recipient)])]
+        }
+        Command::Sol(SolCommand::Payout {
+            file,
+            state,
+            concurrency,
+        }) => {
+            let payouts = read_payouts(std::fs::File::open(file)?)?;
+            let state = state.clone().unwrap_or_else(|| {
+                let mut path = file.clone().into_os_string();
+                path.push(".state.json");
+                path.into()
+            });
+            if text {
+                let total: u64 = payouts.iter().map(|payout| payout.lamports).sum();
+                println!(
+                    "💸 Paying {} SOL to {} recipients, progress in {}...",
+                    format_sol(total),
+                    payouts.len(),
+                    state.display()
+                );
+            }
+
+            let (report, outcomes) = send_payouts(
+                &client,
+                wallet.payer()?,
+                &payouts,
+                &state,
+                *concurrency,
+                &config,
+                |event| match (text, event) {
+                    (true, PayoutEvent::Paid { lines, signature }) => {
+                        println!("✅ Lines {lines:?} paid: {signature}")
+                    }
+                    (true, PayoutEvent::Error { lines, error }) => {
+                        println!("❌ Lines {lines:?}: {error}")
+                    }
+                    (false, _) => {}
+                },
+            )?;
+
+            if outcomes.is_empty() {
+                match cli.output {
+                    OutputFormat::Text => println!("📋 {report}"),
+                    OutputFormat::Json => println!(
+                        "{}",
+                        serde_json::to_string(&PayoutFileReport::new(cli.command.name(), &report))?
+                    ),
+                }
+                if !report.is_complete() {
+                    return Err(PayoutError::Incomplete { unpaid: report.unpaid.len() }.into());
+                }
+            }
+            outcomes.into_iter().map(|outcome| (outcome, Vec::new())).collect()
+        }
+        Command::Token(TokenCommand::CreateMint {
+            mint_keypair,
+            decimals,
+        }) => {
+            let mint: Box<dyn Signer> = match mint_keypair {
+                Some(source) => source.signer()?,
+                None => Box::new(Keypair::new()),
+            };
+
+            let outcome =
+                create_token_mint(&client, wallet.payer()?, mint.as_ref(), *decimals, &config)?;
+
+            if text {
+                println!("✅ Token Mint Pubkey: {}", mint.pubkey());
+            }
+            vec![(outcome, vec![("mint", mint.pubkey())])]
+        }
+        Command::Token(TokenCommand::Mint { mint, amount, to }) => {
+            let payer = wallet.payer()?;
+            let ata = to.unwrap_or_else(|| get_associated_token_address(&payer.pubkey(), mint));
+
+            let amount = token_amount(&client, mint, *amount)?;
+
+            let outcome = mint_tokens(&client, mint, payer, &ata, amount, &config)?;
+
+            vec![(outcome, vec![("mint", *mint), ("token_account", ata)])]
+        }
+        Command::Token(TokenCommand::MintBatch { mint, recipients }) => {
+            let decimals = mint_decimals(&client, mint)?;
+            let recipients = recipients
+                .iter()
+                .map(|(owner, amount)| Ok((*owner, amount.to_base_units(decimals)?)))
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            let batch = mint_tokens_batch(&client, mint, wallet.payer()?, &recipients, &config)?;
+
+            let mut accounts = vec![("mint", *mint)];
+            if let Some(table) = batch.lookup_table {
+                if text {
+                    println!("Lookup table: {table}");
+                }
+                accounts.push(("lookup_table", table));
+            }
+            batch
+                .outcomes
+                .into_iter()
+                .map(|outcome| (outcome, accounts.clone()))
+                .collect()
+        }
+        Command::Token(TokenCommand::CreateAccount { mint, owner }) => {
+            let payer = wallet.payer()?;
+            let owner = owner.unwrap_or(payer.pubkey());
+            let ata = get_associated_token_address(&owner, mint);
+
+            let outcome =
+                create_associated_token_account(&client, mint, &owner, payer, &config)?;
+
+            if text {
+                println!("Token account: {ata}");
+            }
+            vec![(outcome, vec![("mint", *mint), ("owner", owner), ("token_account", ata)])]
+        }
+        Command::Token(TokenCommand::Metadata(MetadataCommand::Create { mint, metadata })) => {
+            let outcome = create_token_metadata(
+                &client,
+                mint,
+                wallet.payer()?,
+                TokenMetadata {
+                    name: metadata.name.clone(),
+                    symbol: metadata.symbol.clone(),
+                    uri: metadata.uri.clone(),
+                },
+                metadata.mutable,
+                &config,
+            )?;
+
+            if text {
+                println!("Metadata account: {}", metadata_address(mint));
+            }
+            vec![(outcome, vec![("mint", *mint), ("metadata", metadata_address(mint))])]
+        }
+        Command::LookupTable(command) => {
+            run_lookup_table(cli, &client, &wallet, command, &config)?
+        }
+        Command::Portfolio { owner } => {
+            let owner = match owner {
+                Some(owner) => *owner,
+                None => wallet.payer()?.pubkey(),
+            };
+            let portfolio = get_portfolio(&client, &owner)?;
+
+            match cli.output {
+                OutputFormat::Text => println!("{portfolio}"),
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string(&PortfolioReport::new(cli.command.name(), &portfolio))?
+                ),
+            }
+            Vec::new()
+        }
+        Command::History {
+            address,
+            limit,
+            before,
+            until,
+            csv,
+        } => {
+            let address = match address {
+                Some(address) => *address,
+                None => wallet.payer()?.pubkey(),
+            };
+            let history = HistoryConfig {
+                limit: *limit,
+                before: *before,
+                until: *until,
+            };
+            let entries = get_history(&client, &address, &history)?;
+
+            if let Some(path) = csv {
+                write_csv(&entries, std::fs::File::create(path)?)?;
+            }
+            match cli.output {
+                OutputFormat::Text => {
+                    if entries.is_empty() {
+                        println!("No transactions for {address}");
+                    }
+                    for entry in &entries {
+                        println!("{entry}");
+                    }
+                    if let Some(path) = csv {
+                        println!("📄 Written to {}", path.display());
+                    }
+                }
+                OutputFormat::Json => {
+                    let report = HistoryReport::new(cli.command.name(), &address, &entries);
+                    println!("{}", serde_json::to_string(&report)?);
+                }
+            }
+            Vec::new()
+        }
+        Command::Decode { transaction } => {
+            let (transaction, keys) = load_transaction(&client, transaction)?;
+            let instructions = decode_instructions(&transaction.message, &keys);
+
+            match cli.output {
+                OutputFormat::Text => {
+                    println!("Signature: {}", transaction.signatures[0]);
+                    println!("Fee payer: {}", keys[0]);
+                    println!("Instructions:");
+                    for (index, instruction) in instructions.iter().enumerate() {
+                        println!("  #{} {instruction}", index + 1);
+                    }
+                }
+                OutputFormat::Json => {
+                    let signature = &transaction.signatures[0];
+                    let report = DecodeReport::new(cli.command.name(), signature, &instructions);
+                    println!("{}", serde_json::to_string(&report)?);
+                }
+            }
+            Vec::new()
+        }
+        Command::Decrypt {
+            limit,
+            before,
+            until,
+        } => {
+            let keypair = wallet.keypair()?;
+            let history = HistoryConfig {
+                limit: *limit,
+                before: *before,
+                until: *until,
+            };
+            let memos = decrypt_history(&client, keypair, &history)?;
+
+            match cli.output {
+                OutputFormat::Text => {
+                    if memos.is_empty() {
+                        println!("No encrypted memos for {}", keypair.pubkey());
+                    }
+                    for memo in &memos {
+                        println!("{memo}");
+                    }
+                }
+                OutputFormat::Json => {
+                    let report = DecryptReport::new(cli.command.name(), &keypair.pubkey(), &memos);
+                    println!("{}", serde_json::to_string(&report)?);
+                }
+            }
+            Vec::new()
+        }
+        Command::Escrow(EscrowCommand::MakeOffer {
+            token_mint_a,
+            token_mint_b,
+            offered,
+            wanted,
+            id,
+            program: ProgramArgs { program },
+        }) => {
+            let terms = OfferTerms {
+                id: id.unwrap_or_else(rand::random),
+                token_mint_a: *token_mint_a,
+                token_mint_b: *token_mint_b,
+                token_a_offered_amount: token_amount(&client, token_mint_a, *offered)?,
+                token_b_wanted_amount: token_amount(&client, token_mint_b, *wanted)?,
+            };
+
+            let (offer, outcome) = make_offer(&client, *program, wallet.payer()?, &terms, &config)?;
+
+            if text {
+                println!("Offer {}: {offer}", terms.id);
+            }
+            let mut accounts = vec![
+                ("offer", offer),
+                ("token_mint_a", *token_mint_a),
+                ("token_mint_b", *token_mint_b),
+            ];
+            if *program == EscrowProgram::Escrow {
+                let token_program = mint_token_program(&client, token_mint_a)?;
+                accounts.push(("vault", vault_address(&offer, token_mint_a, &token_program)));
+            }
+            vec![(outcome, accounts)]
+        }
+        Command::Escrow(EscrowCommand::TakeOffer {
+            offer,
+            program: ProgramArgs { program },
+        }) => {
+            let (taken, outcome) = take_offer(&client, *program, wallet.payer()?, offer, &config)?;
+
+            vec![(
+                outcome,
+                vec![
+                    ("offer", *offer),
+                    ("maker", taken.maker),
+                    ("token_mint_a", taken.token_mint_a),
+                    ("token_mint_b", taken.token_mint_b),
+                ],
+            )]
+        }
+        Command::Pay(PayCommand::Request {
+            recipient,
+            amount,
+            spl_token,
+            references,
+            label,
+            message,
+            memo,
+        }) => {
+            let mut references = references.clone();
+            if references.is_empty() {
+                references.push(Keypair::new().pubkey());
+            }
+            let request = TransferRequest {
+                amount: *amount,
+                spl_token: *spl_token,
+                references,
+                label: label.clone(),
+                message: message.clone(),
+                memo: memo.clone(),
+                ..TransferRequest::new(*recipient)
+            };
+
+            match cli.output {
+                OutputFormat::Text => {
+                    println!("🔗 {request}");
+                    for reference in &request.references {
+                        println!("Reference: {reference}");
+                    }
+                }
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string(&PayRequestReport::new(cli.command.name(), &request))?
+                ),
+            }
+            Vec::new()
+        }
+        Command::Pay(PayCommand::Send { request }) => {
+            if text {
+                let amount = request
+                    .amount
+                    .map_or_else(|| "?".to_string(), |amount| amount.to_string());
+                println!(
+                    "💸 Paying {amount} {} to {}...",
+                    request.currency(),
+                    request.recipient
+                );
+                for field in [&request.label, &request.message].into_iter().flatten() {
+                    println!("   {field}");
+                }
+            }
+
+            let outcome = pay(&client, wallet.payer()?, request, &config)?;
+
+            let mut accounts = vec![("recipient", request.recipient)];
+            accounts.extend(request.spl_token.map(|mint| ("mint", mint)));
+            accounts.extend(
+                request
+                    .references
+                    .first()
+                    .map(|reference| ("reference", *reference)),
+            );
+            vec![(outcome, accounts)]
+        }
+        Command::Pay(PayCommand::Verify { request }) => {
+            let payment = find_payment(&client, request)?;
+
+            match cli.output {
+                OutputFormat::Text => println!("✅ Paid: {payment}"),
+                OutputFormat::Json => {
+                    let report = PaymentReport::new(cli.command.name(), request, &payment);
+                    println!("{}", serde_json::to_string(&report)?);
+                }
+            }
+            Vec::new()
+        }
+    };
+
+    for (outcome, accounts) in sent {
+        match cli.output {
+            OutputFormat::Text => match outcome {
+                TransactionOutcome::Simulated(_) => println!("🧪 {outcome}"),
+                TransactionOutcome::Estimated(_) => println!("💰 {outcome}"),
+                _ => println!("✅ {outcome}"),
+            },
+            OutputFormat::Json => {
+                let report = Report::new(&client, cli.command.name(), &outcome, &accounts);
+                println!("{}", serde_json::to_string(&report)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_lookup_table(
+    cli: &Cli,
+    client: &RpcClient,
+    wallet: &Wallet,
+    command: &LookupTableCommand,
+    config: &TransactionConfig,
+) -> Result<Vec<Sent>, Error> {
+    let authority = |args: &AuthorityArgs| -> Result<Option<Box<dyn Signer + Send + Sync>>, Error> {
+        args.authority.as_ref().map(SignerSource::signer).transpose()
+    };
+
+    let sent = match command {
+        LookupTableCommand::Create { authority } => {
+            let payer: &dyn Signer = wallet.payer()?;
+            let authority = authority.unwrap_or(payer.pubkey());
+            let (table, outcome) = create_lookup_table(client, payer, &authority, config)?;
+
+            if cli.output == OutputFormat::Text {
+                println!("✅ Lookup table: {table}");
+            }
+            vec![(outcome, vec![("lookup_table", table), ("authority", authority)])]
+        }
+        LookupTableCommand::Extend {
+            table,
+            addresses,
+            authority: args,
+        } => {
+            let payer: &dyn Signer = wallet.payer()?;
+            let signer = authority(args)?;
+            let signer = signer.as_deref().map_or(payer, |signer| signer as &dyn Signer);
+
+            extend_lookup_table(client, payer, table, signer, addresses, config)?
+                .into_iter()
+                .map(|outcome| (outcome, vec![("lookup_table", *table)]))
+                .collect()
+        }
+        LookupTableCommand::Deactivate {
+            table,
+            authority: args,
+        } => {
+            let payer: &dyn Signer = wallet.payer()?;
+            let signer = authority(args)?;
+            let signer = signer.as_deref().map_or(payer, |signer| signer as &dyn Signer);
+
+            let outcome = deactivate_lookup_table(client, payer, table, signer, config)?;
+            vec![(outcome, vec![("lookup_table", *table)])]
+        }
+        LookupTableCommand::Close {
+            table,
+            recipient,
+            authority: args,
+        } => {
+            let payer: &dyn Signer = wallet.payer()?;
+            let signer = authority(args)?;
+            let signer = signer.as_deref().map_or(payer, |signer| signer as &dyn Signer);
+            let recipient = recipient.unwrap_or(payer.pubkey());
+
+            let outcome = close_lookup_table(client, payer, table, signer, &recipient, config)?;
+            vec![(outcome, vec![("lookup_table", *table), ("recipient", recipient)])]
+        }
+        LookupTableCommand::Inspect { table } => {
+            let info = get_lookup_table_info(client, table)?;
+
+            match cli.output {
+                OutputFormat::Text => {
+                    println!("Lookup table: {}", info.address);
+                    match info.authority {
+                        Some(authority) => println!("Authority: {authority}"),
+                        None => println!("Authority: none (frozen)"),
+                    }
+                    match info.deactivation_slot {
+                        Some(slot) => println!("Deactivated in slot {slot}"),
+                        None => println!("Active"),
+                    }
+                    println!("Last extended in slot {}", info.last_extended_slot);
+                    println!("Addresses ({}):", info.addresses.len());
+                    for (index, address) in info.addresses.iter().enumerate() {
+                        println!("  {index:>3} {address}");
+                    }
+                }
+                OutputFormat::Json => {
+                    let report = LookupTableReport::new(cli.command.name(), &info);
+                    println!("{}", serde_json::to_string(&report)?);
+                }
+            }
+            Vec::new()
+        }
+    };
+
+    Ok(sent)
+}
@@ -0,0 +1,86 @@
+use mpl_token_metadata::instructions::{CreateMetadataAccountV3, CreateMetadataAccountV3InstructionArgs};
+use mpl_token_metadata::types::DataV2;
+use solana_client::rpc_client::RpcClient;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+use crate::transaction::process_transaction;
+use crate::{Error, TransactionConfig, TransactionOutcome};
+
+/// Name, symbol and URI of a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// Address of the Metaplex metadata account of `mint`.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    mpl_token_metadata::accounts::Metadata::find_pda(mint).0
+}
+
+/// Name, symbol and URI stored in a Metaplex metadata account, without
+/// the zero padding of older accounts.
+pub fn decode_metadata(data: &[u8]) -> Option<TokenMetadata> {
+    let metadata = mpl_token_metadata::accounts::Metadata::safe_deserialize(data).ok()?;
+    let trim = |value: String| value.trim_end_matches('\0').to_string();
+
+    Some(TokenMetadata {
+        name: trim(metadata.name),
+        symbol: trim(metadata.symbol),
+        uri: trim(metadata.uri),
+    })
+}
+
+/// Instruction of [`create_token_metadata`].
+pub fn create_token_metadata_instruction(
+    mint: &Pubkey,
+    payer: &Pubkey,
+    metadata: TokenMetadata,
+    is_mutable: bool,
+) -> Instruction {
+    let metadata_data = DataV2 {
+        name: metadata.name,
+        symbol: metadata.symbol,
+        uri: metadata.uri,
+        seller_fee_basis_points: 0,
+        creators: None,
+        collection: None,
+        uses: None,
+    };
+
+    let args = CreateMetadataAccountV3InstructionArgs {
+        data: metadata_data,
+        is_mutable,
+        collection_details: None,
+    };
+
+    let create_metadata_account_instruction = CreateMetadataAccountV3 {
+        metadata: metadata_address(mint),
+        mint: *mint,
+        mint_authority: *payer,
+        payer: *payer,
+        update_authority: (*payer, false),
+        system_program: solana_program::system_program::id(),
+        rent: None,
+    };
+
+    create_metadata_account_instruction.instruction(args)
+}
+
+/// Creates the Metaplex metadata account of `mint`, with `payer` as mint
+/// and update authority.
+pub fn create_token_metadata(
+    client: &RpcClient,
+    mint: &Pubkey,
+    payer: &dyn Signer,
+    metadata: TokenMetadata,
+    is_mutable: bool,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let ix = create_token_metadata_instruction(mint, &payer.pubkey(), metadata, is_mutable);
+
+    process_transaction(client, &[ix], &[payer], config)
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bip39::{Language, MnemonicType, Seed};
+use solana_sdk::derivation_path::{DerivationPath, DerivationPathError};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::keypair::keypair_from_seed_and_derivation_path;
+use thiserror::Error;
+
+pub use bip39::Mnemonic;
+
+/// Derivation path used by Phantom and Solflare, `n` is the account index.
+pub const DEFAULT_DERIVATION_TEMPLATE: &str = "m/44'/501'/n'/0'";
+
+#[derive(Debug, Error)]
+pub enum MnemonicError {
+    #[error("unsupported seed phrase length {0}, expected 12 or 24 words")]
+    WordCount(usize),
+    #[error("invalid seed phrase: {0}")]
+    InvalidPhrase(String),
+    #[error("derivation path `{0}` has no `n` placeholder for the account index")]
+    NoAccountIndex(String),
+    #[error(transparent)]
+    DerivationPath(#[from] DerivationPathError),
+    #[error("key derivation failed: {0}")]
+    Derivation(String),
+}
+
+/// Generates a new English BIP39 seed phrase of 12 or 24 words.
+pub fn generate_mnemonic(word_count: usize) -> Result<Mnemonic, MnemonicError> {
+    let mnemonic_type = match word_count {
+        12 => MnemonicType::Words12,
+        24 => MnemonicType::Words24,
+        _ => return Err(MnemonicError::WordCount(word_count)),
+    };
+
+    Ok(Mnemonic::new(mnemonic_type, Language::English))
+}
+
+/// Parses an existing English BIP39 seed phrase, checking its checksum.
+pub fn parse_mnemonic(phrase: &str) -> Result<Mnemonic, MnemonicError> {
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    Mnemonic::from_phrase(&phrase, Language::English)
+        .map_err(|error| MnemonicError::InvalidPhrase(error.to_string()))
+}
+
+/// A derivation path where `n` stands for the account index,
+/// e.g. `m/44'/501'/n'/0'`.
+///
+/// All levels are hardened, as SLIP-10 only defines hardened derivation
+/// for ed25519 keys. A template without `n` is a single fixed path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationTemplate(String);
+
+impl DerivationTemplate {
+    /// Returns the path of account `index`.
+    pub fn path(&self, index: u32) -> Result<DerivationPath, MnemonicError> {
+        let path = self.0.replace('n', &index.to_string());
+
+        Ok(DerivationPath::from_absolute_path_str(&path)?)
+    }
+
+    /// Whether the template contains the `n` placeholder.
+    pub fn has_account_index(&self) -> bool {
+        self.0.contains('n')
+    }
+}
+
+impl Default for DerivationTemplate {
+    fn default() -> Self {
+        DerivationTemplate(DEFAULT_DERIVATION_TEMPLATE.to_string())
+    }
+}
+
+impl FromStr for DerivationTemplate {
+    type Err = MnemonicError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let template = DerivationTemplate(s.to_string());
+        // Validate the shape once, so later `path` calls only fail on overflow.
+        template.path(0)?;
+
+        Ok(template)
+    }
+}
+
+impl fmt::Display for DerivationTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Writes `path` the way wallets show it, e.g. `m/44'/501'/0'/0'`.
+pub fn format_path(path: &DerivationPath) -> String {
+    path.path()
+        .iter()
+        .fold("m".to_string(), |text, index| format!("{text}/{index}"))
+}
+
+/// A BIP39 seed that keypairs are derived from with SLIP-10.
+pub struct SeedWallet {
+    seed: Seed,
+}
+
+impl SeedWallet {
+    /// Creates the seed from a phrase and an optional (possibly empty)
+    /// BIP39 passphrase.
+    pub fn new(mnemonic: &Mnemonic, passphrase: &str) -> Self {
+        SeedWallet {
+            seed: Seed::new(mnemonic, passphrase),
+        }
+    }
+
+    /// Derives the keypair at `path`.
+    pub fn keypair(&self, path: &DerivationPath) -> Result<Keypair, MnemonicError> {
+        keypair_from_seed_and_derivation_path(self.seed.as_bytes(), Some(path.clone()))
+            .map_err(|error| MnemonicError::Derivation(error.to_string()))
+    }
+
+    /// Derives accounts `0..count` from `template`.
+    ///
+    /// The same phrase, passphrase and template always give the same
+    /// keypairs, which is what wallets rely on for recovery.
+    pub fn accounts(
+        &self,
+        template: &DerivationTemplate,
+        count: u32,
+    ) -> Result<Vec<(DerivationPath, Keypair)>, MnemonicError> {
+        if count > 1 && !template.has_account_index() {
+            return Err(MnemonicError::NoAccountIndex(template.to_string()));
+        }
+
+        (0..count)
+            .map(|index| {
+                let path = template.path(index)?;
+                let keypair = self.keypair(&path)?;
+                Ok((path, keypair))
+            })
+            .collect()
+    }
+}
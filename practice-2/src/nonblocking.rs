@@ -0,0 +1,377 @@
+//! Async versions of the helpers, on the nonblocking [`RpcClient`], for
+//! callers that run on tokio.
+//!
+//! They build the same instructions as the blocking helpers and follow
+//! the same [`TransactionConfig`] and send with the same retries as
+//! [`crate::sender`]. Signers are `Sync` so the futures are
+//! `Send` and can be spawned. Signing stays synchronous: a
+//! [`crate::remote_signer::RemoteSigner`] blocks on HTTP, so use it from
+//! `spawn_blocking` or stick to local keypairs here.
+
+use std::time::Instant;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::nonce_utils;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::Response;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::TransactionStatus;
+use spl_token::state::Mint;
+use tokio::time::sleep;
+
+use crate::cost::{fee_for_message_params, CostEstimate, CostPlan};
+use crate::fees::{self, ComputeUnitLimit, PriorityFee};
+use crate::lookup_table::lookup_table_accounts;
+use crate::metadata::{create_token_metadata_instruction, TokenMetadata};
+use crate::sender::{expired_result, status_response, Attempt, SendConfig, POLL_INTERVAL};
+use crate::simulation::{self, AccountStates, Simulation};
+use crate::sol::send_sol_with_memo_instructions;
+use crate::token::{
+    create_associated_token_account_instruction, create_token_mint_instructions,
+    mint_tokens_instruction,
+};
+use crate::transaction::{write_if_sign_only, PreparedTransaction};
+use crate::{Error, TransactionConfig, TransactionOutcome};
+
+/// Async [`crate::transaction::process_transaction`].
+pub async fn process_transaction(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    signers: &[&(dyn Signer + Sync)],
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let lookup_tables = fetch_lookup_tables(client, &config.lookup_tables).await?;
+
+    process_transaction_with_lookup_tables(client, instructions, signers, &lookup_tables, config)
+        .await
+}
+
+/// Async [`crate::transaction::process_transaction_with_lookup_tables`].
+pub async fn process_transaction_with_lookup_tables(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    signers: &[&(dyn Signer + Sync)],
+    lookup_tables: &[AddressLookupTableAccount],
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let prepare = Prepare {
+        instructions,
+        signers,
+        lookup_tables,
+        config,
+    };
+    let (blockhash, last_valid_block_height) = match (config.blockhash, &config.nonce) {
+        (Some(blockhash), _) => (blockhash, None),
+        (None, Some(nonce)) => (nonce_blockhash(client, &nonce.account).await?, None),
+        (None, None) => {
+            let (blockhash, last_valid) = client
+                .get_latest_blockhash_with_commitment(client.commitment())
+                .await?;
+            (blockhash, Some(last_valid))
+        }
+    };
+
+    let fees = &config.fees;
+    let price = match fees.priority_fee {
+        PriorityFee::None => None,
+        PriorityFee::Fixed(price) => Some(price),
+        PriorityFee::Percentile(percentile) => {
+            let accounts = prepare.with(|prepared| prepared.writable_accounts());
+            let recent = client.get_recent_prioritization_fees(&accounts).await?;
+            Some(fees::recent_fee_percentile(&recent, percentile))
+        }
+    };
+    let limit = match fees.compute_unit_limit {
+        ComputeUnitLimit::Default => None,
+        ComputeUnitLimit::Fixed(limit) => Some(limit),
+        ComputeUnitLimit::Simulate => {
+            let unsigned = prepare.with(|prepared| {
+                prepared.set_compute_budget(Some(fees::MAX_COMPUTE_UNIT_LIMIT), price);
+                prepared.unsigned(blockhash)
+            })?;
+            let result = client
+                .simulate_transaction_with_config(
+                    &unsigned,
+                    fees::simulation_config(client.commitment()),
+                )
+                .await?;
+            Some(fees::simulated_limit(result.value, fees.compute_unit_margin)?)
+        }
+    };
+
+    if config.estimate || config.dry_run {
+        let unsigned = prepare.with(|prepared| {
+            prepared.set_compute_budget(limit, price);
+            prepared.unsigned(blockhash)
+        })?;
+        if config.estimate {
+            let estimate = estimate_cost(client, &unsigned, lookup_tables).await?;
+            return Ok(TransactionOutcome::Estimated(Box::new(estimate)));
+        }
+        let simulation = simulate(client, &unsigned, lookup_tables).await?;
+        return Ok(TransactionOutcome::Simulated(Box::new(simulation)));
+    }
+    let sign = |blockhash| {
+        prepare.with(|prepared| {
+            prepared.set_compute_budget(limit, price);
+            prepared.sign(blockhash)
+        })
+    };
+    let transaction = sign(blockhash)?;
+
+    if let Some(outcome) = write_if_sign_only(&transaction, config)? {
+        return Ok(outcome);
+    }
+    let signature = send_and_confirm(
+        client,
+        sign,
+        transaction,
+        last_valid_block_height,
+        &config.send,
+    )
+    .await?;
+
+    Ok(TransactionOutcome::Confirmed(signature))
+}
+
+/// What a [`PreparedTransaction`] is made of, with `Sync` signers so it
+/// can be held across awaits.
+struct Prepare<'a> {
+    instructions: &'a [Instruction],
+    signers: &'a [&'a (dyn Signer + Sync)],
+    lookup_tables: &'a [AddressLookupTableAccount],
+    config: &'a TransactionConfig,
+}
+
+impl Prepare<'_> {
+    /// Runs `f` on the prepared transaction. `f` cannot await, so
+    /// `&dyn Signer` (not `Send`) never ends up in a future.
+    fn with<R>(&self, f: impl FnOnce(&mut PreparedTransaction) -> R) -> R {
+        let signers: Vec<&dyn Signer> =
+            self.signers.iter().map(|signer| *signer as &dyn Signer).collect();
+
+        f(&mut PreparedTransaction::new(
+            self.instructions,
+            &signers,
+            self.lookup_tables,
+            self.config,
+        ))
+    }
+}
+
+/// Async [`crate::lookup_table::fetch_lookup_tables`].
+pub async fn fetch_lookup_tables(
+    client: &RpcClient,
+    addresses: &[Pubkey],
+) -> Result<Vec<AddressLookupTableAccount>, Error> {
+    if addresses.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let accounts = client
+        .get_multiple_accounts_with_commitment(addresses, client.commitment())
+        .await?
+        .value;
+
+    lookup_table_accounts(addresses, accounts)
+}
+
+/// Async [`crate::cost::estimate_cost`].
+pub async fn estimate_cost(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<CostEstimate, Error> {
+    let plan = CostPlan::new(&transaction.message, lookup_tables)?;
+    let accounts = client
+        .get_multiple_accounts_with_commitment(&plan.addresses(), client.commitment())
+        .await?
+        .value;
+    let fee = client
+        .send::<Response<Option<u64>>>(
+            RpcRequest::GetFeeForMessage,
+            fee_for_message_params(&transaction.message, client.commitment()),
+        )
+        .await?
+        .value;
+
+    Ok(plan.estimate(accounts, fee)?)
+}
+
+/// Async [`crate::simulation::simulate`].
+pub async fn simulate(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Simulation, Error> {
+    let addresses = simulation::writable_accounts(&transaction.message, lookup_tables);
+    let pre = client
+        .get_multiple_accounts_with_commitment(&addresses, client.commitment())
+        .await?
+        .value;
+    let result = client
+        .simulate_transaction_with_config(
+            transaction,
+            simulation::simulation_config(client.commitment(), &addresses),
+        )
+        .await?
+        .value;
+
+    let states = AccountStates::new(addresses, pre, &result);
+    let mints = states.unknown_mints();
+    let mint_accounts = if mints.is_empty() {
+        Vec::new()
+    } else {
+        client
+            .get_multiple_accounts_with_commitment(&mints, client.commitment())
+            .await?
+            .value
+    };
+
+    Ok(states.simulation(result, &mints, mint_accounts))
+}
+
+/// Async [`crate::sender::send_and_confirm`].
+pub async fn send_and_confirm(
+    client: &RpcClient,
+    sign: impl Fn(Hash) -> Result<VersionedTransaction, Error>,
+    transaction: VersionedTransaction,
+    last_valid_block_height: Option<u64>,
+    config: &SendConfig,
+) -> Result<Signature, Error> {
+    let commitment = client.commitment();
+    let mut attempt = Attempt::new(transaction, last_valid_block_height);
+
+    loop {
+        attempt.broadcasts += 1;
+        let result = client
+            .send_transaction_with_config(&attempt.transaction, attempt.send_config(commitment))
+            .await;
+        attempt.broadcast_result(result)?;
+
+        let deadline = Instant::now() + config.backoff(attempt.broadcasts);
+        loop {
+            if let Some(status) = signature_status(client, &attempt.signature()).await? {
+                if let Some(result) = attempt.status_result(status, commitment) {
+                    return result;
+                }
+            }
+            if Instant::now() >= deadline {
+                break;
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+
+        if attempt.exhausted(config) {
+            return Err(attempt.not_confirmed());
+        }
+
+        let expired = expired_result(&attempt, client.get_block_height().await)?;
+        if expired && signature_status(client, &attempt.signature()).await? == Some(None) {
+            let (blockhash, last_valid) = client
+                .get_latest_blockhash_with_commitment(commitment)
+                .await?;
+            attempt.resign(&sign, blockhash, last_valid, config)?;
+        }
+    }
+}
+
+async fn signature_status(
+    client: &RpcClient,
+    signature: &Signature,
+) -> Result<Option<Option<TransactionStatus>>, Error> {
+    status_response(
+        client
+            .get_signature_statuses(&[*signature])
+            .await
+            .map(|statuses| statuses.value),
+    )
+}
+
+/// Async [`crate::transaction::nonce_blockhash`].
+pub async fn nonce_blockhash(client: &RpcClient, account: &Pubkey) -> Result<Hash, Error> {
+    let account =
+        nonce_utils::nonblocking::get_account_with_commitment(client, account, client.commitment())
+            .await?;
+
+    Ok(nonce_utils::data_from_account(&account)?.blockhash())
+}
+
+/// Async [`crate::sol::send_sol_with_memo`].
+pub async fn send_sol_with_memo(
+    client: &RpcClient,
+    sender: &(dyn Signer + Sync),
+    recipient: &Pubkey,
+    amount: u64,
+    memo: Option<&str>,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let instructions =
+        send_sol_with_memo_instructions(&sender.pubkey(), recipient, amount, memo);
+
+    process_transaction(client, &instructions, &[sender], config).await
+}
+
+/// Async [`crate::token::create_token_mint`].
+pub async fn create_token_mint(
+    client: &RpcClient,
+    payer: &(dyn Signer + Sync),
+    mint: &(dyn Signer + Sync),
+    decimals: u8,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let mint_rent = client
+        .get_minimum_balance_for_rent_exemption(Mint::LEN)
+        .await?;
+    let instructions =
+        create_token_mint_instructions(&payer.pubkey(), &mint.pubkey(), decimals, mint_rent)?;
+
+    process_transaction(client, &instructions, &[payer, mint], config).await
+}
+
+/// Async [`crate::token::mint_tokens`].
+pub async fn mint_tokens(
+    client: &RpcClient,
+    mint: &Pubkey,
+    payer: &(dyn Signer + Sync),
+    ata: &Pubkey,
+    amount: u64,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let mint_to_ix = mint_tokens_instruction(mint, &payer.pubkey(), ata, amount)?;
+
+    process_transaction(client, &[mint_to_ix], &[payer], config).await
+}
+
+/// Async [`crate::token::create_associated_token_account`].
+pub async fn create_associated_token_account(
+    client: &RpcClient,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    payer: &(dyn Signer + Sync),
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let ix = create_associated_token_account_instruction(mint, owner, &payer.pubkey());
+
+    process_transaction(client, &[ix], &[payer], config).await
+}
+
+/// Async [`crate::metadata::create_token_metadata`].
+pub async fn create_token_metadata(
+    client: &RpcClient,
+    mint: &Pubkey,
+    payer: &(dyn Signer + Sync),
+    metadata: TokenMetadata,
+    is_mutable: bool,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let ix = create_token_metadata_instruction(mint, &payer.pubkey(), metadata, is_mutable);
+
+    process_transaction(client, &[ix], &[payer], config).await
+}
@@ -0,0 +1,257 @@
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use practice_2::amount::format_sol;
+use practice_2::config::ClusterArgs;
+use practice_2::nonce::{
+    advance_nonce_account, create_nonce_account, get_nonce_info, nonce_rent,
+    withdraw_nonce_account,
+};
+use practice_2::report::{ErrorReport, NonceReport, OutputFormat, Report};
+use practice_2::transaction::TransactionArgs;
+use practice_2::{
+    Amount, ClusterConfig, Error, SignerSource, TransactionConfig, TransactionOutcome, load_signer,
+};
+
+/// Manages durable nonce accounts. Transactions are built as by the
+/// `practice-2` CLI, with the same `--keypair`, `--output` and transaction
+/// flags such as `--blockhash`, `--sign-only` and `--dry-run`.
+#[derive(Parser)]
+#[command(about)]
+struct Cli {
+    /// Fee payer and default nonce authority: a keypair file, `env:NAME`,
+    /// `-`, `cli-config`, a signing service URL or a bare pubkey for
+    /// sign-only. Defaults to `KEYPAIR` or the usual keypair lookup
+    #[arg(long, short = 'k', global = true)]
+    keypair: Option<SignerSource>,
+    /// Output format
+    #[arg(long, short = 'o', global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    #[command(flatten)]
+    cluster: ClusterArgs,
+    #[command(flatten)]
+    transaction: TransactionArgs,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a nonce account funded by the fee payer.
+    Create {
+        /// Signer for the new account. A fresh keypair is used and saved
+        /// as `nonce-<pubkey>.json` if not given.
+        #[arg(long)]
+        nonce_keypair: Option<SignerSource>,
+        /// Authority of the nonce, the fee payer if not given.
+        #[arg(long)]
+        authority: Option<Pubkey>,
+        /// SOL to fund the account with, the rent exempt minimum if not
+        /// given.
+        #[arg(long)]
+        amount: Option<Amount>,
+    },
+    /// Show the authority, stored nonce and balance of a nonce account.
+    Inspect { account: Pubkey },
+    /// Advance the nonce, invalidating transactions signed with it.
+    Advance {
+        account: Pubkey,
+        /// Nonce authority, the fee payer if not given.
+        #[arg(long)]
+        authority: Option<SignerSource>,
+    },
+    /// Withdraw lamports; withdrawing the whole balance closes the account.
+    Withdraw {
+        account: Pubkey,
+        recipient: Pubkey,
+        /// SOL to withdraw, the whole balance if not given.
+        #[arg(long)]
+        amount: Option<Amount>,
+        /// Nonce authority, the fee payer if not given.
+        #[arg(long)]
+        authority: Option<SignerSource>,
+    },
+}
+
+impl Command {
+    /// The command as typed, for reports.
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Create { .. } => "nonce-account create",
+            Command::Inspect { .. } => "nonce-account inspect",
+            Command::Advance { .. } => "nonce-account advance",
+            Command::Withdraw { .. } => "nonce-account withdraw",
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match (run(&cli), cli.output) {
+        (Ok(()), _) => return ExitCode::SUCCESS,
+        (Err(error), OutputFormat::Json) => {
+            match serde_json::to_string(&ErrorReport::new(cli.command.name(), &error)) {
+                Ok(report) => println!("{report}"),
+                Err(_) => eprintln!("Error: {error:?}"),
+            }
+        }
+        (Err(error), OutputFormat::Text) => eprintln!("Error: {error:?}"),
+    }
+
+    ExitCode::FAILURE
+}
+
+fn run(cli: &Cli) -> Result<(), Error> {
+    let client = ClusterConfig::resolve(&cli.cluster)?.rpc_client();
+    let text = cli.output == OutputFormat::Text;
+    let load_payer = || match &cli.keypair {
+        Some(source) => source.signer(),
+        None => load_signer(),
+    };
+
+    let (outcome, accounts) = match &cli.command {
+        Command::Create {
+            nonce_keypair,
+            authority,
+            amount,
+        } => {
+            let payer = load_payer()?;
+            let nonce: Box<dyn Signer> = match nonce_keypair {
+                Some(source) => source.signer()?,
+                None => {
+                    let keypair = Keypair::new();
+                    let path = format!("nonce-{}.json", keypair.pubkey());
+                    practice_2::keypair::write_keypair_file(&keypair, path.as_ref())?;
+                    // Standard output only carries the report in JSON mode.
+                    if text {
+                        println!("🔑 Nonce keypair saved to {path}");
+                    } else {
+                        eprintln!("🔑 Nonce keypair saved to {path}");
+                    }
+                    Box::new(keypair)
+                }
+            };
+            let authority = authority.unwrap_or(payer.pubkey());
+            let lamports = match amount {
+                Some(amount) => amount.to_lamports()?,
+                None => nonce_rent(&client)?,
+            };
+
+            let outcome = create_nonce_account(
+                &client,
+                payer.as_ref(),
+                nonce.as_ref(),
+                &authority,
+                lamports,
+                &config_for(&cli.transaction, nonce.pubkey())?,
+            )?;
+
+            if text {
+                println!("✅ Nonce account: {}", nonce.pubkey());
+            }
+            (outcome, vec![("nonce_account", nonce.pubkey()), ("authority", authority)])
+        }
+        Command::Inspect { account } => {
+            let info = get_nonce_info(&client, account)?;
+
+            match cli.output {
+                OutputFormat::Text => {
+                    println!("Nonce account: {}", info.address);
+                    println!("Authority: {}", info.authority);
+                    println!("Nonce: {}", info.blockhash);
+                    println!("Fee per signature: {} lamports", info.lamports_per_signature);
+                    println!("Balance: {} SOL", format_sol(info.balance));
+                }
+                OutputFormat::Json => {
+                    let report = NonceReport::new(cli.command.name(), &info);
+                    println!("{}", serde_json::to_string(&report)?);
+                }
+            }
+            return Ok(());
+        }
+        Command::Advance { account, authority } => {
+            let payer = load_payer()?;
+            let authority = authority.as_ref().map(SignerSource::signer).transpose()?;
+            let authority = authority.as_deref().unwrap_or(payer.as_ref());
+
+            let outcome = advance_nonce_account(
+                &client,
+                payer.as_ref(),
+                account,
+                authority,
+                &config_for(&cli.transaction, *account)?,
+            )?;
+
+            (outcome, vec![("nonce_account", *account)])
+        }
+        Command::Withdraw {
+            account,
+            recipient,
+            amount,
+            authority,
+        } => {
+            let payer = load_payer()?;
+            let authority = authority.as_ref().map(SignerSource::signer).transpose()?;
+            let authority = authority.as_deref().unwrap_or(payer.as_ref());
+            let lamports = match amount {
+                Some(amount) => amount.to_lamports()?,
+                None => client.get_balance(account)?,
+            };
+
+            let outcome = withdraw_nonce_account(
+                &client,
+                payer.as_ref(),
+                account,
+                authority,
+                recipient,
+                lamports,
+                &config_for(&cli.transaction, *account)?,
+            )?;
+
+            if text {
+                println!("💸 Withdrew {} SOL to {recipient}", format_sol(lamports));
+            }
+            (outcome, vec![("nonce_account", *account), ("recipient", *recipient)])
+        }
+    };
+
+    print_outcome(&client, cli, &outcome, &accounts)
+}
+
+fn print_outcome(
+    client: &RpcClient,
+    cli: &Cli,
+    outcome: &TransactionOutcome,
+    accounts: &[(&str, Pubkey)],
+) -> Result<(), Error> {
+    match cli.output {
+        OutputFormat::Text => match outcome {
+            TransactionOutcome::Simulated(_) => println!("🧪 {outcome}"),
+            TransactionOutcome::Estimated(_) => println!("💰 {outcome}"),
+            _ => println!("✅ {outcome}"),
+        },
+        OutputFormat::Json => {
+            let report = Report::new(client, cli.command.name(), outcome, accounts);
+            println!("{}", serde_json::to_string(&report)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// The configuration from `args`, without the nonce mode if it names the
+/// account the command works on: the account cannot be the nonce of its
+/// own transaction.
+fn config_for(args: &TransactionArgs, account: Pubkey) -> Result<TransactionConfig, Error> {
+    let mut config = TransactionConfig::resolve(args)?;
+    if config.nonce.as_ref().is_some_and(|nonce| nonce.account == account) {
+        config.nonce = None;
+    }
+
+    Ok(config)
+}
@@ -0,0 +1,102 @@
+//! Durable nonce accounts.
+//!
+//! A transaction whose blockhash is the value stored in a nonce account,
+//! and whose first instruction advances that nonce, stays valid until the
+//! nonce is advanced, instead of expiring about a minute after signing.
+//! Helpers use a nonce through [`TransactionConfig::nonce`].
+
+use solana_client::nonce_utils;
+use solana_client::rpc_client::RpcClient;
+use solana_program::hash::Hash;
+use solana_program::nonce::State;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_sdk::signature::Signer;
+
+use crate::transaction::process_transaction;
+use crate::{Error, TransactionConfig, TransactionOutcome};
+
+/// What a nonce account holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceInfo {
+    pub address: Pubkey,
+    pub authority: Pubkey,
+    /// The stored nonce, used as the blockhash of transactions.
+    pub blockhash: Hash,
+    pub lamports_per_signature: u64,
+    pub balance: u64,
+}
+
+/// Lamports a nonce account needs to be rent exempt.
+pub fn nonce_rent(client: &RpcClient) -> Result<u64, Error> {
+    Ok(client.get_minimum_balance_for_rent_exemption(State::size())?)
+}
+
+/// Creates the nonce account `nonce` funded with `lamports`, which
+/// `authority` can advance and withdraw from.
+pub fn create_nonce_account(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    nonce: &dyn Signer,
+    authority: &Pubkey,
+    lamports: u64,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let instructions = system_instruction::create_nonce_account(
+        &payer.pubkey(),
+        &nonce.pubkey(),
+        authority,
+        lamports,
+    );
+
+    process_transaction(client, &instructions, &[payer, nonce], config)
+}
+
+/// Fetches and decodes the nonce account at `address`.
+pub fn get_nonce_info(client: &RpcClient, address: &Pubkey) -> Result<NonceInfo, Error> {
+    let account = nonce_utils::get_account_with_commitment(client, address, client.commitment())?;
+    let data = nonce_utils::data_from_account(&account)?;
+
+    Ok(NonceInfo {
+        address: *address,
+        authority: data.authority,
+        blockhash: data.blockhash(),
+        lamports_per_signature: data.fee_calculator.lamports_per_signature,
+        balance: account.lamports,
+    })
+}
+
+/// Advances the nonce, invalidating transactions signed with its current
+/// value.
+pub fn advance_nonce_account(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    nonce: &Pubkey,
+    authority: &dyn Signer,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let ix = system_instruction::advance_nonce_account(nonce, &authority.pubkey());
+
+    process_transaction(client, &[ix], &[payer, authority], config)
+}
+
+/// Withdraws `lamports` from the nonce account to `recipient`. Withdrawing
+/// the whole balance closes the account.
+pub fn withdraw_nonce_account(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    nonce: &Pubkey,
+    authority: &dyn Signer,
+    recipient: &Pubkey,
+    lamports: u64,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let ix = system_instruction::withdraw_nonce_account(
+        nonce,
+        &authority.pubkey(),
+        recipient,
+        lamports,
+    );
+
+    process_transaction(client, &[ix], &[payer, authority], config)
+}
@@ -0,0 +1,273 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+
+use practice_2::offline::{
+    add_signatures, ensure_fully_signed, missing_signers, read_transaction, write_transaction,
+};
+use practice_2::config::ClusterArgs;
+use practice_2::cost::estimate_cost;
+use practice_2::decoder::decode_instructions;
+use practice_2::lookup_table::fetch_lookup_tables;
+use practice_2::report::{ErrorReport, OutputFormat, Report, TransactionFileReport};
+use practice_2::simulation::simulate;
+use practice_2::transaction::TransactionArgs;
+use practice_2::{
+    ClusterConfig, Error, SignerSource, TransactionConfig, TransactionOutcome, load_signer,
+};
+
+/// Signs and broadcasts transactions written by `practice-2 --sign-only
+/// FILE`. Takes the same `--keypair`, `--output` and transaction flags as
+/// `practice-2`; of the latter only `--dry-run` and `--estimate` apply to
+/// a transaction that is already built.
+#[derive(Parser)]
+#[command(about)]
+struct Cli {
+    /// Signer for `sign` when no `--signer` is given: a keypair file,
+    /// `env:NAME`, `-`, `cli-config` or a signing service URL. Defaults to
+    /// `KEYPAIR` or the usual keypair lookup
+    #[arg(long, short = 'k', global = true)]
+    keypair: Option<SignerSource>,
+    /// Output format
+    #[arg(long, short = 'o', global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    #[command(flatten)]
+    cluster: ClusterArgs,
+    #[command(flatten)]
+    transaction: TransactionArgs,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Add signatures to a transaction file, keeping the ones it has.
+    Sign {
+        file: PathBuf,
+        /// Signer to sign with, repeatable. Defaults to `--keypair`.
+        #[arg(long = "signer")]
+        signers: Vec<SignerSource>,
+    },
+    /// Show the blockhash, signers and decoded instructions of a
+    /// transaction file.
+    Inspect { file: PathBuf },
+    /// Show the fees and rent deposits of a transaction file.
+    Estimate { file: PathBuf },
+    /// Send a fully signed transaction file and wait for confirmation.
+    /// With `--dry-run` simulate it instead, signed or not, and with
+    /// `--estimate` show its cost.
+    Broadcast { file: PathBuf },
+}
+
+impl Command {
+    /// The command as typed, for reports.
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Sign { .. } => "offline-tx sign",
+            Command::Inspect { .. } => "offline-tx inspect",
+            Command::Estimate { .. } => "offline-tx estimate",
+            Command::Broadcast { .. } => "offline-tx broadcast",
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match (run(&cli), cli.output) {
+        (Ok(()), _) => return ExitCode::SUCCESS,
+        (Err(error), OutputFormat::Json) => {
+            match serde_json::to_string(&ErrorReport::new(cli.command.name(), &error)) {
+                Ok(report) => println!("{report}"),
+                Err(_) => eprintln!("Error: {error:?}"),
+            }
+        }
+        (Err(error), OutputFormat::Text) => eprintln!("Error: {error:?}"),
+    }
+
+    ExitCode::FAILURE
+}
+
+fn run(cli: &Cli) -> Result<(), Error> {
+    match &cli.command {
+        Command::Sign { file, signers } => sign(cli, file, signers),
+        Command::Inspect { file } => inspect(cli, file),
+        Command::Estimate { file } => {
+            let client = ClusterConfig::resolve(&cli.cluster)?.rpc_client();
+            let outcome = estimate(&client, &read_transaction(file)?)?;
+            print_outcome(&client, cli, &outcome)
+        }
+        Command::Broadcast { file } => broadcast(cli, file),
+    }
+}
+
+fn sign(cli: &Cli, file: &Path, sources: &[SignerSource]) -> Result<(), Error> {
+    let mut transaction = read_transaction(file)?;
+
+    let signers = if sources.is_empty() {
+        vec![match &cli.keypair {
+            Some(source) => source.signer()?,
+            None => load_signer()?,
+        }]
+    } else {
+        sources
+            .iter()
+            .map(SignerSource::signer)
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    let signers: Vec<&dyn Signer> = signers.iter().map(|signer| signer.as_ref() as &dyn Signer).collect();
+
+    add_signatures(&mut transaction, &signers)?;
+    write_transaction(&transaction, file)?;
+    let missing = missing_signers(&transaction);
+
+    if cli.output == OutputFormat::Json {
+        let roles: Vec<String> = (1..=signers.len()).map(|n| format!("signer_{n}")).collect();
+        let accounts: Vec<(&str, Pubkey)> = roles
+            .iter()
+            .zip(&signers)
+            .map(|(role, signer)| (role.as_str(), signer.pubkey()))
+            .collect();
+        let outcome = TransactionOutcome::SignedOnly {
+            path: file.to_path_buf(),
+            missing,
+        };
+        // Not contacted: a signed-only report needs nothing from the cluster.
+        let client = ClusterConfig::resolve(&cli.cluster)?.rpc_client();
+        let report = Report::new(&client, cli.command.name(), &outcome, &accounts);
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+
+    for signer in &signers {
+        println!("✍️ Signed by {}", signer.pubkey());
+    }
+    if missing.is_empty() {
+        println!("✅ Fully signed, ready to broadcast");
+    } else {
+        for pubkey in missing {
+            println!("⏳ Still to sign: {pubkey}");
+        }
+    }
+
+    Ok(())
+}
+
+fn inspect(cli: &Cli, file: &Path) -> Result<(), Error> {
+    let transaction = read_transaction(file)?;
+    let message = &transaction.message;
+    let keys = message.static_account_keys();
+    let instructions = decode_instructions(message, keys);
+
+    if cli.output == OutputFormat::Json {
+        let report = TransactionFileReport::new(cli.command.name(), &transaction, &instructions);
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+
+    let version = match message {
+        VersionedMessage::Legacy(_) => "legacy",
+        VersionedMessage::V0(_) => "v0",
+    };
+    println!("Message version: {version}");
+    println!("Blockhash: {}", message.recent_blockhash());
+    println!("Fee payer: {}", keys[0]);
+
+    println!("Signers:");
+    let required = message.header().num_required_signatures as usize;
+    for (pubkey, signature) in keys.iter().zip(&transaction.signatures).take(required) {
+        if *signature == Signature::default() {
+            println!("  {pubkey}: missing");
+        } else {
+            println!("  {pubkey}: {signature}");
+        }
+    }
+
+    if let Some(lookups) = message.address_table_lookups().filter(|lookups| !lookups.is_empty()) {
+        println!("Lookup tables:");
+        for lookup in lookups {
+            println!(
+                "  {} ({} writable, {} readonly)",
+                lookup.account_key,
+                lookup.writable_indexes.len(),
+                lookup.readonly_indexes.len(),
+            );
+        }
+    }
+
+    println!("Instructions:");
+    for (index, instruction) in instructions.iter().enumerate() {
+        println!("  #{} {instruction}", index + 1);
+    }
+
+    Ok(())
+}
+
+fn estimate(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+) -> Result<TransactionOutcome, Error> {
+    let lookup_tables = fetch_lookup_tables(client, &lookup_table_addresses(transaction))?;
+
+    let estimate = estimate_cost(client, transaction, &lookup_tables)?;
+
+    Ok(TransactionOutcome::Estimated(Box::new(estimate)))
+}
+
+fn broadcast(cli: &Cli, file: &Path) -> Result<(), Error> {
+    let client = ClusterConfig::resolve(&cli.cluster)?.rpc_client();
+    let config = TransactionConfig::resolve(&cli.transaction)?;
+    let transaction = read_transaction(file)?;
+
+    let outcome = if config.estimate {
+        estimate(&client, &transaction)?
+    } else if config.dry_run {
+        let lookup_tables =
+            fetch_lookup_tables(&client, &lookup_table_addresses(&transaction))?;
+
+        TransactionOutcome::Simulated(Box::new(simulate(&client, &transaction, &lookup_tables)?))
+    } else {
+        ensure_fully_signed(&transaction)?;
+
+        if cli.output == OutputFormat::Text {
+            println!("📡 Broadcasting {}...", transaction.signatures[0]);
+        }
+
+        TransactionOutcome::Confirmed(client.send_and_confirm_transaction(&transaction)?)
+    };
+
+    print_outcome(&client, cli, &outcome)
+}
+
+fn print_outcome(client: &RpcClient, cli: &Cli, outcome: &TransactionOutcome) -> Result<(), Error> {
+    match cli.output {
+        OutputFormat::Text => match outcome {
+            TransactionOutcome::Simulated(_) => println!("🧪 {outcome}"),
+            TransactionOutcome::Estimated(_) => println!("💰 {outcome}"),
+            _ => println!("✅ {outcome}"),
+        },
+        OutputFormat::Json => {
+            let report = Report::new(client, cli.command.name(), outcome, &[]);
+            println!("{}", serde_json::to_string(&report)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Lookup tables the message of `transaction` loads accounts from.
+fn lookup_table_addresses(transaction: &VersionedTransaction) -> Vec<Pubkey> {
+    transaction
+        .message
+        .address_table_lookups()
+        .unwrap_or_default()
+        .iter()
+        .map(|lookup| lookup.account_key)
+        .collect()
+}
@@ -0,0 +1,115 @@
+//! Transactions that are signed in several steps, possibly on air-gapped
+//! machines, and broadcast later.
+//!
+//! A transaction is stored as its base64 encoded wire format, the same
+//! encoding `sendTransaction` accepts, for legacy and v0 messages alike.
+//! Signatures that are still missing are all zeroes.
+
+use std::path::Path;
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::sanitize::SanitizeError;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::signer::{unique_signers, SignerError};
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum OfflineError {
+    #[error("invalid base64 transaction: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("invalid transaction wire format: {0}")]
+    WireFormat(#[from] bincode::Error),
+    #[error("malformed transaction: {0}")]
+    Malformed(#[from] SanitizeError),
+    #[error("transaction is still missing signatures from {}", format_pubkeys(.0))]
+    MissingSignatures(Vec<Pubkey>),
+}
+
+/// Encodes `transaction` as base64 wire format.
+pub fn encode_transaction(transaction: &VersionedTransaction) -> Result<String, OfflineError> {
+    Ok(BASE64_STANDARD.encode(bincode::serialize(transaction)?))
+}
+
+/// Decodes a transaction from base64 wire format, checking that its
+/// signatures and account indexes are consistent with its message.
+pub fn decode_transaction(encoded: &str) -> Result<VersionedTransaction, OfflineError> {
+    let bytes = BASE64_STANDARD.decode(encoded.trim())?;
+    let transaction: VersionedTransaction = bincode::deserialize(&bytes)?;
+    transaction.sanitize()?;
+
+    Ok(transaction)
+}
+
+/// Writes `transaction` to `path` as base64 wire format.
+pub fn write_transaction(transaction: &VersionedTransaction, path: &Path) -> Result<(), crate::Error> {
+    std::fs::write(path, encode_transaction(transaction)?)?;
+
+    Ok(())
+}
+
+/// Reads a transaction written by [`write_transaction`].
+pub fn read_transaction(path: &Path) -> Result<VersionedTransaction, crate::Error> {
+    Ok(decode_transaction(&std::fs::read_to_string(path)?)?)
+}
+
+/// Required signers whose signatures are still missing.
+pub fn missing_signers(transaction: &VersionedTransaction) -> Vec<Pubkey> {
+    let required = transaction.message.header().num_required_signatures as usize;
+
+    transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .zip(&transaction.signatures)
+        .take(required)
+        .filter(|(_, signature)| **signature == Signature::default())
+        .map(|(pubkey, _)| *pubkey)
+        .collect()
+}
+
+/// Adds signatures from `signers`, keeping the ones already present.
+///
+/// The message is not changed, so signatures made by other parties stay
+/// valid. Signers that are not required by the transaction are an error.
+pub fn add_signatures(
+    transaction: &mut VersionedTransaction,
+    signers: &[&dyn Signer],
+) -> Result<(), crate::Error> {
+    let message = transaction.message.serialize();
+    let required = transaction.message.header().num_required_signatures as usize;
+    let keys = &transaction.message.static_account_keys()[..required];
+
+    for signer in unique_signers(signers.to_vec()) {
+        let position = keys
+            .iter()
+            .position(|key| *key == signer.pubkey())
+            .ok_or(SignerError::KeypairPubkeyMismatch)?;
+        transaction.signatures[position] = signer.try_sign_message(&message)?;
+    }
+
+    Ok(())
+}
+
+/// Checks that every required signature is present and valid.
+pub fn ensure_fully_signed(transaction: &VersionedTransaction) -> Result<(), crate::Error> {
+    let missing = missing_signers(transaction);
+    if !missing.is_empty() {
+        return Err(OfflineError::MissingSignatures(missing).into());
+    }
+
+    if !transaction.verify_with_results().into_iter().all(|valid| valid) {
+        return Err(TransactionError::SignatureFailure.into());
+    }
+
+    Ok(())
+}
+
+fn format_pubkeys(pubkeys: &[Pubkey]) -> String {
+    pubkeys
+        .iter()
+        .map(Pubkey::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
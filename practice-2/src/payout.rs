@@ -0,0 +1,649 @@
+//! SOL payouts to many recipients from a CSV file.
+//!
+//! The file has the columns `recipient`, `amount` (in SOL) and an optional
+//! `memo`. Transfers are packed into as few transactions as fit, each
+//! transfer with its memo as by [`send_sol_with_memo_instructions`], and
+//! the transactions are sent on several threads.
+//!
+//! Progress is kept in a JSON state file. Every signed version of a
+//! transaction is written there before it is broadcast, so after an
+//! interruption [`reconcile`] can look its signatures up: a payout whose
+//! transaction landed is not sent again, and one is only sent again once
+//! none of its transactions can land anymore, because their blockhashes
+//! expired.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread::sleep;
+
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS;
+use solana_program::hash::Hash;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use thiserror::Error;
+
+use crate::amount::{format_sol, Amount};
+use crate::lookup_table::fetch_lookup_tables;
+use crate::sender::{SendError, POLL_INTERVAL};
+use crate::sol::send_sol_with_memo_instructions;
+use crate::token::pack;
+use crate::transaction::{process_transaction_observed, process_transaction_with_lookup_tables};
+use crate::{TransactionConfig, TransactionOutcome};
+
+/// Transactions sent at the same time by default.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Version of the state file format.
+const STATE_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum PayoutError {
+    #[error("line {line}: {message}")]
+    InvalidRow { line: u64, message: String },
+    #[error("the state file belongs to other payouts: row {row} differs")]
+    StateMismatch { row: usize },
+    #[error("unsupported state file version {0}")]
+    StateVersion(u32),
+    #[error("payouts cannot use a durable nonce or be signed only")]
+    Unsupported,
+    #[error("{unpaid} payouts were not paid, run the payout again to retry them")]
+    Incomplete { unpaid: usize },
+}
+
+/// A row of the payout file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payout {
+    /// Line in the file, for messages.
+    pub line: u64,
+    pub recipient: Pubkey,
+    pub lamports: u64,
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    recipient: String,
+    amount: String,
+    #[serde(default)]
+    memo: Option<String>,
+}
+
+/// Reads payouts from CSV with a header row.
+pub fn read_payouts(reader: impl io::Read) -> Result<Vec<Payout>, crate::Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(reader);
+
+    let invalid = |line, message: String| PayoutError::InvalidRow { line, message };
+    let line_of = |error: &csv::Error| {
+        error
+            .position()
+            .map(|position| position.line())
+            .unwrap_or_default()
+    };
+    let headers = reader
+        .headers()
+        .map_err(|error| invalid(line_of(&error), error.to_string()))?
+        .clone();
+
+    let mut payouts = Vec::new();
+    let mut total: u64 = 0;
+    for record in reader.records() {
+        let record = record.map_err(|error| invalid(line_of(&error), error.to_string()))?;
+        let line = record
+            .position()
+            .map(|position| position.line())
+            .unwrap_or_default();
+        let record: Row = record
+            .deserialize(Some(&headers))
+            .map_err(|error| invalid(line, error.to_string()))?;
+
+        let recipient = record
+            .recipient
+            .parse()
+            .map_err(|error| invalid(line, format!("invalid recipient: {error}")))?;
+        let lamports = record
+            .amount
+            .parse::<Amount>()
+            .and_then(Amount::to_lamports)
+            .map_err(|error| invalid(line, error.to_string()))?;
+        if lamports == 0 {
+            return Err(invalid(line, "amount must be positive".to_string()).into());
+        }
+        total = total
+            .checked_add(lamports)
+            .ok_or_else(|| invalid(line, "total of the file is too large".to_string()))?;
+
+        payouts.push(Payout {
+            line,
+            recipient,
+            lamports,
+            memo: record.memo.filter(|memo| !memo.is_empty()),
+        });
+    }
+
+    Ok(payouts)
+}
+
+/// Where a payout stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutStatus {
+    /// Not sent, or sent in transactions that can no longer land.
+    Pending,
+    /// In a signed transaction that may have been broadcast and may still
+    /// land.
+    Sent,
+    Paid,
+    /// Its transaction landed but failed, so nobody in it was paid; it is
+    /// sent again on the next run.
+    Failed,
+}
+
+/// A signed version of a transaction carrying payouts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SentTransaction {
+    pub signature: String,
+    pub blockhash: String,
+}
+
+/// A payout in the state file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayoutRecord {
+    pub line: u64,
+    pub recipient: String,
+    pub lamports: u64,
+    pub memo: Option<String>,
+    pub status: PayoutStatus,
+    /// Every signed version of the transactions carrying the payout.
+    pub transactions: Vec<SentTransaction>,
+    /// The transaction that paid, or that failed.
+    pub signature: Option<String>,
+    /// Last error sending the payout.
+    pub error: Option<String>,
+}
+
+impl PayoutRecord {
+    fn matches(&self, payout: &Payout) -> bool {
+        self.recipient == payout.recipient.to_string()
+            && self.lamports == payout.lamports
+            && self.memo == payout.memo
+    }
+}
+
+/// Progress of the payouts of one file, as stored in the state file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayoutState {
+    pub version: u32,
+    /// In the order of the file.
+    pub payouts: Vec<PayoutRecord>,
+}
+
+impl PayoutState {
+    /// A fresh state with every payout pending.
+    pub fn new(payouts: &[Payout]) -> Self {
+        PayoutState {
+            version: STATE_VERSION,
+            payouts: payouts
+                .iter()
+                .map(|payout| PayoutRecord {
+                    line: payout.line,
+                    recipient: payout.recipient.to_string(),
+                    lamports: payout.lamports,
+                    memo: payout.memo.clone(),
+                    status: PayoutStatus::Pending,
+                    transactions: Vec::new(),
+                    signature: None,
+                    error: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// The state in `path` if it exists, checked to belong to `payouts`,
+    /// or a fresh one.
+    pub fn load(path: &Path, payouts: &[Payout]) -> Result<Self, crate::Error> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(PayoutState::new(payouts))
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let state: PayoutState = serde_json::from_str(&contents)?;
+
+        if state.version != STATE_VERSION {
+            return Err(PayoutError::StateVersion(state.version).into());
+        }
+        if let Some(row) = (0..state.payouts.len().max(payouts.len())).find(|row| {
+            match (state.payouts.get(*row), payouts.get(*row)) {
+                (Some(record), Some(payout)) => !record.matches(payout),
+                _ => true,
+            }
+        }) {
+            return Err(PayoutError::StateMismatch { row: row + 1 }.into());
+        }
+
+        Ok(state)
+    }
+
+    /// Writes the state to `path`, replacing the previous one at once so
+    /// an interruption cannot leave half a file.
+    pub fn write(&self, path: &Path) -> Result<(), crate::Error> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        std::fs::write(&temporary, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&temporary, path)?;
+
+        Ok(())
+    }
+
+    /// Payouts still to send: pending and failed ones.
+    pub fn unsent(&self) -> Vec<usize> {
+        (0..self.payouts.len())
+            .filter(|index| {
+                matches!(
+                    self.payouts[*index].status,
+                    PayoutStatus::Pending | PayoutStatus::Failed
+                )
+            })
+            .collect()
+    }
+
+    pub fn report(&self) -> PayoutReport {
+        let mut report = PayoutReport::default();
+        let mut signatures = Vec::new();
+
+        for record in &self.payouts {
+            report.requested.add(record.lamports);
+            let tally = match record.status {
+                PayoutStatus::Paid => &mut report.paid,
+                PayoutStatus::Failed => &mut report.failed,
+                PayoutStatus::Pending => &mut report.pending,
+                PayoutStatus::Sent => &mut report.unknown,
+            };
+            tally.add(record.lamports);
+
+            match (&record.status, &record.signature) {
+                (PayoutStatus::Paid, Some(signature)) => signatures.push(signature.as_str()),
+                _ => report.unpaid.push(record.clone()),
+            }
+        }
+        signatures.sort_unstable();
+        signatures.dedup();
+        report.transactions = signatures.len();
+
+        report
+    }
+}
+
+/// Number and sum of payouts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub count: usize,
+    pub lamports: u64,
+}
+
+impl Tally {
+    fn add(&mut self, lamports: u64) {
+        self.count += 1;
+        // Tallies are parts of the file, whose total `read_payouts` checked.
+        self.lamports = self.lamports.saturating_add(lamports);
+    }
+}
+
+impl fmt::Display for Tally {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = if self.count == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} payout{plural}, {} SOL",
+            self.count,
+            format_sol(self.lamports)
+        )
+    }
+}
+
+/// What was paid of the file, and what was not.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PayoutReport {
+    pub requested: Tally,
+    pub paid: Tally,
+    pub failed: Tally,
+    pub pending: Tally,
+    /// Sent, but whether they landed is not known yet.
+    pub unknown: Tally,
+    /// Transactions the paid payouts went out in.
+    pub transactions: usize,
+    pub unpaid: Vec<PayoutRecord>,
+}
+
+impl PayoutReport {
+    pub fn is_complete(&self) -> bool {
+        self.paid == self.requested
+    }
+}
+
+impl fmt::Display for PayoutReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Requested: {}", self.requested)?;
+        write!(
+            f,
+            "\nPaid: {} in {} transactions",
+            self.paid, self.transactions
+        )?;
+        for (label, tally) in [
+            ("Failed", self.failed),
+            ("Not sent", self.pending),
+            ("Not known yet", self.unknown),
+        ] {
+            if tally.count > 0 {
+                write!(f, "\n{label}: {tally}")?;
+            }
+        }
+
+        for record in &self.unpaid {
+            let status = match record.status {
+                PayoutStatus::Pending => "not sent",
+                PayoutStatus::Sent => "not known yet",
+                PayoutStatus::Paid => "paid",
+                PayoutStatus::Failed => "failed",
+            };
+            write!(
+                f,
+                "\n  line {}: {} SOL to {}, {status}",
+                record.line,
+                format_sol(record.lamports),
+                record.recipient
+            )?;
+            if let Some(error) = &record.error {
+                write!(f, ": {error}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// What happened while sending, reported from the sending threads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PayoutEvent {
+    Paid {
+        lines: Vec<u64>,
+        signature: Signature,
+    },
+    Error {
+        lines: Vec<u64>,
+        error: String,
+    },
+}
+
+/// Settles the sent payouts of `state` from the chain: paid or failed if
+/// one of their transactions landed, pending again if none of them can
+/// land anymore. Returns whether some are still undecided.
+pub fn reconcile(client: &RpcClient, state: &mut PayoutState) -> Result<bool, crate::Error> {
+    let sent: Vec<&SentTransaction> = state
+        .payouts
+        .iter()
+        .filter(|record| record.status == PayoutStatus::Sent)
+        .flat_map(|record| &record.transactions)
+        .collect();
+
+    // Checked before the statuses: once a blockhash is invalid, a
+    // transaction that has not landed by then never will. The other way
+    // round, one landing in its last valid block in between would look
+    // lost, and be paid again.
+    let mut valid_blockhashes = HashMap::new();
+    for blockhash in sent.iter().filter_map(|sent| sent.blockhash.parse::<Hash>().ok()) {
+        if let Entry::Vacant(entry) = valid_blockhashes.entry(blockhash) {
+            entry.insert(client.is_blockhash_valid(&blockhash, CommitmentConfig::processed())?);
+        }
+    }
+
+    let signatures: Vec<Signature> = sent
+        .iter()
+        .filter_map(|sent| sent.signature.parse().ok())
+        .collect();
+    let mut statuses = Vec::new();
+    for chunk in signatures.chunks(MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS) {
+        statuses.extend(client.get_signature_statuses_with_history(chunk)?.value);
+    }
+    let mut landed = HashMap::new();
+    for (signature, status) in signatures.iter().zip(statuses) {
+        if let Some(status) = status {
+            landed.insert(signature.to_string(), status);
+        }
+    }
+
+    let commitment = client.commitment();
+    let mut undecided = false;
+    for record in &mut state.payouts {
+        if record.status != PayoutStatus::Sent {
+            continue;
+        }
+
+        let found = record.transactions.iter().find_map(|sent| {
+            landed
+                .get(&sent.signature)
+                .map(|status| (sent.signature.clone(), status))
+        });
+        match found {
+            Some((signature, status)) if status.satisfies_commitment(commitment) => {
+                record.status = match &status.err {
+                    None => PayoutStatus::Paid,
+                    Some(error) => {
+                        record.error = Some(error.to_string());
+                        PayoutStatus::Failed
+                    }
+                };
+                record.signature = Some(signature);
+            }
+            // Landed, but not at the commitment yet.
+            Some(_) => undecided = true,
+            None => {
+                let can_land = record.transactions.iter().any(|sent| {
+                    sent.blockhash
+                        .parse::<Hash>()
+                        .is_ok_and(|blockhash| valid_blockhashes.get(&blockhash) == Some(&true))
+                });
+
+                if can_land {
+                    undecided = true;
+                } else {
+                    record.status = PayoutStatus::Pending;
+                }
+            }
+        }
+    }
+
+    Ok(undecided)
+}
+
+/// [`reconcile`] until every payout is decided, saving the state to
+/// `path` as it goes. Takes at most about as long as a blockhash lives.
+pub fn settle(
+    client: &RpcClient,
+    state: &mut PayoutState,
+    path: &Path,
+) -> Result<(), crate::Error> {
+    loop {
+        let undecided = reconcile(client, state)?;
+        state.write(path)?;
+        if !undecided {
+            return Ok(());
+        }
+        sleep(POLL_INTERVAL);
+    }
+}
+
+/// Sends the payouts not yet paid according to the state file at
+/// `state_path`, from `concurrency` threads, and returns what the file
+/// says afterwards.
+///
+/// In a dry run or estimate the transactions are only simulated or
+/// estimated, and their outcomes returned; the state file is not written.
+pub fn send_payouts(
+    client: &RpcClient,
+    payer: &(dyn Signer + Sync),
+    payouts: &[Payout],
+    state_path: &Path,
+    concurrency: usize,
+    config: &TransactionConfig,
+    on_event: impl Fn(PayoutEvent) + Sync,
+) -> Result<(PayoutReport, Vec<TransactionOutcome>), crate::Error> {
+    if config.nonce.is_some() || config.sign_only.is_some() {
+        return Err(PayoutError::Unsupported.into());
+    }
+
+    let mut state = PayoutState::load(state_path, payouts)?;
+    let preview = config.dry_run || config.estimate;
+    if !preview {
+        settle(client, &mut state, state_path)?;
+    }
+
+    let unsent = state.unsent();
+    let groups: Vec<_> = unsent
+        .iter()
+        .map(|index| {
+            let payout = &payouts[*index];
+            send_sol_with_memo_instructions(
+                &payer.pubkey(),
+                &payout.recipient,
+                payout.lamports,
+                payout.memo.as_deref(),
+            )
+        })
+        .collect();
+    let lookup_tables = fetch_lookup_tables(client, &config.lookup_tables)?;
+    let chunks = pack(&groups, payer, &lookup_tables, config)?;
+
+    if preview {
+        let outcomes = chunks
+            .into_iter()
+            .map(|chunk| {
+                process_transaction_with_lookup_tables(
+                    client,
+                    &groups[chunk].concat(),
+                    &[payer],
+                    &lookup_tables,
+                    config,
+                )
+            })
+            .collect::<Result<_, _>>()?;
+
+        return Ok((state.report(), outcomes));
+    }
+
+    let state = Mutex::new(state);
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, chunks.len().max(1)) {
+            scope.spawn(|| {
+                while let Some(chunk) = chunks.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let rows = &unsent[chunk.clone()];
+                    let lines: Vec<u64> = rows.iter().map(|row| payouts[*row].line).collect();
+
+                    let result = process_transaction_observed(
+                        client,
+                        &groups[chunk.clone()].concat(),
+                        &[payer],
+                        &lookup_tables,
+                        config,
+                        &|transaction| record_sent(&state, state_path, rows, transaction),
+                    );
+                    let event = record_result(&state, state_path, rows, result);
+                    on_event(match event {
+                        Ok(signature) => PayoutEvent::Paid { lines, signature },
+                        Err(error) => PayoutEvent::Error { lines, error },
+                    });
+                }
+            });
+        }
+    });
+
+    let mut state = state.into_inner().unwrap_or_else(PoisonError::into_inner);
+    settle(client, &mut state, state_path)?;
+
+    Ok((state.report(), Vec::new()))
+}
+
+fn lock(state: &Mutex<PayoutState>) -> MutexGuard<'_, PayoutState> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Records a signed version of the transaction of `rows` before it is
+/// broadcast.
+fn record_sent(
+    state: &Mutex<PayoutState>,
+    path: &Path,
+    rows: &[usize],
+    transaction: &VersionedTransaction,
+) -> Result<(), crate::Error> {
+    let sent = SentTransaction {
+        signature: transaction.signatures[0].to_string(),
+        blockhash: transaction.message.recent_blockhash().to_string(),
+    };
+
+    let mut state = lock(state);
+    for row in rows {
+        let record = &mut state.payouts[*row];
+        record.status = PayoutStatus::Sent;
+        record.transactions.push(sent.clone());
+    }
+    state.write(path)
+}
+
+/// Records how sending the transaction of `rows` ended, returning the
+/// signature or the error message.
+fn record_result(
+    state: &Mutex<PayoutState>,
+    path: &Path,
+    rows: &[usize],
+    result: Result<TransactionOutcome, crate::Error>,
+) -> Result<Signature, String> {
+    let mut state = lock(state);
+    let outcome = match result {
+        Ok(TransactionOutcome::Confirmed(signature)) => {
+            for row in rows {
+                let record = &mut state.payouts[*row];
+                record.status = PayoutStatus::Paid;
+                record.signature = Some(signature.to_string());
+                record.error = None;
+            }
+            Ok(signature)
+        }
+        Ok(outcome) => Err(format!("unexpected outcome: {outcome}")),
+        Err(error) => {
+            let landed = match &error {
+                crate::Error::Send(SendError::Failed { signature, .. }) => Some(*signature),
+                _ => None,
+            };
+            for row in rows {
+                let record = &mut state.payouts[*row];
+                record.error = Some(error.to_string());
+                // Otherwise left as it is: pending if it was never signed,
+                // sent (and settled later) if it may have been broadcast.
+                if let Some(signature) = landed {
+                    record.status = PayoutStatus::Failed;
+                    record.signature = Some(signature.to_string());
+                }
+            }
+            Err(error.to_string())
+        }
+    };
+
+    match state.write(path) {
+        Ok(()) => outcome,
+        Err(error) => Err(format!("state file not written: {error}")),
+    }
+}
@@ -0,0 +1,291 @@
+//! Everything a wallet holds: SOL and the token accounts of both token
+//! programs, with the decimals and Metaplex metadata of their mints.
+//!
+//! Token accounts are fetched with `getTokenAccountsByOwner` as raw
+//! account data, so the extensions of Token-2022 accounts can be read.
+//! An empty account can be closed to get its rent back, unless it is
+//! frozen or still holds withheld transfer fees.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcTokenAccountsFilter};
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::{Response, RpcKeyedAccount};
+use solana_program::program_option::COption;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use spl_token_2022::extension::transfer_fee::TransferFeeAmount;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::state::AccountState;
+
+use crate::amount::{format_amount, format_sol};
+use crate::metadata::{decode_metadata, metadata_address, TokenMetadata};
+
+/// Most accounts `getMultipleAccounts` returns at once.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// A delegate allowed to transfer tokens of an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delegation {
+    pub delegate: Pubkey,
+    /// Base units the delegate may still transfer.
+    pub amount: u64,
+}
+
+/// One token account of the owner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenHolding {
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    /// SPL Token or Token-2022.
+    pub token_program: Pubkey,
+    /// Base units of the mint.
+    pub amount: u64,
+    /// `None` if the mint could not be read.
+    pub decimals: Option<u8>,
+    /// `None` for a mint without Metaplex metadata.
+    pub metadata: Option<TokenMetadata>,
+    pub frozen: bool,
+    pub delegation: Option<Delegation>,
+    /// Rent deposit of the account, returned when it is closed.
+    pub lamports: u64,
+    /// Transfer fees of Token-2022 withheld in the account.
+    pub withheld_fees: u64,
+}
+
+impl TokenHolding {
+    /// Decodes the token account `address`, without what is read from its
+    /// mint.
+    pub fn decode(address: Pubkey, account: &Account) -> Option<Self> {
+        if account.owner != spl_token::id() && account.owner != spl_token_2022::id() {
+            return None;
+        }
+        let state =
+            StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data).ok()?;
+        let delegation = match state.base.delegate {
+            COption::Some(delegate) => Some(Delegation {
+                delegate,
+                amount: state.base.delegated_amount,
+            }),
+            COption::None => None,
+        };
+        let withheld_fees = state
+            .get_extension::<TransferFeeAmount>()
+            .map(|fee| u64::from(fee.withheld_amount))
+            .unwrap_or_default();
+
+        Some(TokenHolding {
+            address,
+            mint: state.base.mint,
+            token_program: account.owner,
+            amount: state.base.amount,
+            decimals: None,
+            metadata: None,
+            frozen: state.base.state == AccountState::Frozen,
+            delegation,
+            lamports: account.lamports,
+            withheld_fees,
+        })
+    }
+
+    /// Whether closing the account would succeed and return its rent.
+    pub fn is_closable(&self) -> bool {
+        self.amount == 0 && !self.frozen && self.withheld_fees == 0
+    }
+
+    /// The amount in tokens, or in base units if the decimals are unknown.
+    pub fn ui_amount(&self) -> String {
+        match self.decimals {
+            Some(decimals) => format_amount(self.amount, decimals),
+            None => self.amount.to_string(),
+        }
+    }
+
+    /// The symbol of the token, or its mint.
+    pub fn label(&self) -> String {
+        match &self.metadata {
+            Some(metadata) if !metadata.symbol.is_empty() => metadata.symbol.clone(),
+            _ => self.mint.to_string(),
+        }
+    }
+}
+
+/// What an owner holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Portfolio {
+    pub owner: Pubkey,
+    pub lamports: u64,
+    /// Accounts holding tokens first, then the empty ones.
+    pub tokens: Vec<TokenHolding>,
+}
+
+impl Portfolio {
+    /// The portfolio of `owner` from its token accounts, with the mint and
+    /// metadata accounts of [`related_addresses`] in `accounts`.
+    pub fn new(
+        owner: Pubkey,
+        lamports: u64,
+        token_accounts: &[(Pubkey, Account)],
+        accounts: &HashMap<Pubkey, Account>,
+    ) -> Self {
+        let mut tokens: Vec<_> = token_accounts
+            .iter()
+            .filter_map(|(address, account)| TokenHolding::decode(*address, account))
+            .map(|mut holding| {
+                holding.decimals = accounts
+                    .get(&holding.mint)
+                    .and_then(|mint| {
+                        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint.data).ok()
+                    })
+                    .map(|mint| mint.base.decimals);
+                holding.metadata = accounts
+                    .get(&metadata_address(&holding.mint))
+                    .filter(|account| account.owner == mpl_token_metadata::ID)
+                    .and_then(|account| decode_metadata(&account.data));
+                holding
+            })
+            .collect();
+        tokens.sort_by_key(|holding| (holding.amount == 0, holding.label()));
+
+        Portfolio {
+            owner,
+            lamports,
+            tokens,
+        }
+    }
+
+    /// Empty accounts that can be closed.
+    pub fn closable(&self) -> impl Iterator<Item = &TokenHolding> {
+        self.tokens.iter().filter(|holding| holding.is_closable())
+    }
+
+    /// Lamports closing every closable account would return.
+    pub fn reclaimable_rent(&self) -> u64 {
+        self.closable().map(|holding| holding.lamports).sum()
+    }
+}
+
+impl fmt::Display for Portfolio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Owner {}: {} SOL", self.owner, format_sol(self.lamports))?;
+        if self.tokens.is_empty() {
+            return write!(f, "\nNo token accounts");
+        }
+
+        write!(f, "\nToken accounts ({}):", self.tokens.len())?;
+        for holding in &self.tokens {
+            write!(f, "\n  {} {}", holding.ui_amount(), holding.label())?;
+            if let Some(metadata) = &holding.metadata {
+                write!(f, " ({})", metadata.name)?;
+            }
+            write!(f, ", mint {}", holding.mint)?;
+
+            write!(f, "\n    account {}", holding.address)?;
+            if holding.token_program == spl_token_2022::id() {
+                write!(f, " (Token-2022)")?;
+            }
+            if holding.frozen {
+                write!(f, ", frozen")?;
+            }
+            if let Some(delegation) = &holding.delegation {
+                let amount = match holding.decimals {
+                    Some(decimals) => format_amount(delegation.amount, decimals),
+                    None => delegation.amount.to_string(),
+                };
+                write!(f, ", {amount} delegated to {}", delegation.delegate)?;
+            }
+            if holding.withheld_fees > 0 {
+                write!(f, ", {} withheld in fees", holding.withheld_fees)?;
+            }
+            if holding.is_closable() {
+                write!(f, ", empty")?;
+            }
+        }
+
+        let closable = self.closable().count();
+        if closable > 0 {
+            write!(
+                f,
+                "\nEmpty accounts that can be closed: {closable}, reclaiming {} SOL of rent",
+                format_sol(self.reclaimable_rent())
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Mints of the token accounts and their metadata accounts.
+pub fn related_addresses(token_accounts: &[(Pubkey, Account)]) -> Vec<Pubkey> {
+    let mut mints: Vec<_> = token_accounts
+        .iter()
+        .filter_map(|(address, account)| TokenHolding::decode(*address, account))
+        .map(|holding| holding.mint)
+        .collect();
+    mints.sort();
+    mints.dedup();
+
+    let metadata = mints.iter().map(metadata_address).collect::<Vec<_>>();
+    mints.extend(metadata);
+
+    mints
+}
+
+/// Token accounts of `owner` under `token_program`.
+pub fn get_token_accounts(
+    client: &RpcClient,
+    owner: &Pubkey,
+    token_program: &Pubkey,
+) -> Result<Vec<(Pubkey, Account)>, crate::Error> {
+    let config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(client.commitment()),
+        ..RpcAccountInfoConfig::default()
+    };
+    let response: Response<Vec<RpcKeyedAccount>> = client.send(
+        RpcRequest::GetTokenAccountsByOwner,
+        serde_json::json!([
+            owner.to_string(),
+            RpcTokenAccountsFilter::ProgramId(token_program.to_string()),
+            config,
+        ]),
+    )?;
+
+    Ok(response
+        .value
+        .into_iter()
+        .filter_map(|keyed| {
+            Some((
+                keyed.pubkey.parse().ok()?,
+                keyed.account.decode::<Account>()?,
+            ))
+        })
+        .collect())
+}
+
+/// Fetches the SOL balance and the token accounts of `owner`, with the
+/// decimals and metadata of their mints.
+pub fn get_portfolio(client: &RpcClient, owner: &Pubkey) -> Result<Portfolio, crate::Error> {
+    let lamports = client.get_balance(owner)?;
+    let mut token_accounts = get_token_accounts(client, owner, &spl_token::id())?;
+    token_accounts.extend(get_token_accounts(client, owner, &spl_token_2022::id())?);
+
+    let addresses = related_addresses(&token_accounts);
+    let mut accounts = HashMap::new();
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let fetched = client
+            .get_multiple_accounts_with_commitment(chunk, client.commitment())?
+            .value;
+        accounts.extend(
+            chunk
+                .iter()
+                .zip(fetched)
+                .filter_map(|(address, account)| Some((*address, account?))),
+        );
+    }
+
+    Ok(Portfolio::new(*owner, lamports, &token_accounts, &accounts))
+}
@@ -0,0 +1,87 @@
+//! Programs the CLI knows by name.
+
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::{address_lookup_table, compute_budget};
+
+use crate::escrow::{ESCROW_APPROVE_PROGRAM_ID, ESCROW_PROGRAM_ID};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownProgram {
+    System,
+    ComputeBudget,
+    AddressLookupTable,
+    Token,
+    Token2022,
+    AssociatedToken,
+    Memo,
+    /// Memo v1, which still shows up in older transactions.
+    MemoV1,
+    TokenMetadata,
+    Escrow,
+    EscrowApprove,
+}
+
+impl KnownProgram {
+    pub const ALL: [KnownProgram; 11] = [
+        KnownProgram::System,
+        KnownProgram::ComputeBudget,
+        KnownProgram::AddressLookupTable,
+        KnownProgram::Token,
+        KnownProgram::Token2022,
+        KnownProgram::AssociatedToken,
+        KnownProgram::Memo,
+        KnownProgram::MemoV1,
+        KnownProgram::TokenMetadata,
+        KnownProgram::Escrow,
+        KnownProgram::EscrowApprove,
+    ];
+
+    pub fn id(self) -> Pubkey {
+        match self {
+            KnownProgram::System => system_program::id(),
+            KnownProgram::ComputeBudget => compute_budget::id(),
+            KnownProgram::AddressLookupTable => address_lookup_table::program::id(),
+            KnownProgram::Token => spl_token::id(),
+            KnownProgram::Token2022 => spl_token_2022::id(),
+            KnownProgram::AssociatedToken => spl_associated_token_account::id(),
+            KnownProgram::Memo => spl_memo::id(),
+            KnownProgram::MemoV1 => spl_memo::v1::id(),
+            KnownProgram::TokenMetadata => mpl_token_metadata::ID,
+            KnownProgram::Escrow => ESCROW_PROGRAM_ID,
+            KnownProgram::EscrowApprove => ESCROW_APPROVE_PROGRAM_ID,
+        }
+    }
+
+    pub fn from_id(id: &Pubkey) -> Option<Self> {
+        Self::ALL.into_iter().find(|program| program.id() == *id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KnownProgram::System => "System",
+            KnownProgram::ComputeBudget => "Compute Budget",
+            KnownProgram::AddressLookupTable => "Address Lookup Table",
+            KnownProgram::Token => "SPL Token",
+            KnownProgram::Token2022 => "Token-2022",
+            KnownProgram::AssociatedToken => "Associated Token Account",
+            KnownProgram::Memo => "Memo",
+            KnownProgram::MemoV1 => "Memo v1",
+            KnownProgram::TokenMetadata => "Token Metadata",
+            KnownProgram::Escrow => "Escrow",
+            KnownProgram::EscrowApprove => "Escrow Approve",
+        }
+    }
+
+    pub fn is_memo(self) -> bool {
+        matches!(self, KnownProgram::Memo | KnownProgram::MemoV1)
+    }
+}
+
+/// The name of the program `id`, or its address if it is not known.
+pub fn program_name(id: &Pubkey) -> String {
+    match KnownProgram::from_id(id) {
+        Some(program) => program.name().to_string(),
+        None => id.to_string(),
+    }
+}
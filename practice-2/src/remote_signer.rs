@@ -0,0 +1,212 @@
+//! Signing over HTTP, so authority keys can stay on a signing service
+//! instead of developer machines.
+//!
+//! Protocol:
+//! - `GET /pubkey` returns `{"pubkey": "<base58>"}`,
+//! - `POST /sign` with `{"message": "<base64 message bytes>"}` returns
+//!   `{"signature": "<base58>"}`.
+//!
+//! If a token is configured, requests carry `Authorization: Bearer <token>`.
+
+use std::str::FromStr;
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::signer::SignerError;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Variable with the bearer token shared by the signer and the service.
+pub const SIGNING_TOKEN_VAR: &str = "SIGNING_SERVER_TOKEN";
+
+#[derive(Serialize, Deserialize)]
+struct PubkeyResponse {
+    pubkey: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignRequest {
+    message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+/// A [`Signer`] that asks a signing service for every signature.
+///
+/// The public key is fetched once when connecting. Returned signatures are
+/// verified before use, so a misbehaving service cannot slip in a signature
+/// for another key or message.
+pub struct RemoteSigner {
+    url: String,
+    token: Option<String>,
+    pubkey: Pubkey,
+    client: reqwest::blocking::Client,
+}
+
+impl RemoteSigner {
+    /// Connects to the service at `url`, using the `SIGNING_SERVER_TOKEN`
+    /// variable as bearer token if it is set.
+    pub fn connect(url: &str) -> Result<Self, SignerError> {
+        let token = std::env::var(SIGNING_TOKEN_VAR).ok();
+        Self::connect_with_token(url, token)
+    }
+
+    /// Connects to the service at `url` with an explicit bearer token.
+    pub fn connect_with_token(url: &str, token: Option<String>) -> Result<Self, SignerError> {
+        let url = url.trim_end_matches('/').to_string();
+        let client = reqwest::blocking::Client::new();
+
+        let mut request = client.get(format!("{url}/pubkey"));
+        if let Some(token) = &token {
+            request = request.bearer_auth(token);
+        }
+        let response: PubkeyResponse = request
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json())
+            .map_err(|error| SignerError::Connection(error.to_string()))?;
+
+        let pubkey = Pubkey::from_str(&response.pubkey)
+            .map_err(|error| SignerError::Protocol(format!("bad pubkey from {url}: {error}")))?;
+
+        Ok(RemoteSigner {
+            url,
+            token,
+            pubkey,
+            client,
+        })
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let mut request = self
+            .client
+            .post(format!("{}/sign", self.url))
+            .json(&SignRequest {
+                message: BASE64_STANDARD.encode(message),
+            });
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response: SignResponse = request
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json())
+            .map_err(|error| SignerError::Connection(error.to_string()))?;
+
+        let signature = Signature::from_str(&response.signature)
+            .map_err(|error| SignerError::Protocol(format!("bad signature: {error}")))?;
+
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(SignerError::Protocol(format!(
+                "signing service returned a signature that does not verify for {}",
+                self.pubkey
+            )));
+        }
+
+        Ok(signature)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// Serves signing requests for `keypair` until the server is unblocked or
+/// dropped, calling `on_signed` with every message signed and its
+/// signature.
+///
+/// This is a local stand-in for a real signing service, for tests and
+/// demos: it signs any message it is sent, while a real service would check
+/// what it signs against a policy.
+pub fn serve(
+    server: &Server,
+    keypair: &Keypair,
+    token: Option<&str>,
+    on_signed: impl Fn(&[u8], &Signature),
+) {
+    for mut request in server.incoming_requests() {
+        let (status, body) = if request_authorized(&request, token) {
+            route(&mut request, keypair, &on_signed)
+        } else {
+            (401, error_body("missing or wrong bearer token"))
+        };
+
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(
+                Header::from_bytes("Content-Type", "application/json")
+                    .expect("static header is valid"),
+            );
+        let _ = request.respond(response);
+    }
+}
+
+fn request_authorized(request: &Request, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+
+    let expected = format!("Bearer {token}");
+    request
+        .headers()
+        .iter()
+        .any(|header| header.field.equiv("Authorization") && header.value.as_str() == expected)
+}
+
+fn route(
+    request: &mut Request,
+    keypair: &Keypair,
+    on_signed: &impl Fn(&[u8], &Signature),
+) -> (u16, String) {
+    match (request.method(), request.url()) {
+        (Method::Get, "/pubkey") => (
+            200,
+            json_body(&PubkeyResponse {
+                pubkey: keypair.pubkey().to_string(),
+            }),
+        ),
+        (Method::Post, "/sign") => {
+            let mut body = String::new();
+            if request.as_reader().read_to_string(&mut body).is_err() {
+                return (400, error_body("unreadable request body"));
+            }
+
+            let Some(message) = serde_json::from_str::<SignRequest>(&body)
+                .ok()
+                .and_then(|sign| BASE64_STANDARD.decode(sign.message).ok())
+            else {
+                return (400, error_body("expected {\"message\": \"<base64>\"}"));
+            };
+
+            let signature = keypair.sign_message(&message);
+            on_signed(&message, &signature);
+
+            (
+                200,
+                json_body(&SignResponse {
+                    signature: signature.to_string(),
+                }),
+            )
+        }
+        _ => (404, error_body("not found")),
+    }
+}
+
+fn json_body<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("response serializes")
+}
+
+fn error_body(error: &str) -> String {
+    serde_json::json!({ "error": error }).to_string()
+}
@@ -0,0 +1,821 @@
+//! Machine-readable results of the CLI commands, printed with
+//! `--output json` so scripts can chain commands without scraping text.
+//!
+//! A successful command prints one [`Report`] object per transaction, one
+//! per line: most commands send a single transaction, batches can send
+//! more. Commands that only read (like `lookup-table inspect`) print what
+//! they read instead. A failed command prints an [`ErrorReport`] and exits
+//! with status 1; its `kind` is one of the codes returned by
+//! [`Error::kind`], which do not change between versions. A payout that
+//! leaves rows unpaid also exits with status 1, after its report.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::bs58;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::amount::{format_amount, format_delta, format_sol, SOL_DECIMALS};
+use crate::cost::CostEstimate;
+use crate::decoder::DecodedInstruction;
+use crate::encrypted_memo::ReceivedMemo;
+use crate::history::{format_time, HistoryEntry};
+use crate::lookup_table::LookupTableInfo;
+use crate::nonce::NonceInfo;
+use crate::payout::{PayoutReport, PayoutStatus, Tally};
+use crate::portfolio::Portfolio;
+use crate::simulation::Simulation;
+use crate::solana_pay::{Payment, TransferRequest};
+use crate::{Error, TransactionOutcome};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    Confirmed,
+    SignedOnly,
+    Simulated,
+    Estimated,
+}
+
+/// Lamports of an account before and after the transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BalanceChange {
+    pub pre: u64,
+    pub post: u64,
+}
+
+/// Tokens held by a token account before and after the transaction, in
+/// base units. `pre` is `None` for an account the transaction created.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TokenBalanceChange {
+    pub mint: String,
+    pub owner: Option<String>,
+    pub decimals: u8,
+    pub pre: Option<String>,
+    pub post: Option<String>,
+}
+
+/// What a transaction would cost, see [`CostEstimate`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CostReport {
+    pub signatures: u64,
+    pub signature_fee: u64,
+    pub priority_fee: u64,
+    pub program_fees: u64,
+    /// Rent deposits by the address of the created account.
+    pub rent: BTreeMap<String, RentReport>,
+    pub total: u64,
+    pub fee_payer: String,
+    pub fee_payer_balance: u64,
+    /// Whether the fee payer can pay its part of the cost.
+    pub sufficient: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RentReport {
+    pub kind: &'static str,
+    pub space: usize,
+    pub lamports: u64,
+    pub funder: String,
+}
+
+/// What a command did.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub command: String,
+    pub status: ReportStatus,
+    pub signature: Option<String>,
+    pub slot: Option<u64>,
+    /// Fee paid, in lamports; estimated with `--estimate`.
+    pub fee: Option<u64>,
+    /// Addresses the command created or worked on, by role.
+    pub accounts: BTreeMap<String, String>,
+    /// SOL balances of every account in the transaction; in a dry run,
+    /// of every writable account.
+    pub balances: BTreeMap<String, BalanceChange>,
+    /// Token balances of every token account in the transaction; in a dry
+    /// run, of every writable one.
+    pub token_balances: BTreeMap<String, TokenBalanceChange>,
+    /// Sign-only mode: where the transaction was written.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Sign-only mode: signers that still have to sign.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_signers: Vec<String>,
+    /// Dry run: why the transaction would fail.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulation_error: Option<String>,
+    /// Dry run: compute units the transaction consumed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units_consumed: Option<u64>,
+    /// Dry run: program logs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<String>,
+    /// Estimate: fees and rent deposits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<CostReport>,
+    /// Why slot, fee or balances of a confirmed transaction are missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+impl Report {
+    /// Builds the report of `outcome`, fetching slot, fee and balances of
+    /// a confirmed transaction from the cluster. A dry run reports the
+    /// simulated balances, logs and compute units instead, and an
+    /// estimate its cost.
+    ///
+    /// The transaction was sent whether or not its details can be fetched,
+    /// so failing to fetch them leaves them out with a `warning`.
+    pub fn new(
+        client: &RpcClient,
+        command: &str,
+        outcome: &TransactionOutcome,
+        accounts: &[(&str, Pubkey)],
+    ) -> Self {
+        let mut report = Report {
+            command: command.to_string(),
+            status: ReportStatus::Confirmed,
+            signature: None,
+            slot: None,
+            fee: None,
+            accounts: accounts
+                .iter()
+                .map(|(role, pubkey)| (role.to_string(), pubkey.to_string()))
+                .collect(),
+            balances: BTreeMap::new(),
+            token_balances: BTreeMap::new(),
+            path: None,
+            missing_signers: Vec::new(),
+            simulation_error: None,
+            units_consumed: None,
+            logs: Vec::new(),
+            cost: None,
+            warning: None,
+        };
+
+        match outcome {
+            TransactionOutcome::Confirmed(signature) => {
+                report.signature = Some(signature.to_string());
+                if let Err(error) = report.add_transaction_details(client, signature) {
+                    report.warning = Some(format!("transaction details unavailable: {error}"));
+                }
+            }
+            TransactionOutcome::SignedOnly { path, missing } => {
+                report.status = ReportStatus::SignedOnly;
+                report.path = Some(path.clone());
+                report.missing_signers = missing.iter().map(Pubkey::to_string).collect();
+            }
+            TransactionOutcome::Simulated(simulation) => {
+                report.status = ReportStatus::Simulated;
+                report.add_simulation(simulation);
+            }
+            TransactionOutcome::Estimated(estimate) => {
+                report.status = ReportStatus::Estimated;
+                report.fee = Some(estimate.fees());
+                report.cost = Some(CostReport::new(estimate));
+            }
+        }
+
+        report
+    }
+
+    fn add_simulation(&mut self, simulation: &Simulation) {
+        self.simulation_error = simulation.error.as_ref().map(ToString::to_string);
+        self.units_consumed = simulation.units_consumed;
+        self.logs = simulation.logs.clone();
+
+        for account in &simulation.accounts {
+            let key = account.address.to_string();
+            self.balances.insert(
+                key.clone(),
+                BalanceChange {
+                    pre: account.pre_lamports,
+                    post: account.post_lamports,
+                },
+            );
+            if let Some(token) = &account.token {
+                self.token_balances.insert(
+                    key,
+                    TokenBalanceChange {
+                        mint: token.mint.to_string(),
+                        owner: Some(token.owner.to_string()),
+                        decimals: token.decimals,
+                        pre: token.pre.map(|amount| amount.to_string()),
+                        post: token.post.map(|amount| amount.to_string()),
+                    },
+                );
+            }
+        }
+    }
+
+    fn add_transaction_details(
+        &mut self,
+        client: &RpcClient,
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        // `getTransaction` does not serve `processed` transactions.
+        let commitment = CommitmentConfig::confirmed();
+        client.poll_for_signature_with_commitment(signature, commitment)?;
+
+        let confirmed = client.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(commitment),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        self.slot = Some(confirmed.slot);
+
+        let Some(meta) = confirmed.transaction.meta else {
+            return Ok(());
+        };
+        self.fee = Some(meta.fee);
+
+        let mut keys: Vec<String> = confirmed
+            .transaction
+            .transaction
+            .decode()
+            .map(|tx| {
+                tx.message
+                    .static_account_keys()
+                    .iter()
+                    .map(Pubkey::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let OptionSerializer::Some(loaded) = meta.loaded_addresses {
+            keys.extend(loaded.writable);
+            keys.extend(loaded.readonly);
+        }
+
+        for ((key, pre), post) in keys.iter().zip(meta.pre_balances).zip(meta.post_balances) {
+            self.balances.insert(key.clone(), BalanceChange { pre, post });
+        }
+
+        let pre_tokens = meta.pre_token_balances.unwrap_or(Vec::new());
+        let post_tokens = meta.post_token_balances.unwrap_or(Vec::new());
+        for (balance, is_post) in pre_tokens
+            .into_iter()
+            .map(|balance| (balance, false))
+            .chain(post_tokens.into_iter().map(|balance| (balance, true)))
+        {
+            let Some(key) = keys.get(balance.account_index as usize) else {
+                continue;
+            };
+            let change = self
+                .token_balances
+                .entry(key.clone())
+                .or_insert_with(|| TokenBalanceChange {
+                    mint: balance.mint.clone(),
+                    owner: balance.owner.clone().into(),
+                    decimals: balance.ui_token_amount.decimals,
+                    pre: None,
+                    post: None,
+                });
+            let amount = Some(balance.ui_token_amount.amount);
+            if is_post {
+                change.post = amount;
+            } else {
+                change.pre = amount;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CostReport {
+    pub fn new(estimate: &CostEstimate) -> Self {
+        CostReport {
+            signatures: estimate.signatures,
+            signature_fee: estimate.signature_fee,
+            priority_fee: estimate.priority_fee,
+            program_fees: estimate.program_fees,
+            rent: estimate
+                .rent
+                .iter()
+                .map(|deposit| {
+                    let report = RentReport {
+                        kind: deposit.kind.name(),
+                        space: deposit.space,
+                        lamports: deposit.lamports,
+                        funder: deposit.funder.to_string(),
+                    };
+                    (deposit.address.to_string(), report)
+                })
+                .collect(),
+            total: estimate.total(),
+            fee_payer: estimate.fee_payer.to_string(),
+            fee_payer_balance: estimate.fee_payer_balance,
+            sufficient: estimate.is_covered(),
+        }
+    }
+}
+
+/// Balances of an owner, printed by `portfolio`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioReport {
+    pub command: String,
+    pub owner: String,
+    pub lamports: u64,
+    pub sol: String,
+    pub tokens: Vec<HoldingReport>,
+    /// Rent of the token accounts that can be closed.
+    pub reclaimable_rent: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HoldingReport {
+    pub address: String,
+    pub mint: String,
+    pub token_program: String,
+    /// Base units of the mint.
+    pub amount: String,
+    pub ui_amount: String,
+    pub decimals: Option<u8>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub frozen: bool,
+    pub delegate: Option<String>,
+    pub delegated_amount: Option<String>,
+    /// Rent deposit of the account, in lamports.
+    pub rent: u64,
+    pub closable: bool,
+}
+
+impl PortfolioReport {
+    pub fn new(command: &str, portfolio: &Portfolio) -> Self {
+        PortfolioReport {
+            command: command.to_string(),
+            owner: portfolio.owner.to_string(),
+            lamports: portfolio.lamports,
+            sol: format_sol(portfolio.lamports),
+            tokens: portfolio
+                .tokens
+                .iter()
+                .map(|holding| HoldingReport {
+                    address: holding.address.to_string(),
+                    mint: holding.mint.to_string(),
+                    token_program: holding.token_program.to_string(),
+                    amount: holding.amount.to_string(),
+                    ui_amount: holding.ui_amount(),
+                    decimals: holding.decimals,
+                    name: holding.metadata.as_ref().map(|metadata| metadata.name.clone()),
+                    symbol: holding
+                        .metadata
+                        .as_ref()
+                        .map(|metadata| metadata.symbol.clone()),
+                    frozen: holding.frozen,
+                    delegate: holding
+                        .delegation
+                        .as_ref()
+                        .map(|delegation| delegation.delegate.to_string()),
+                    delegated_amount: holding
+                        .delegation
+                        .as_ref()
+                        .map(|delegation| delegation.amount.to_string()),
+                    rent: holding.lamports,
+                    closable: holding.is_closable(),
+                })
+                .collect(),
+            reclaimable_rent: portfolio.reclaimable_rent(),
+        }
+    }
+}
+
+/// Recent transactions of an address, printed by `history`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryReport {
+    pub command: String,
+    pub address: String,
+    pub transactions: Vec<HistoryEntryReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntryReport {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub time: Option<String>,
+    pub error: Option<String>,
+    pub fee: u64,
+    pub fee_payer: String,
+    pub lamports_delta: String,
+    pub sol_delta: String,
+    pub token_deltas: Vec<TokenDeltaReport>,
+    pub memos: Vec<String>,
+    pub programs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenDeltaReport {
+    pub account: String,
+    pub mint: String,
+    /// Base units of the mint.
+    pub delta: String,
+    pub ui_delta: String,
+}
+
+impl HistoryReport {
+    pub fn new(command: &str, address: &Pubkey, entries: &[HistoryEntry]) -> Self {
+        HistoryReport {
+            command: command.to_string(),
+            address: address.to_string(),
+            transactions: entries
+                .iter()
+                .map(|entry| HistoryEntryReport {
+                    signature: entry.signature.to_string(),
+                    slot: entry.slot,
+                    block_time: entry.block_time,
+                    time: entry.block_time.map(format_time),
+                    error: entry.error.as_ref().map(ToString::to_string),
+                    fee: entry.fee,
+                    fee_payer: entry.fee_payer.to_string(),
+                    lamports_delta: entry.sol_delta.to_string(),
+                    sol_delta: format_delta(entry.sol_delta, SOL_DECIMALS),
+                    token_deltas: entry
+                        .token_deltas
+                        .iter()
+                        .map(|token| TokenDeltaReport {
+                            account: token.account.to_string(),
+                            mint: token.mint.to_string(),
+                            delta: token.delta.to_string(),
+                            ui_delta: format_delta(token.delta, token.decimals),
+                        })
+                        .collect(),
+                    memos: entry.memos.clone(),
+                    programs: entry.program_names(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Instructions of a transaction, printed by `decode`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodeReport {
+    pub command: String,
+    pub signature: String,
+    pub instructions: Vec<InstructionReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstructionReport {
+    pub program_id: String,
+    pub program: Option<&'static str>,
+    pub name: Option<String>,
+    pub decoded: bool,
+    pub args: BTreeMap<&'static str, String>,
+    pub accounts: Vec<InstructionAccountReport>,
+    /// Base58 encoded.
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstructionAccountReport {
+    pub role: Option<&'static str>,
+    pub address: Option<String>,
+}
+
+impl DecodeReport {
+    pub fn new(command: &str, signature: &Signature, instructions: &[DecodedInstruction]) -> Self {
+        DecodeReport {
+            command: command.to_string(),
+            signature: signature.to_string(),
+            instructions: instructions.iter().map(InstructionReport::new).collect(),
+        }
+    }
+}
+
+impl InstructionReport {
+    pub fn new(instruction: &DecodedInstruction) -> Self {
+        InstructionReport {
+            program_id: instruction.program_id.to_string(),
+            program: instruction.program.map(|program| program.name()),
+            name: instruction.name.clone(),
+            decoded: instruction.decoded,
+            args: instruction.args.iter().cloned().collect(),
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|account| InstructionAccountReport {
+                    role: account.role,
+                    address: account.address.map(|address| address.to_string()),
+                })
+                .collect(),
+            data: bs58::encode(&instruction.data).into_string(),
+        }
+    }
+}
+
+/// A transaction file, printed by `offline-tx inspect`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionFileReport {
+    pub command: String,
+    pub message_version: &'static str,
+    pub blockhash: String,
+    pub fee_payer: String,
+    /// Signatures by signer, `None` where one is missing.
+    pub signers: BTreeMap<String, Option<String>>,
+    pub lookup_tables: Vec<String>,
+    pub instructions: Vec<InstructionReport>,
+}
+
+impl TransactionFileReport {
+    pub fn new(
+        command: &str,
+        transaction: &VersionedTransaction,
+        instructions: &[DecodedInstruction],
+    ) -> Self {
+        let message = &transaction.message;
+        let keys = message.static_account_keys();
+        let required = usize::from(message.header().num_required_signatures);
+
+        TransactionFileReport {
+            command: command.to_string(),
+            message_version: match message {
+                VersionedMessage::Legacy(_) => "legacy",
+                VersionedMessage::V0(_) => "v0",
+            },
+            blockhash: message.recent_blockhash().to_string(),
+            fee_payer: keys[0].to_string(),
+            signers: keys
+                .iter()
+                .zip(&transaction.signatures)
+                .take(required)
+                .map(|(pubkey, signature)| {
+                    let signature = (*signature != Signature::default())
+                        .then(|| signature.to_string());
+                    (pubkey.to_string(), signature)
+                })
+                .collect(),
+            lookup_tables: message
+                .address_table_lookups()
+                .unwrap_or_default()
+                .iter()
+                .map(|lookup| lookup.account_key.to_string())
+                .collect(),
+            instructions: instructions.iter().map(InstructionReport::new).collect(),
+        }
+    }
+}
+
+/// Outcome of a payout file, printed by `sol payout`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PayoutFileReport {
+    pub command: String,
+    /// `complete` once everything is paid, else `incomplete`.
+    pub status: &'static str,
+    pub requested: TallyReport,
+    pub paid: TallyReport,
+    pub failed: TallyReport,
+    pub pending: TallyReport,
+    pub unknown: TallyReport,
+    pub transactions: usize,
+    pub unpaid: Vec<UnpaidReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TallyReport {
+    pub count: usize,
+    pub lamports: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnpaidReport {
+    pub line: u64,
+    pub recipient: String,
+    pub lamports: u64,
+    pub status: PayoutStatus,
+    pub signature: Option<String>,
+    pub error: Option<String>,
+}
+
+impl PayoutFileReport {
+    pub fn new(command: &str, report: &PayoutReport) -> Self {
+        let tally = |tally: &Tally| TallyReport {
+            count: tally.count,
+            lamports: tally.lamports,
+        };
+
+        PayoutFileReport {
+            command: command.to_string(),
+            status: if report.is_complete() {
+                "complete"
+            } else {
+                "incomplete"
+            },
+            requested: tally(&report.requested),
+            paid: tally(&report.paid),
+            failed: tally(&report.failed),
+            pending: tally(&report.pending),
+            unknown: tally(&report.unknown),
+            transactions: report.transactions,
+            unpaid: report
+                .unpaid
+                .iter()
+                .map(|record| UnpaidReport {
+                    line: record.line,
+                    recipient: record.recipient.clone(),
+                    lamports: record.lamports,
+                    status: record.status,
+                    signature: record.signature.clone(),
+                    error: record.error.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Encrypted memos sent and received, printed by `decrypt`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecryptReport {
+    pub command: String,
+    pub address: String,
+    pub memos: Vec<MemoReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoReport {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub time: Option<String>,
+    pub sender: String,
+    pub recipient: String,
+    pub memo: Option<String>,
+    /// Why the memo could not be decrypted.
+    pub error: Option<String>,
+}
+
+impl DecryptReport {
+    pub fn new(command: &str, address: &Pubkey, memos: &[ReceivedMemo]) -> Self {
+        DecryptReport {
+            command: command.to_string(),
+            address: address.to_string(),
+            memos: memos
+                .iter()
+                .map(|memo| MemoReport {
+                    signature: memo.signature.to_string(),
+                    slot: memo.slot,
+                    block_time: memo.block_time,
+                    time: memo.block_time.map(format_time),
+                    sender: memo.sender.to_string(),
+                    recipient: memo.recipient.to_string(),
+                    memo: memo.memo.as_ref().ok().cloned(),
+                    error: memo.memo.as_ref().err().map(ToString::to_string),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A Solana Pay URL, printed by `pay request`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PayRequestReport {
+    pub command: String,
+    pub url: String,
+    pub recipient: String,
+    pub references: Vec<String>,
+}
+
+impl PayRequestReport {
+    pub fn new(command: &str, request: &TransferRequest) -> Self {
+        PayRequestReport {
+            command: command.to_string(),
+            url: request.to_string(),
+            recipient: request.recipient.to_string(),
+            references: request.references.iter().map(Pubkey::to_string).collect(),
+        }
+    }
+}
+
+/// The transaction paying a request, printed by `pay verify`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentReport {
+    pub command: String,
+    pub status: &'static str,
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub time: Option<String>,
+    pub payer: String,
+    pub recipient: String,
+    pub spl_token: Option<String>,
+    /// Base units of the mint, or lamports.
+    pub received: String,
+    pub ui_received: String,
+}
+
+impl PaymentReport {
+    pub fn new(command: &str, request: &TransferRequest, payment: &Payment) -> Self {
+        PaymentReport {
+            command: command.to_string(),
+            status: "paid",
+            signature: payment.signature.to_string(),
+            slot: payment.slot,
+            block_time: payment.block_time,
+            time: payment.block_time.map(format_time),
+            payer: payment.payer.to_string(),
+            recipient: request.recipient.to_string(),
+            spl_token: request.spl_token.map(|mint| mint.to_string()),
+            received: payment.received.to_string(),
+            ui_received: format_amount(payment.received, payment.decimals),
+        }
+    }
+}
+
+/// A lookup table, printed by `lookup-table inspect`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LookupTableReport {
+    pub command: String,
+    pub address: String,
+    /// `None` once the table is frozen.
+    pub authority: Option<String>,
+    pub deactivation_slot: Option<u64>,
+    pub last_extended_slot: u64,
+    pub addresses: Vec<String>,
+}
+
+impl LookupTableReport {
+    pub fn new(command: &str, info: &LookupTableInfo) -> Self {
+        LookupTableReport {
+            command: command.to_string(),
+            address: info.address.to_string(),
+            authority: info.authority.map(|authority| authority.to_string()),
+            deactivation_slot: info.deactivation_slot,
+            last_extended_slot: info.last_extended_slot,
+            addresses: info.addresses.iter().map(Pubkey::to_string).collect(),
+        }
+    }
+}
+
+/// A nonce account, printed by `nonce-account inspect`.
+#[derive(Debug, Clone, Serialize)]
+pub struct NonceReport {
+    pub command: String,
+    pub address: String,
+    pub authority: String,
+    /// The stored nonce, used as the blockhash of transactions.
+    pub nonce: String,
+    pub lamports_per_signature: u64,
+    pub balance: u64,
+}
+
+impl NonceReport {
+    pub fn new(command: &str, info: &NonceInfo) -> Self {
+        NonceReport {
+            command: command.to_string(),
+            address: info.address.to_string(),
+            authority: info.authority.to_string(),
+            nonce: info.blockhash.to_string(),
+            lamports_per_signature: info.lamports_per_signature,
+            balance: info.balance,
+        }
+    }
+}
+
+/// The object printed for a failed command.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    pub command: String,
+    pub status: &'static str,
+    pub error: ErrorDetails,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorDetails {
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl ErrorReport {
+    pub fn new(command: &str, error: &Error) -> Self {
+        ErrorReport {
+            command: command.to_string(),
+            status: "error",
+            error: ErrorDetails {
+                kind: error.kind(),
+                message: error.to_string(),
+            },
+        }
+    }
+}
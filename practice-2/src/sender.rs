@@ -0,0 +1,339 @@
+//! Sending transactions until they are confirmed.
+//!
+//! [`send_and_confirm`] sends a signed transaction and rebroadcasts it with
+//! exponential backoff until it is confirmed. When its blockhash expires
+//! (the block height passes the last valid block height returned with the
+//! blockhash) and it has not landed, it can no longer land, so it is
+//! re-signed with a fresh blockhash and sent again. A transaction is only
+//! ever re-signed after the previous version expired, and rebroadcasting
+//! the same signed transaction cannot execute it twice, so retries never
+//! duplicate a transfer or a mint.
+//!
+//! Errors are split by [`classify`]: network problems and overloaded nodes
+//! are retried, an expired blockhash leads to re-signing, anything the
+//! transaction itself did wrong (e.g. insufficient funds) fails at once.
+
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_client::rpc_custom_error::{
+    JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE, JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED,
+    JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY, JSON_RPC_SERVER_ERROR_SLOT_SKIPPED,
+};
+use solana_client::rpc_request::RpcError;
+use solana_program::hash::Hash;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use solana_transaction_status::TransactionStatus;
+use thiserror::Error;
+
+/// How often the signature status is polled between rebroadcasts.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Error)]
+pub enum SendError {
+    #[error("transaction {signature} failed: {error}")]
+    Failed {
+        signature: Signature,
+        error: TransactionError,
+    },
+    #[error("transaction {signature} was not confirmed after {attempts} attempts")]
+    NotConfirmed { signature: Signature, attempts: u32 },
+    #[error("blockhash expired {resigns} times without the transaction landing")]
+    Expired { resigns: u32 },
+}
+
+/// Retry limits of [`send_and_confirm`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendConfig {
+    /// Broadcasts of one signed transaction. Ignored while a fresh
+    /// blockhash is used: the blockhash expiry ends those.
+    pub max_attempts: u32,
+    /// Times the transaction is re-signed with a fresh blockhash.
+    pub max_resigns: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for SendConfig {
+    fn default() -> Self {
+        SendConfig {
+            max_attempts: 10,
+            max_resigns: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+impl SendConfig {
+    /// Wait before rebroadcast number `attempt`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        backoff(self.initial_backoff, self.max_backoff, attempt)
+    }
+}
+
+/// Exponential backoff: `initial` before attempt 1 (counting from 1),
+/// doubling with every attempt up to `max`.
+pub fn backoff(initial: Duration, max: Duration, attempt: u32) -> Duration {
+    initial
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(max)
+}
+
+/// What to do about a failed RPC call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Try the same call again later.
+    Retry,
+    /// The blockhash is unknown to the node: wait for expiry and re-sign.
+    BlockhashNotFound,
+    /// The transaction already landed.
+    AlreadyProcessed,
+    /// Give up.
+    Fatal,
+}
+
+/// Sorts an RPC error into an [`ErrorClass`].
+pub fn classify(error: &ClientError) -> ErrorClass {
+    match error.get_transaction_error() {
+        Some(TransactionError::BlockhashNotFound) => return ErrorClass::BlockhashNotFound,
+        Some(TransactionError::AlreadyProcessed) => return ErrorClass::AlreadyProcessed,
+        Some(TransactionError::WouldExceedAccountDataBlockLimit)
+        | Some(TransactionError::WouldExceedMaxBlockCostLimit)
+        | Some(TransactionError::WouldExceedMaxAccountCostLimit)
+        | Some(TransactionError::WouldExceedAccountDataTotalLimit) => return ErrorClass::Retry,
+        Some(_) => return ErrorClass::Fatal,
+        None => {}
+    }
+
+    match error.kind() {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => ErrorClass::Retry,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })
+            if [
+                JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+                JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+                JSON_RPC_SERVER_ERROR_SLOT_SKIPPED,
+                JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED,
+            ]
+            .contains(code) =>
+        {
+            ErrorClass::Retry
+        }
+        _ => ErrorClass::Fatal,
+    }
+}
+
+/// The transaction as it is being sent: its latest signed version and
+/// what is known about its blockhash.
+pub(crate) struct Attempt {
+    pub(crate) transaction: VersionedTransaction,
+    /// Set when the blockhash was fetched for this transaction, so it can
+    /// be replaced on expiry; `None` for a given blockhash or nonce.
+    pub(crate) last_valid_block_height: Option<u64>,
+    pub(crate) broadcasts: u32,
+    pub(crate) resigns: u32,
+}
+
+impl Attempt {
+    pub(crate) fn new(transaction: VersionedTransaction, last_valid_block_height: Option<u64>) -> Self {
+        Attempt {
+            transaction,
+            last_valid_block_height,
+            broadcasts: 0,
+            resigns: 0,
+        }
+    }
+
+    pub(crate) fn signature(&self) -> Signature {
+        self.transaction.signatures[0]
+    }
+
+    /// The first broadcast of a signed transaction runs preflight checks so
+    /// errors surface early; rebroadcasts skip them, as they would only
+    /// report the transaction as already processed.
+    pub(crate) fn send_config(&self, commitment: CommitmentConfig) -> RpcSendTransactionConfig {
+        RpcSendTransactionConfig {
+            skip_preflight: self.broadcasts > 1,
+            preflight_commitment: Some(commitment.commitment),
+            max_retries: Some(0),
+            ..RpcSendTransactionConfig::default()
+        }
+    }
+
+    /// Handles the result of a broadcast. Errors worth retrying are left
+    /// to the next round, as is a blockhash the node does not know yet.
+    pub(crate) fn broadcast_result(
+        &self,
+        result: Result<Signature, ClientError>,
+    ) -> Result<(), crate::Error> {
+        match result {
+            Ok(_) => Ok(()),
+            Err(error) => match classify(&error) {
+                ErrorClass::Retry
+                | ErrorClass::BlockhashNotFound
+                | ErrorClass::AlreadyProcessed => Ok(()),
+                ErrorClass::Fatal => Err(error.into()),
+            },
+        }
+    }
+
+    /// Turns a signature status into the final result, if it is final.
+    pub(crate) fn status_result(
+        &self,
+        status: Option<TransactionStatus>,
+        commitment: CommitmentConfig,
+    ) -> Option<Result<Signature, crate::Error>> {
+        let status = status?;
+        if let Some(error) = status.err {
+            return Some(Err(SendError::Failed {
+                signature: self.signature(),
+                error,
+            }
+            .into()));
+        }
+
+        status
+            .satisfies_commitment(commitment)
+            .then(|| Ok(self.signature()))
+    }
+
+    /// Whether the blockhash of the current signed version expired at
+    /// `block_height`.
+    pub(crate) fn expired(&self, block_height: u64) -> bool {
+        self.last_valid_block_height
+            .is_some_and(|last_valid| block_height > last_valid)
+    }
+
+    /// Whether the broadcasts of a transaction that cannot be re-signed
+    /// are used up.
+    pub(crate) fn exhausted(&self, config: &SendConfig) -> bool {
+        self.last_valid_block_height.is_none() && self.broadcasts >= config.max_attempts
+    }
+
+    pub(crate) fn not_confirmed(&self) -> crate::Error {
+        SendError::NotConfirmed {
+            signature: self.signature(),
+            attempts: self.broadcasts,
+        }
+        .into()
+    }
+
+    /// Replaces the expired transaction with one `sign` signs with
+    /// `blockhash`.
+    pub(crate) fn resign(
+        &mut self,
+        sign: impl FnOnce(Hash) -> Result<VersionedTransaction, crate::Error>,
+        blockhash: Hash,
+        last_valid_block_height: u64,
+        config: &SendConfig,
+    ) -> Result<(), crate::Error> {
+        if self.resigns >= config.max_resigns {
+            return Err(SendError::Expired {
+                resigns: self.resigns,
+            }
+            .into());
+        }
+
+        self.transaction = sign(blockhash)?;
+        self.last_valid_block_height = Some(last_valid_block_height);
+        self.broadcasts = 0;
+        self.resigns += 1;
+
+        Ok(())
+    }
+}
+
+/// Sends the signed `transaction` until it is confirmed at the client's
+/// commitment, re-signing it with `sign` when its blockhash expires.
+///
+/// `last_valid_block_height` belongs to the blockhash of `transaction`;
+/// pass `None` for a blockhash that must not be replaced (a given one or a
+/// durable nonce), which limits sending to [`SendConfig::max_attempts`].
+pub fn send_and_confirm(
+    client: &RpcClient,
+    sign: impl Fn(Hash) -> Result<VersionedTransaction, crate::Error>,
+    transaction: VersionedTransaction,
+    last_valid_block_height: Option<u64>,
+    config: &SendConfig,
+) -> Result<Signature, crate::Error> {
+    let commitment = client.commitment();
+    let mut attempt = Attempt::new(transaction, last_valid_block_height);
+
+    loop {
+        attempt.broadcasts += 1;
+        let result = client
+            .send_transaction_with_config(&attempt.transaction, attempt.send_config(commitment));
+        attempt.broadcast_result(result)?;
+
+        let deadline = Instant::now() + config.backoff(attempt.broadcasts);
+        loop {
+            if let Some(status) = signature_status(client, &attempt.signature())? {
+                if let Some(result) = attempt.status_result(status, commitment) {
+                    return result;
+                }
+            }
+            if Instant::now() >= deadline {
+                break;
+            }
+            sleep(POLL_INTERVAL);
+        }
+
+        if attempt.exhausted(config) {
+            return Err(attempt.not_confirmed());
+        }
+
+        let expired = expired_result(&attempt, client.get_block_height())?;
+        // Checked after the block height: once that is past the last valid
+        // block height, a transaction that has not landed never will.
+        if expired && signature_status(client, &attempt.signature())? == Some(None) {
+            let (blockhash, last_valid) =
+                client.get_latest_blockhash_with_commitment(commitment)?;
+            attempt.resign(&sign, blockhash, last_valid, config)?;
+        }
+    }
+}
+
+/// Handles the result of fetching the block height: whether the blockhash
+/// expired, `false` if the height could not be fetched for a reason worth
+/// retrying.
+pub(crate) fn expired_result(
+    attempt: &Attempt,
+    result: Result<u64, ClientError>,
+) -> Result<bool, crate::Error> {
+    match result {
+        Ok(height) => Ok(attempt.expired(height)),
+        Err(error) if classify(&error) == ErrorClass::Retry => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Handles the result of fetching the status of one signature: `None` if
+/// it could not be fetched for a reason worth retrying, `Some(None)` if
+/// the transaction is unknown.
+pub(crate) fn status_response(
+    result: Result<Vec<Option<TransactionStatus>>, ClientError>,
+) -> Result<Option<Option<TransactionStatus>>, crate::Error> {
+    match result {
+        Ok(statuses) => Ok(Some(statuses.into_iter().next().flatten())),
+        Err(error) if classify(&error) == ErrorClass::Retry => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// The status of `signature`: `None` if it could not be fetched for a
+/// reason worth retrying, `Some(None)` if the transaction is unknown.
+fn signature_status(
+    client: &RpcClient,
+    signature: &Signature,
+) -> Result<Option<Option<TransactionStatus>>, crate::Error> {
+    status_response(
+        client
+            .get_signature_statuses(&[*signature])
+            .map(|statuses| statuses.value),
+    )
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::signer::null_signer::NullSigner;
+use solana_sdk::signer::presigner::Presigner;
+
+use crate::keypair::KEYPAIR_VAR;
+use crate::remote_signer::RemoteSigner;
+use crate::{Error, KeypairSource};
+
+/// Where signatures come from.
+///
+/// Parsed from a string with [`FromStr`]:
+/// - `http://...` or `https://...` is a [`RemoteSigner`] service,
+/// - `PUBKEY=SIGNATURE` is a signature produced elsewhere (a
+///   [`Presigner`]); it only works for a transaction with the exact same
+///   message, including the blockhash,
+/// - a bare `PUBKEY` that is not an existing file is a [`NullSigner`]: the
+///   signature is left empty, to be added later with `offline-tx sign`,
+/// - anything else is a [`KeypairSource`] (file, `env:NAME`, `-`,
+///   `cli-config`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerSource {
+    Keypair(KeypairSource),
+    Presigned { pubkey: Pubkey, signature: Signature },
+    Pubkey(Pubkey),
+    Remote(String),
+}
+
+impl SignerSource {
+    /// Picks a source when none was given explicitly: the `KEYPAIR`
+    /// variable parsed as a [`SignerSource`], otherwise the keypair lookup
+    /// of [`KeypairSource::discover`].
+    pub fn discover() -> Result<Self, Error> {
+        dotenv::dotenv().ok();
+
+        match std::env::var(KEYPAIR_VAR) {
+            Ok(source) => source.parse(),
+            Err(_) => Ok(SignerSource::Keypair(KeypairSource::discover()?)),
+        }
+    }
+
+    /// Opens the signer: reads the keypair, or connects to the service.
+    pub fn signer(&self) -> Result<Box<dyn Signer + Send + Sync>, Error> {
+        Ok(match self {
+            SignerSource::Keypair(source) => Box::new(source.load()?),
+            SignerSource::Presigned { pubkey, signature } => {
+                Box::new(Presigner::new(pubkey, signature))
+            }
+            SignerSource::Pubkey(pubkey) => Box::new(NullSigner::new(pubkey)),
+            SignerSource::Remote(url) => Box::new(RemoteSigner::connect(url)?),
+        })
+    }
+}
+
+impl FromStr for SignerSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("http://") || s.starts_with("https://") {
+            return Ok(SignerSource::Remote(s.to_string()));
+        }
+
+        if let Some((pubkey, signature)) = s.split_once('=') {
+            if let (Ok(pubkey), Ok(signature)) = (pubkey.parse(), signature.parse()) {
+                return Ok(SignerSource::Presigned { pubkey, signature });
+            }
+        }
+
+        if let Ok(pubkey) = s.parse() {
+            if !Path::new(s).exists() {
+                return Ok(SignerSource::Pubkey(pubkey));
+            }
+        }
+
+        Ok(SignerSource::Keypair(s.parse::<KeypairSource>()?))
+    }
+}
+
+impl fmt::Display for SignerSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerSource::Keypair(source) => source.fmt(f),
+            SignerSource::Presigned { pubkey, .. } => write!(f, "presigned {pubkey}"),
+            SignerSource::Pubkey(pubkey) => write!(f, "offline signer {pubkey}"),
+            SignerSource::Remote(url) => write!(f, "signing service {url}"),
+        }
+    }
+}
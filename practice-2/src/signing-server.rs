@@ -0,0 +1,26 @@
+use solana_sdk::signer::Signer;
+use tiny_http::Server;
+
+use practice_2::remote_signer::{serve, SIGNING_TOKEN_VAR};
+use practice_2::{Error, load_keypair};
+
+/// Address the server listens on, unless `SIGNING_SERVER_ADDR` is set.
+const DEFAULT_ADDR: &str = "127.0.0.1:9191";
+
+/// Local stand-in for the signing service used by `KEYPAIR=http://...`.
+/// Signs with the keypair `load_keypair` finds, e.g. `SECRET_KEY` from `.env`.
+pub fn main() -> Result<(), Error> {
+    let keypair = load_keypair()?;
+    let addr = std::env::var("SIGNING_SERVER_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+    let token = std::env::var(SIGNING_TOKEN_VAR).ok();
+
+    let server = Server::http(&addr).map_err(std::io::Error::other)?;
+
+    println!("🔏 Signing for {} on http://{}", keypair.pubkey(), addr);
+
+    serve(&server, &keypair, token.as_deref(), |message, signature| {
+        println!("✍️ Signed {} byte message: {signature}", message.len())
+    });
+
+    Ok(())
+}
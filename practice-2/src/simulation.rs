@@ -0,0 +1,336 @@
+//! Dry runs: simulating transactions instead of sending them.
+//!
+//! With [`crate::TransactionConfig::dry_run`] set, [`crate::transaction`]
+//! builds the transaction as usual, compute budget included, and hands it
+//! to `simulateTransaction`, which executes it against the current state of
+//! the cluster without broadcasting anything. Signatures are not checked,
+//! so nothing is signed either.
+//!
+//! The [`Simulation`] holds the program logs, the compute units consumed
+//! and the lamports and token balances of every writable account before
+//! and after, read back from the simulation.
+
+use std::fmt;
+
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::amount::format_amount;
+use crate::fees;
+
+/// Variable that turns on dry runs when set to anything but `0` or
+/// `false`.
+pub const DRY_RUN_VAR: &str = "DRY_RUN";
+
+/// What a transaction would do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Simulation {
+    /// Why the transaction would fail, `None` if it would succeed.
+    pub error: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    /// Every writable account, in the order of the message. Empty if the
+    /// simulation failed before the accounts could be read back.
+    pub accounts: Vec<AccountChange>,
+}
+
+/// A writable account before and after the transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountChange {
+    pub address: Pubkey,
+    /// 0 for an account the transaction creates.
+    pub pre_lamports: u64,
+    /// 0 for an account the transaction closes.
+    pub post_lamports: u64,
+    /// Set if the account is an SPL Token or Token-2022 account before or
+    /// after.
+    pub token: Option<TokenChange>,
+}
+
+/// Tokens held by a token account before and after the transaction, in
+/// base units. `pre` is `None` for an account the transaction creates,
+/// `post` for one it closes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenChange {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub decimals: u8,
+    pub pre: Option<u64>,
+    pub post: Option<u64>,
+}
+
+impl fmt::Display for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            None => write!(f, "dry run: the transaction would succeed")?,
+            Some(error) => write!(f, "dry run: the transaction would fail: {error}")?,
+        }
+        if let Some(units) = self.units_consumed {
+            write!(f, ", {units} compute units consumed")?;
+        }
+
+        if !self.logs.is_empty() {
+            write!(f, "\nLogs:")?;
+            for log in &self.logs {
+                write!(f, "\n  {log}")?;
+            }
+        }
+
+        if !self.accounts.is_empty() {
+            write!(f, "\nWritable accounts:")?;
+        }
+        for account in &self.accounts {
+            write!(
+                f,
+                "\n  {}: {} -> {} lamports ({:+})",
+                account.address,
+                account.pre_lamports,
+                account.post_lamports,
+                i128::from(account.post_lamports) - i128::from(account.pre_lamports),
+            )?;
+            if let Some(token) = &account.token {
+                let amount = |amount: Option<u64>| match amount {
+                    Some(amount) => format_amount(amount, token.decimals),
+                    None => "none".to_string(),
+                };
+                write!(
+                    f,
+                    "\n    {} -> {} of {}",
+                    amount(token.pre),
+                    amount(token.post),
+                    token.mint,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Simulates `transaction` and reads back its writable accounts, resolving
+/// the ones loaded from `lookup_tables`.
+pub fn simulate(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Simulation, crate::Error> {
+    let addresses = writable_accounts(&transaction.message, lookup_tables);
+    let pre = client
+        .get_multiple_accounts_with_commitment(&addresses, client.commitment())?
+        .value;
+    let result = client
+        .simulate_transaction_with_config(
+            transaction,
+            simulation_config(client.commitment(), &addresses),
+        )?
+        .value;
+
+    let states = AccountStates::new(addresses, pre, &result);
+    let mints = states.unknown_mints();
+    let mint_accounts = if mints.is_empty() {
+        Vec::new()
+    } else {
+        client
+            .get_multiple_accounts_with_commitment(&mints, client.commitment())?
+            .value
+    };
+
+    Ok(states.simulation(result, &mints, mint_accounts))
+}
+
+/// The accounts `message` may write, static ones first, then the ones it
+/// loads from `lookup_tables`. Tables the message uses but that are not in
+/// `lookup_tables` are skipped.
+pub fn writable_accounts(
+    message: &VersionedMessage,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Vec<Pubkey> {
+    let keys = message.static_account_keys();
+    let mut accounts: Vec<Pubkey> = (0..keys.len())
+        .filter(|&index| message.is_maybe_writable(index, None))
+        .map(|index| keys[index])
+        .collect();
+
+    for lookup in message.address_table_lookups().unwrap_or_default() {
+        let Some(table) = lookup_tables
+            .iter()
+            .find(|table| table.key == lookup.account_key)
+        else {
+            continue;
+        };
+        accounts.extend(
+            lookup
+                .writable_indexes
+                .iter()
+                .filter_map(|&index| table.addresses.get(usize::from(index))),
+        );
+    }
+
+    accounts
+}
+
+/// [`fees::simulation_config`] that also returns the accounts at
+/// `addresses` as they are after the transaction.
+pub(crate) fn simulation_config(
+    commitment: CommitmentConfig,
+    addresses: &[Pubkey],
+) -> RpcSimulateTransactionConfig {
+    RpcSimulateTransactionConfig {
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: addresses.iter().map(Pubkey::to_string).collect(),
+        }),
+        ..fees::simulation_config(commitment)
+    }
+}
+
+/// The writable accounts of a simulated transaction, before and after.
+/// Shared by the blocking and the [`crate::nonblocking`] pipeline, which
+/// only differ in how they fetch.
+pub(crate) struct AccountStates {
+    addresses: Vec<Pubkey>,
+    pre: Vec<Option<Account>>,
+    /// `None` if the simulation returned no accounts.
+    post: Option<Vec<Option<Account>>>,
+}
+
+impl AccountStates {
+    pub(crate) fn new(
+        addresses: Vec<Pubkey>,
+        pre: Vec<Option<Account>>,
+        result: &RpcSimulateTransactionResult,
+    ) -> Self {
+        let post = result.accounts.as_ref().map(|accounts| {
+            accounts
+                .iter()
+                .map(|account| account.as_ref().and_then(UiAccount::decode::<Account>))
+                .collect()
+        });
+
+        AccountStates {
+            addresses,
+            pre,
+            post,
+        }
+    }
+
+    fn all(&self) -> impl Iterator<Item = &Account> {
+        self.pre.iter().chain(self.post.iter().flatten()).flatten()
+    }
+
+    /// Mints of the token accounts whose decimals cannot be read from the
+    /// accounts themselves, to be fetched.
+    pub(crate) fn unknown_mints(&self) -> Vec<Pubkey> {
+        let mut mints: Vec<Pubkey> = Vec::new();
+        for (mint, _, _) in self.all().filter_map(token_account) {
+            if self.decimals(&mint).is_none() && !mints.contains(&mint) {
+                mints.push(mint);
+            }
+        }
+
+        mints
+    }
+
+    /// Decimals of `mint` if it is one of the writable accounts.
+    fn decimals(&self, mint: &Pubkey) -> Option<u8> {
+        let index = self.addresses.iter().position(|address| address == mint)?;
+        let post = self.post.as_ref().and_then(|post| post[index].as_ref());
+
+        post.or(self.pre[index].as_ref()).and_then(mint_decimals)
+    }
+
+    /// The [`Simulation`] from its `result`, with the `mints` returned by
+    /// [`AccountStates::unknown_mints`] fetched into `mint_accounts`.
+    pub(crate) fn simulation(
+        self,
+        result: RpcSimulateTransactionResult,
+        mints: &[Pubkey],
+        mint_accounts: Vec<Option<Account>>,
+    ) -> Simulation {
+        let fetched: Vec<(Pubkey, u8)> = mints
+            .iter()
+            .zip(mint_accounts)
+            .filter_map(|(mint, account)| Some((*mint, mint_decimals(&account?)?)))
+            .collect();
+        let decimals = |mint: &Pubkey| {
+            self.decimals(mint).or_else(|| {
+                fetched
+                    .iter()
+                    .find(|(fetched, _)| fetched == mint)
+                    .map(|(_, decimals)| *decimals)
+            })
+        };
+
+        let accounts = match &self.post {
+            None => Vec::new(),
+            Some(post) => self
+                .addresses
+                .iter()
+                .zip(&self.pre)
+                .zip(post)
+                .map(|((address, pre), post)| {
+                    let pre_token = pre.as_ref().and_then(token_account);
+                    let post_token = post.as_ref().and_then(token_account);
+                    let token = pre_token.or(post_token).and_then(|(mint, owner, _)| {
+                        Some(TokenChange {
+                            mint,
+                            owner,
+                            decimals: decimals(&mint)?,
+                            pre: pre_token.map(|(_, _, amount)| amount),
+                            post: post_token.map(|(_, _, amount)| amount),
+                        })
+                    });
+
+                    AccountChange {
+                        address: *address,
+                        pre_lamports: pre.as_ref().map_or(0, |account| account.lamports),
+                        post_lamports: post.as_ref().map_or(0, |account| account.lamports),
+                        token,
+                    }
+                })
+                .collect(),
+        };
+
+        Simulation {
+            error: result.err,
+            logs: result.logs.unwrap_or_default(),
+            units_consumed: result.units_consumed,
+            accounts,
+        }
+    }
+}
+
+fn is_token_program(program: &Pubkey) -> bool {
+    *program == spl_token::id() || *program == spl_token_2022::id()
+}
+
+/// Mint, owner and amount of a token account.
+pub(crate) fn token_account(account: &Account) -> Option<(Pubkey, Pubkey, u64)> {
+    if !is_token_program(&account.owner) {
+        return None;
+    }
+    let state =
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data).ok()?;
+
+    Some((state.base.mint, state.base.owner, state.base.amount))
+}
+
+fn mint_decimals(account: &Account) -> Option<u8> {
+    if !is_token_program(&account.owner) {
+        return None;
+    }
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data).ok()?;
+
+    Some(state.base.decimals)
+}
@@ -0,0 +1,56 @@
+use solana_client::rpc_client::RpcClient;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::transfer;
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::encrypted_memo::encrypt_memo;
+use crate::transaction::process_transaction;
+use crate::{Error, TransactionConfig, TransactionOutcome};
+
+/// Instructions of [`send_sol_with_memo`].
+pub fn send_sol_with_memo_instructions(
+    sender: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    memo: Option<&str>,
+) -> Vec<Instruction> {
+    let mut instructions = vec![transfer(sender, recipient, amount)];
+
+    if let Some(memo) = memo {
+        instructions.push(spl_memo::build_memo(memo.as_bytes(), &[sender]));
+    }
+
+    instructions
+}
+
+/// Sends `amount` lamports from `sender` to `recipient`, with an optional
+/// memo signed by the sender.
+pub fn send_sol_with_memo(
+    client: &RpcClient,
+    sender: &dyn Signer,
+    recipient: &Pubkey,
+    amount: u64,
+    memo: Option<&str>,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let instructions =
+        send_sol_with_memo_instructions(&sender.pubkey(), recipient, amount, memo);
+
+    process_transaction(client, &instructions, &[sender], config)
+}
+
+/// Like [`send_sol_with_memo`], with the memo encrypted for `recipient` by
+/// [`encrypt_memo`], so only the two parties can read it.
+pub fn send_sol_with_encrypted_memo(
+    client: &RpcClient,
+    sender: &Keypair,
+    recipient: &Pubkey,
+    amount: u64,
+    memo: &str,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let envelope = encrypt_memo(sender, recipient, memo)?;
+
+    send_sol_with_memo(client, sender, recipient, amount, Some(&envelope), config)
+}
@@ -0,0 +1,468 @@
+//! Solana Pay transfer requests.
+//!
+//! A [`TransferRequest`] is the `solana:<recipient>?amount=...` URL a shop
+//! shows as a link or QR code. [`pay`] sends the transfer it asks for, SOL
+//! or an SPL token, with the memo first and the reference keys attached to
+//! the transfer instruction. The shop then finds the payment with
+//! [`find_payment`], looking up transactions by a reference key and
+//! checking what the recipient received.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::transfer;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{Signature, Signer};
+use solana_transaction_status::UiTransactionEncoding;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use thiserror::Error;
+use url::{form_urlencoded, Url};
+
+use crate::amount::{format_amount, format_delta, mint_decimals, AmountError, SOL_DECIMALS};
+use crate::escrow::mint_token_program;
+use crate::history::{format_time, transaction_keys, HistoryEntry, MAX_SIGNATURES_PER_PAGE};
+use crate::transaction::process_transaction;
+use crate::{Amount, TransactionConfig, TransactionOutcome};
+
+/// Scheme of Solana Pay URLs.
+pub const SCHEME: &str = "solana";
+
+#[derive(Debug, Error)]
+pub enum PayError {
+    #[error("invalid Solana Pay URL: {0}")]
+    InvalidUrl(String),
+    #[error("the payment request has no amount")]
+    NoAmount,
+    #[error("the payment request has no reference to find the payment by")]
+    NoReference,
+    #[error("{owner} has no token account for {mint}")]
+    NoTokenAccount { owner: Pubkey, mint: Pubkey },
+    #[error("no transaction refers to {0}")]
+    NotFound(Pubkey),
+    #[error("{signature} does not pay the request: {reason}")]
+    Mismatch {
+        signature: Signature,
+        reason: String,
+    },
+    #[error("{signature} could not be fetched, it may be the payment: {reason}")]
+    Unavailable {
+        signature: Signature,
+        reason: String,
+    },
+}
+
+/// A Solana Pay transfer request: who to pay, how much and of what.
+///
+/// Parsed from and displayed as a `solana:` URL. Without `spl_token` the
+/// amount is in SOL, otherwise in tokens of that mint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferRequest {
+    pub recipient: Pubkey,
+    pub amount: Option<Amount>,
+    pub spl_token: Option<Pubkey>,
+    /// Keys added to the transfer instruction, to find the payment by.
+    pub references: Vec<Pubkey>,
+    /// Who asks for the payment, e.g. the shop.
+    pub label: Option<String>,
+    /// What the payment is for, e.g. the order.
+    pub message: Option<String>,
+    /// Written on chain with the memo program.
+    pub memo: Option<String>,
+}
+
+impl TransferRequest {
+    /// A request to pay `recipient`, with nothing else filled in.
+    pub fn new(recipient: Pubkey) -> Self {
+        TransferRequest {
+            recipient,
+            amount: None,
+            spl_token: None,
+            references: Vec::new(),
+            label: None,
+            message: None,
+            memo: None,
+        }
+    }
+
+    /// What is paid: `SOL` or the mint.
+    pub fn currency(&self) -> String {
+        self.spl_token
+            .map_or_else(|| "SOL".to_string(), |mint| mint.to_string())
+    }
+}
+
+impl FromStr for TransferRequest {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |message: String| PayError::InvalidUrl(message);
+        let url = Url::parse(s).map_err(|error| invalid(error.to_string()))?;
+        if url.scheme() != SCHEME {
+            return Err(invalid(format!("the scheme is not `{SCHEME}:`")).into());
+        }
+        if url.path().starts_with("http") {
+            return Err(invalid("transaction requests are not supported".to_string()).into());
+        }
+        let mut request = TransferRequest::new(
+            url.path()
+                .parse()
+                .map_err(|_| invalid(format!("invalid recipient `{}`", url.path())))?,
+        );
+
+        for (key, value) in url.query_pairs() {
+            let once = |given: bool| {
+                if given {
+                    Err(invalid(format!("`{key}` is given more than once")))
+                } else {
+                    Ok(())
+                }
+            };
+            let pubkey = || {
+                value
+                    .parse::<Pubkey>()
+                    .map_err(|_| invalid(format!("invalid {key} `{value}`")))
+            };
+            match key.as_ref() {
+                "amount" => {
+                    once(request.amount.is_some())?;
+                    request.amount = Some(value.parse()?);
+                }
+                "spl-token" => {
+                    once(request.spl_token.is_some())?;
+                    request.spl_token = Some(pubkey()?);
+                }
+                "reference" => request.references.push(pubkey()?),
+                "label" => {
+                    once(request.label.is_some())?;
+                    request.label = Some(value.into_owned());
+                }
+                "message" => {
+                    once(request.message.is_some())?;
+                    request.message = Some(value.into_owned());
+                }
+                "memo" => {
+                    once(request.memo.is_some())?;
+                    request.memo = Some(value.into_owned());
+                }
+                // Later versions of the spec may add fields.
+                _ => {}
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+impl fmt::Display for TransferRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        if let Some(amount) = self.amount {
+            query.append_pair("amount", &amount.to_string());
+        }
+        if let Some(mint) = self.spl_token {
+            query.append_pair("spl-token", &mint.to_string());
+        }
+        for reference in &self.references {
+            query.append_pair("reference", &reference.to_string());
+        }
+        for (key, value) in [
+            ("label", &self.label),
+            ("message", &self.message),
+            ("memo", &self.memo),
+        ] {
+            if let Some(value) = value {
+                query.append_pair(key, value);
+            }
+        }
+
+        write!(f, "{SCHEME}:{}", self.recipient)?;
+        match query.finish() {
+            query if query.is_empty() => Ok(()),
+            query => write!(f, "?{query}"),
+        }
+    }
+}
+
+/// Instructions paying `request` from `payer`: the memo, if any, then the
+/// transfer with the reference keys as extra read-only accounts.
+///
+/// `mint` is the token program and decimals of `spl_token`, as read by
+/// [`pay`]; it is ignored for SOL.
+pub fn pay_instructions(
+    payer: &Pubkey,
+    request: &TransferRequest,
+    mint: Option<(Pubkey, u8)>,
+) -> Result<Vec<Instruction>, crate::Error> {
+    let amount = request.amount.ok_or(PayError::NoAmount)?;
+
+    let mut instructions = Vec::new();
+    if let Some(memo) = &request.memo {
+        instructions.push(spl_memo::build_memo(memo.as_bytes(), &[payer]));
+    }
+
+    let mut transfer = match (request.spl_token, mint) {
+        (None, _) => transfer(payer, &request.recipient, amount.to_lamports()?),
+        (Some(mint), Some((token_program, decimals))) => {
+            let ata =
+                |owner| get_associated_token_address_with_program_id(owner, &mint, &token_program);
+            spl_token_2022::instruction::transfer_checked(
+                &token_program,
+                &ata(payer),
+                &mint,
+                &ata(&request.recipient),
+                payer,
+                &[],
+                amount.to_base_units(decimals)?,
+                decimals,
+            )?
+        }
+        (Some(mint), None) => return Err(AmountError::InvalidMint(mint).into()),
+    };
+    transfer.accounts.extend(
+        request
+            .references
+            .iter()
+            .map(|reference| AccountMeta::new_readonly(*reference, false)),
+    );
+    instructions.push(transfer);
+
+    Ok(instructions)
+}
+
+/// Pays `request` from `payer`. For a token payment the recipient must
+/// already have an associated token account for the mint.
+pub fn pay(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    request: &TransferRequest,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, crate::Error> {
+    let mint = match request.spl_token {
+        Some(mint) => {
+            let token_program = mint_token_program(client, &mint)?;
+            let ata = get_associated_token_address_with_program_id(
+                &request.recipient,
+                &mint,
+                &token_program,
+            );
+            if client
+                .get_account_with_commitment(&ata, client.commitment())?
+                .value
+                .is_none()
+            {
+                return Err(PayError::NoTokenAccount {
+                    owner: request.recipient,
+                    mint,
+                }
+                .into());
+            }
+            Some((token_program, mint_decimals(client, &mint)?))
+        }
+        None => None,
+    };
+    let instructions = pay_instructions(&payer.pubkey(), request, mint)?;
+
+    process_transaction(client, &instructions, &[payer], config)
+}
+
+/// A transaction that pays a [`TransferRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payment {
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub payer: Pubkey,
+    /// What the recipient received, in base units of the currency.
+    pub received: u64,
+    pub decimals: u8,
+}
+
+impl fmt::Display for Payment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} slot {}", self.signature, self.slot)?;
+        if let Some(time) = self.block_time {
+            write!(f, ", {}", format_time(time))?;
+        }
+        write!(
+            f,
+            "\n  received {} from {}",
+            format_amount(self.received, self.decimals),
+            self.payer
+        )
+    }
+}
+
+/// Checks that the transaction of `entry`, seen from the recipient of
+/// `request`, pays it: it succeeded, refers to every reference key, gave
+/// the recipient at least the amount of the right currency and carries
+/// the memo. `keys` are the accounts of the transaction, as by
+/// [`transaction_keys`], and `decimals` those of the currency.
+pub fn check_payment(
+    request: &TransferRequest,
+    decimals: u8,
+    entry: &HistoryEntry,
+    keys: &[Pubkey],
+) -> Result<Payment, PayError> {
+    let mismatch = |reason: String| PayError::Mismatch {
+        signature: entry.signature,
+        reason,
+    };
+    let amount = request
+        .amount
+        .ok_or(PayError::NoAmount)?
+        .to_base_units(decimals)
+        .map_err(|error| mismatch(error.to_string()))?;
+
+    if let Some(error) = &entry.error {
+        return Err(mismatch(format!("it failed: {error}")));
+    }
+    if let Some(reference) = request
+        .references
+        .iter()
+        .find(|reference| !keys.contains(reference))
+    {
+        return Err(mismatch(format!("it does not refer to {reference}")));
+    }
+
+    let received = match request.spl_token {
+        None => entry.sol_delta,
+        Some(mint) => entry
+            .token_deltas
+            .iter()
+            .filter(|delta| delta.mint == mint)
+            .map(|delta| delta.delta)
+            .sum(),
+    };
+    if received < i128::from(amount) {
+        return Err(mismatch(format!(
+            "{} received {} {}, not {} {}",
+            request.recipient,
+            format_delta(received, decimals).trim_start_matches('+'),
+            request.currency(),
+            format_amount(amount, decimals),
+            request.currency()
+        )));
+    }
+
+    if let Some(memo) = &request.memo {
+        if !entry.memos.contains(memo) {
+            return Err(mismatch(format!("it has no memo {memo:?}")));
+        }
+    }
+
+    Ok(Payment {
+        signature: entry.signature,
+        slot: entry.slot,
+        block_time: entry.block_time,
+        payer: entry.fee_payer,
+        received: u64::try_from(received).unwrap_or(u64::MAX),
+        decimals,
+    })
+}
+
+/// Finds the transaction paying `request` by its first reference key and
+/// checks it with [`check_payment`].
+///
+/// Anyone can send a transaction that refers to the key, so every one of
+/// them, oldest first, is checked until one pays. If none does, the error
+/// is that of a transaction that could not be fetched, since it may be the
+/// payment, and otherwise the mismatch of the last one.
+pub fn find_payment(
+    client: &RpcClient,
+    request: &TransferRequest,
+) -> Result<Payment, crate::Error> {
+    let reference = request.references.first().ok_or(PayError::NoReference)?;
+    let decimals = match &request.spl_token {
+        Some(mint) => mint_decimals(client, mint)?,
+        None => SOL_DECIMALS,
+    };
+    // Neither method serves `processed` transactions.
+    let commitment = if client.commitment() == CommitmentConfig::processed() {
+        CommitmentConfig::confirmed()
+    } else {
+        client.commitment()
+    };
+
+    let mut signatures = Vec::new();
+    let mut before = None;
+    loop {
+        let page = client.get_signatures_for_address_with_config(
+            reference,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                limit: Some(MAX_SIGNATURES_PER_PAGE),
+                commitment: Some(commitment),
+                ..GetConfirmedSignaturesForAddress2Config::default()
+            },
+        )?;
+        let last_page = page.len() < MAX_SIGNATURES_PER_PAGE;
+
+        for status in page {
+            let signature: Signature = status.signature.parse().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "invalid signature from RPC")
+            })?;
+            before = Some(signature);
+            signatures.push(signature);
+        }
+        if last_page {
+            break;
+        }
+    }
+
+    let mut mismatch = PayError::NotFound(*reference);
+    let mut unavailable = None;
+    for signature in signatures.into_iter().rev() {
+        let confirmed = match client.get_transaction_with_config(
+            &signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(commitment),
+                max_supported_transaction_version: Some(0),
+            },
+        ) {
+            Ok(confirmed) => confirmed,
+            Err(error) => {
+                unavailable = Some(PayError::Unavailable {
+                    signature,
+                    reason: error.to_string(),
+                });
+                continue;
+            }
+        };
+        let (Some(transaction), Some(meta)) = (
+            confirmed.transaction.transaction.decode(),
+            confirmed.transaction.meta,
+        ) else {
+            unavailable = Some(PayError::Unavailable {
+                signature,
+                reason: "the RPC node returned it without its status".to_string(),
+            });
+            continue;
+        };
+
+        let entry = HistoryEntry::new(
+            &request.recipient,
+            signature,
+            confirmed.slot,
+            confirmed.block_time,
+            &transaction,
+            &meta,
+        );
+        match check_payment(
+            request,
+            decimals,
+            &entry,
+            &transaction_keys(&transaction, &meta),
+        ) {
+            Ok(payment) => return Ok(payment),
+            Err(error) => mismatch = error,
+        }
+    }
+
+    Err(unavailable.unwrap_or(mismatch).into())
+}
@@ -0,0 +1,282 @@
+use std::ops::Range;
+
+use solana_client::rpc_client::RpcClient;
+use solana_program::instruction::Instruction;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_sdk::address_lookup_table::state::LOOKUP_TABLE_MAX_ADDRESSES;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::signer::Signer;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use spl_token::instruction::{initialize_mint, mint_to};
+use spl_token::state::Mint;
+
+use crate::lookup_table::{
+    create_lookup_table, extend_lookup_table, fetch_lookup_tables, wait_for_lookup_table,
+    LookupTableError,
+};
+use crate::transaction::{fits, process_transaction, process_transaction_with_lookup_tables};
+use crate::{Error, TransactionConfig, TransactionOutcome};
+
+/// Instructions of [`create_token_mint`]; `mint_rent` is the rent exempt
+/// minimum for [`Mint::LEN`] bytes.
+pub fn create_token_mint_instructions(
+    payer: &Pubkey,
+    mint: &Pubkey,
+    decimals: u8,
+    mint_rent: u64,
+) -> Result<Vec<Instruction>, Error> {
+    let token_program_id = spl_token::id();
+
+    // Create the mint account
+    let create_account_ix = system_instruction::create_account(
+        payer,
+        mint,
+        mint_rent,
+        Mint::LEN as u64,
+        &token_program_id,
+    );
+
+    let initialize_mint_ix =
+        initialize_mint(&token_program_id, mint, payer, Some(payer), decimals)?;
+
+    Ok(vec![create_account_ix, initialize_mint_ix])
+}
+
+/// Creates the mint account `mint` with `decimals`, with `payer` as mint
+/// and freeze authority.
+pub fn create_token_mint(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    mint: &dyn Signer,
+    decimals: u8,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let mint_rent = client.get_minimum_balance_for_rent_exemption(Mint::LEN)?;
+    let instructions =
+        create_token_mint_instructions(&payer.pubkey(), &mint.pubkey(), decimals, mint_rent)?;
+
+    process_transaction(client, &instructions, &[payer, mint], config)
+}
+
+/// Instruction of [`mint_tokens`].
+pub fn mint_tokens_instruction(
+    mint: &Pubkey,
+    authority: &Pubkey,
+    ata: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, Error> {
+    Ok(mint_to(&spl_token::id(), mint, ata, authority, &[authority], amount)?)
+}
+
+/// Mints `amount` base units of `mint` to the token account `ata`, with
+/// `payer` as mint authority.
+pub fn mint_tokens(
+    client: &RpcClient,
+    mint: &Pubkey,
+    payer: &dyn Signer,
+    ata: &Pubkey,
+    amount: u64,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let mint_to_ix = mint_tokens_instruction(mint, &payer.pubkey(), ata, amount)?;
+
+    process_transaction(client, &[mint_to_ix], &[payer], config)
+}
+
+/// Instruction of [`create_associated_token_account`].
+pub fn create_associated_token_account_instruction(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    create_associated_token_account_idempotent(payer, owner, mint, &spl_token::id())
+}
+
+/// Creates the associated token account of `owner` for `mint`, paid by
+/// `payer`. Does nothing if it already exists.
+pub fn create_associated_token_account(
+    client: &RpcClient,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    payer: &dyn Signer,
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let ix = create_associated_token_account_instruction(mint, owner, &payer.pubkey());
+
+    process_transaction(client, &[ix], &[payer], config)
+}
+
+/// What [`mint_tokens_batch`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchOutcome {
+    /// Lookup table created because the batch did not fit into one legacy
+    /// transaction. It stays active; deactivate and close it to get the
+    /// rent back.
+    pub lookup_table: Option<Pubkey>,
+    pub outcomes: Vec<TransactionOutcome>,
+}
+
+/// A [`mint_tokens_batch`] that failed after it created its lookup table.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "batch failed after {} transactions, lookup table {lookup_table} left to close: {source}",
+    sent.len()
+)]
+pub struct BatchError {
+    /// The table created for the batch, still active.
+    pub lookup_table: Pubkey,
+    /// Transactions of the batch that went through before the failure.
+    pub sent: Vec<TransactionOutcome>,
+    pub source: Box<Error>,
+}
+
+/// Instructions of [`mint_tokens_batch`], one group per recipient: create
+/// the associated token account of the owner if needed and mint to it.
+pub fn mint_tokens_batch_instructions(
+    mint: &Pubkey,
+    payer: &Pubkey,
+    recipients: &[(Pubkey, u64)],
+) -> Result<Vec<Vec<Instruction>>, Error> {
+    recipients
+        .iter()
+        .map(|(owner, amount)| {
+            let ata = get_associated_token_address(owner, mint);
+
+            Ok(vec![
+                create_associated_token_account_instruction(mint, owner, payer),
+                mint_tokens_instruction(mint, payer, &ata, *amount)?,
+            ])
+        })
+        .collect()
+}
+
+/// Mints to the associated token accounts of many `recipients` (owner and
+/// amount in base units), creating the accounts as needed, with `payer` as
+/// mint authority.
+///
+/// Everything goes into one transaction if it fits. Otherwise a lookup
+/// table with the accounts of the batch is created, and the recipients are
+/// packed into as few v0 transactions using it as possible. If anything
+/// fails once the table exists, the error is a [`BatchError`] with the
+/// table and the transactions already sent.
+pub fn mint_tokens_batch(
+    client: &RpcClient,
+    mint: &Pubkey,
+    payer: &dyn Signer,
+    recipients: &[(Pubkey, u64)],
+    config: &TransactionConfig,
+) -> Result<BatchOutcome, Error> {
+    let groups = mint_tokens_batch_instructions(mint, &payer.pubkey(), recipients)?;
+    let instructions = groups.concat();
+    let mut lookup_tables = fetch_lookup_tables(client, &config.lookup_tables)?;
+
+    if fits(&instructions, payer, &lookup_tables, config)? {
+        let outcome = process_transaction_with_lookup_tables(
+            client,
+            &instructions,
+            &[payer],
+            &lookup_tables,
+            config,
+        )?;
+
+        return Ok(BatchOutcome {
+            lookup_table: None,
+            outcomes: vec![outcome],
+        });
+    }
+    if config.dry_run || config.estimate {
+        return Err(LookupTableError::DryRun.into());
+    }
+    if config.sign_only.is_some() {
+        return Err(LookupTableError::SignOnly.into());
+    }
+
+    let addresses = batch_addresses(&instructions, &payer.pubkey());
+    if addresses.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(LookupTableError::TooManyAddresses(addresses.len()).into());
+    }
+    // The table only exists to send the batch, so it never uses a nonce or
+    // an offline blockhash.
+    let table_config = TransactionConfig {
+        fees: config.fees.clone(),
+        send: config.send.clone(),
+        ..TransactionConfig::default()
+    };
+    let (table, _) = create_lookup_table(client, payer, &payer.pubkey(), &table_config)?;
+
+    let mut outcomes = Vec::new();
+    let send = || -> Result<(), Error> {
+        extend_lookup_table(client, payer, &table, payer, &addresses, &table_config)?;
+        wait_for_lookup_table(client, &table)?;
+        lookup_tables.push(AddressLookupTableAccount {
+            key: table,
+            addresses,
+        });
+
+        for chunk in pack(&groups, payer, &lookup_tables, config)? {
+            outcomes.push(process_transaction_with_lookup_tables(
+                client,
+                &groups[chunk].concat(),
+                &[payer],
+                &lookup_tables,
+                config,
+            )?);
+        }
+        Ok(())
+    };
+
+    match send() {
+        Ok(()) => Ok(BatchOutcome {
+            lookup_table: Some(table),
+            outcomes,
+        }),
+        Err(error) => Err(BatchError {
+            lookup_table: table,
+            sent: outcomes,
+            source: Box::new(error),
+        }
+        .into()),
+    }
+}
+
+/// The accounts of `instructions` other than the fee payer, which has to
+/// stay in the message as a signer.
+fn batch_addresses(instructions: &[Instruction], payer: &Pubkey) -> Vec<Pubkey> {
+    let mut addresses = Vec::new();
+    for meta in instructions.iter().flat_map(|ix| &ix.accounts) {
+        if meta.pubkey != *payer && !addresses.contains(&meta.pubkey) {
+            addresses.push(meta.pubkey);
+        }
+    }
+
+    addresses
+}
+
+/// Packs the instruction `groups` in order into transactions that fit,
+/// never splitting a group. Returns the range of groups of each
+/// transaction.
+pub(crate) fn pack(
+    groups: &[Vec<Instruction>],
+    payer: &dyn Signer,
+    lookup_tables: &[AddressLookupTableAccount],
+    config: &TransactionConfig,
+) -> Result<Vec<Range<usize>>, Error> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    for end in 1..=groups.len() {
+        let candidate = groups[start..end].concat();
+        if end - start > 1 && !fits(&candidate, payer, lookup_tables, config)? {
+            chunks.push(start..end - 1);
+            start = end - 1;
+        }
+    }
+    if start < groups.len() {
+        chunks.push(start..groups.len());
+    }
+
+    Ok(chunks)
+}
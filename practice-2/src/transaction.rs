@@ -0,0 +1,593 @@
+//! Building, signing and sending the transactions of the helpers.
+//!
+//! Every helper collects its instructions and hands them to
+//! [`process_transaction`] together with a [`TransactionConfig`], which
+//! decides where the blockhash comes from and whether the transaction is
+//! sent or only signed and written to a file (see [`crate::offline`]).
+//! Sending goes through [`crate::sender`], which retries until the
+//! transaction is confirmed. A dry run simulates the transaction instead
+//! (see [`crate::simulation`]), and an estimate only reports its cost (see
+//! [`crate::cost`]).
+//!
+//! Transactions use a legacy message unless [`MessageVersion::V0`] is
+//! asked for or lookup tables (see [`crate::lookup_table`]) are given.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use clap::{Args, ValueEnum};
+use solana_client::nonce_utils;
+use solana_client::rpc_client::RpcClient;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::message::{v0, Message, VersionedMessage};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::signer::unique_signers;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::cost::{self, CostEstimate};
+use crate::fees::{self, ComputeUnitLimit, FeeConfig, PriorityFee};
+use crate::lookup_table::{fetch_lookup_tables, LookupTableError};
+use crate::sender::{self, SendConfig};
+use crate::simulation::{self, Simulation};
+use crate::{offline, ConfigError, Error, SignerSource};
+
+/// Variable with the blockhash to sign with instead of fetching one.
+pub const BLOCKHASH_VAR: &str = "BLOCKHASH";
+
+/// Variable with a durable nonce account to use instead of a recent
+/// blockhash.
+pub const NONCE_ACCOUNT_VAR: &str = "NONCE_ACCOUNT";
+
+/// Variable with a [`SignerSource`] for the nonce authority. The fee payer
+/// is the authority if unset.
+pub const NONCE_AUTHORITY_VAR: &str = "NONCE_AUTHORITY";
+
+/// Variable with a file to write the signed transaction to instead of
+/// sending it.
+pub const SIGN_ONLY_VAR: &str = "SIGN_ONLY";
+
+/// Variable with the message version, `legacy` or `v0`.
+pub const MESSAGE_VERSION_VAR: &str = "MESSAGE_VERSION";
+
+/// Variable with comma separated lookup tables for v0 messages.
+pub const LOOKUP_TABLES_VAR: &str = "LOOKUP_TABLES";
+
+/// Message format of a transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum MessageVersion {
+    #[default]
+    Legacy,
+    /// Versioned message, which can load accounts from lookup tables.
+    V0,
+}
+
+/// A durable nonce used in place of a recent blockhash.
+pub struct NonceConfig {
+    pub account: Pubkey,
+    /// Signer allowed to advance the nonce, the fee payer if `None`.
+    pub authority: Option<Box<dyn Signer + Send + Sync>>,
+}
+
+/// How a transaction gets its blockhash and where it ends up.
+///
+/// The default fetches the latest blockhash and sends the transaction.
+#[derive(Default)]
+pub struct TransactionConfig {
+    /// Blockhash to sign with. With a nonce this has to be the stored
+    /// nonce value; it is fetched from the nonce account if `None`.
+    pub blockhash: Option<Hash>,
+    pub nonce: Option<NonceConfig>,
+    /// Write the transaction here instead of sending it.
+    pub sign_only: Option<PathBuf>,
+    /// Message format; lookup tables imply [`MessageVersion::V0`].
+    pub message_version: MessageVersion,
+    /// Lookup tables to compile v0 messages with.
+    pub lookup_tables: Vec<Pubkey>,
+    /// Priority fee and compute unit limit.
+    pub fees: FeeConfig,
+    /// Retry limits for sending.
+    pub send: SendConfig,
+    /// Simulate the transaction instead of sending or writing it.
+    pub dry_run: bool,
+    /// Only estimate the cost of the transaction; takes precedence over
+    /// `dry_run`.
+    pub estimate: bool,
+}
+
+/// Command line flags for [`TransactionConfig`], to `#[command(flatten)]`
+/// into a clap parser. Flags take precedence over the variables.
+#[derive(Debug, Clone, Default, Args)]
+pub struct TransactionArgs {
+    /// Blockhash to sign with instead of fetching one
+    #[arg(long, global = true)]
+    pub blockhash: Option<Hash>,
+    /// Durable nonce account to use instead of a recent blockhash
+    #[arg(long, global = true)]
+    pub nonce: Option<Pubkey>,
+    /// Nonce authority, the fee payer if not given
+    #[arg(long, global = true)]
+    pub nonce_authority: Option<SignerSource>,
+    /// Write the signed transaction to FILE instead of sending it
+    #[arg(long, global = true, value_name = "FILE")]
+    pub sign_only: Option<PathBuf>,
+    /// Message format of the transactions
+    #[arg(long, global = true, value_enum)]
+    pub message_version: Option<MessageVersion>,
+    /// Lookup table to compile a v0 message with; can be repeated
+    #[arg(long = "lookup-table", global = true, value_name = "ADDRESS")]
+    pub lookup_tables: Vec<Pubkey>,
+    /// Compute unit price: none, micro-lamports per compute unit, or a
+    /// percentile of recent fees for the written accounts like p75
+    #[arg(long, global = true, value_name = "FEE")]
+    pub priority_fee: Option<PriorityFee>,
+    /// Compute unit limit: default, a number of compute units, or auto to
+    /// simulate the transaction first
+    #[arg(long, global = true, value_name = "LIMIT")]
+    pub compute_unit_limit: Option<ComputeUnitLimit>,
+    /// Percent added to the simulated compute units with
+    /// `--compute-unit-limit auto`, 10 if not given
+    #[arg(long, global = true, value_name = "PERCENT")]
+    pub compute_unit_margin: Option<u32>,
+    /// Simulate the transactions and show logs, compute units and balance
+    /// changes instead of sending them
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+    /// Show the fees and rent deposits of the transactions instead of
+    /// sending them
+    #[arg(long, global = true)]
+    pub estimate: bool,
+}
+
+impl TransactionConfig {
+    /// Reads the configuration from the `BLOCKHASH`, `NONCE_ACCOUNT`,
+    /// `NONCE_AUTHORITY`, `SIGN_ONLY`, `MESSAGE_VERSION`, `LOOKUP_TABLES`,
+    /// `PRIORITY_FEE`, `COMPUTE_UNIT_LIMIT`, `DRY_RUN` and `ESTIMATE`
+    /// variables.
+    pub fn from_env() -> Result<Self, Error> {
+        Self::resolve(&TransactionArgs::default())
+    }
+
+    /// Resolves the configuration from `args`, falling back to the
+    /// variables read by [`TransactionConfig::from_env`].
+    pub fn resolve(args: &TransactionArgs) -> Result<Self, Error> {
+        dotenv::dotenv().ok();
+
+        let blockhash = match args.blockhash {
+            Some(blockhash) => Some(blockhash),
+            None => std::env::var(BLOCKHASH_VAR)
+                .ok()
+                .map(|blockhash| Hash::from_str(&blockhash))
+                .transpose()?,
+        };
+
+        let account = match args.nonce {
+            Some(account) => Some(account),
+            None => std::env::var(NONCE_ACCOUNT_VAR)
+                .ok()
+                .map(|account| Pubkey::from_str(&account))
+                .transpose()?,
+        };
+        let nonce = match account {
+            Some(account) => {
+                let authority = match &args.nonce_authority {
+                    Some(source) => Some(source.clone()),
+                    None => std::env::var(NONCE_AUTHORITY_VAR)
+                        .ok()
+                        .map(|source| source.parse::<SignerSource>())
+                        .transpose()?,
+                };
+
+                Some(NonceConfig {
+                    account,
+                    authority: authority.map(|source| source.signer()).transpose()?,
+                })
+            }
+            None => None,
+        };
+
+        let message_version = match args.message_version {
+            Some(version) => version,
+            None => match std::env::var(MESSAGE_VERSION_VAR) {
+                Ok(version) => MessageVersion::from_str(&version, true)
+                    .map_err(|_| ConfigError::MessageVersion(version))?,
+                Err(_) => MessageVersion::default(),
+            },
+        };
+        let lookup_tables = if args.lookup_tables.is_empty() {
+            std::env::var(LOOKUP_TABLES_VAR)
+                .ok()
+                .map(|tables| {
+                    tables
+                        .split(',')
+                        .map(|table| Pubkey::from_str(table.trim()))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .transpose()?
+                .unwrap_or_default()
+        } else {
+            args.lookup_tables.clone()
+        };
+
+        let fees = FeeConfig {
+            priority_fee: match args.priority_fee {
+                Some(fee) => fee,
+                None => std::env::var(fees::PRIORITY_FEE_VAR)
+                    .ok()
+                    .map(|fee| fee.parse())
+                    .transpose()?
+                    .unwrap_or_default(),
+            },
+            compute_unit_limit: match args.compute_unit_limit {
+                Some(limit) => limit,
+                None => std::env::var(fees::COMPUTE_UNIT_LIMIT_VAR)
+                    .ok()
+                    .map(|limit| limit.parse())
+                    .transpose()?
+                    .unwrap_or_default(),
+            },
+            compute_unit_margin: args
+                .compute_unit_margin
+                .unwrap_or(fees::DEFAULT_COMPUTE_UNIT_MARGIN),
+        };
+
+        Ok(TransactionConfig {
+            blockhash,
+            nonce,
+            sign_only: args
+                .sign_only
+                .clone()
+                .or_else(|| std::env::var_os(SIGN_ONLY_VAR).map(PathBuf::from)),
+            message_version,
+            lookup_tables,
+            fees,
+            send: SendConfig::default(),
+            dry_run: args.dry_run || flag_var(simulation::DRY_RUN_VAR),
+            estimate: args.estimate || flag_var(cost::ESTIMATE_VAR),
+        })
+    }
+}
+
+/// Whether the variable `name` is set to anything but `0` or `false`.
+fn flag_var(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|value| !matches!(value.as_str(), "" | "0" | "false"))
+}
+
+/// What happened to a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionOutcome {
+    /// Sent and confirmed.
+    Confirmed(Signature),
+    /// Written to `path`; `missing` signers still have to sign it with
+    /// `offline-tx sign` before it can be broadcast.
+    SignedOnly { path: PathBuf, missing: Vec<Pubkey> },
+    /// Simulated in a dry run, nothing was sent.
+    Simulated(Box<Simulation>),
+    /// Only the cost was estimated, nothing was sent.
+    Estimated(Box<CostEstimate>),
+}
+
+impl fmt::Display for TransactionOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionOutcome::Confirmed(signature) => {
+                write!(f, "transaction confirmed, signature: {signature}")
+            }
+            TransactionOutcome::SignedOnly { path, missing } if missing.is_empty() => {
+                write!(f, "fully signed transaction written to {}", path.display())
+            }
+            TransactionOutcome::SignedOnly { path, missing } => {
+                write!(f, "transaction written to {}, still to sign:", path.display())?;
+                for pubkey in missing {
+                    write!(f, " {pubkey}")?;
+                }
+                Ok(())
+            }
+            TransactionOutcome::Simulated(simulation) => write!(f, "{simulation}"),
+            TransactionOutcome::Estimated(estimate) => write!(f, "{estimate}"),
+        }
+    }
+}
+
+/// Builds a transaction from `instructions`, signs it with `signers` and
+/// sends it, writes it out, simulates it or estimates its cost, as
+/// `config` says.
+///
+/// The first signer pays the fees. With a durable nonce, the
+/// `advance_nonce_account` instruction is put first, as the runtime
+/// requires. Signers that are only placeholders (a bare pubkey
+/// [`SignerSource`]) leave their signature empty, which is only allowed in
+/// sign-only mode.
+pub fn process_transaction(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    signers: &[&dyn Signer],
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    let lookup_tables = fetch_lookup_tables(client, &config.lookup_tables)?;
+
+    process_transaction_with_lookup_tables(client, instructions, signers, &lookup_tables, config)
+}
+
+/// [`process_transaction`] with a v0 message compiled with
+/// `lookup_tables`, in addition to the ones in `config`.
+pub fn process_transaction_with_lookup_tables(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    signers: &[&dyn Signer],
+    lookup_tables: &[AddressLookupTableAccount],
+    config: &TransactionConfig,
+) -> Result<TransactionOutcome, Error> {
+    process_transaction_observed(client, instructions, signers, lookup_tables, config, &|_| Ok(()))
+}
+
+/// [`process_transaction_with_lookup_tables`] calling `on_signed` with
+/// every signed version of the transaction before it is first broadcast,
+/// so callers can record signatures that may land.
+pub(crate) fn process_transaction_observed(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    signers: &[&dyn Signer],
+    lookup_tables: &[AddressLookupTableAccount],
+    config: &TransactionConfig,
+    on_signed: &dyn Fn(&VersionedTransaction) -> Result<(), Error>,
+) -> Result<TransactionOutcome, Error> {
+    let mut prepared = PreparedTransaction::new(instructions, signers, lookup_tables, config);
+
+    // Only a blockhash fetched here may be replaced once it expires.
+    let (blockhash, last_valid_block_height) = match (config.blockhash, &config.nonce) {
+        (Some(blockhash), _) => (blockhash, None),
+        (None, Some(nonce)) => (nonce_blockhash(client, &nonce.account)?, None),
+        (None, None) => {
+            let (blockhash, last_valid) =
+                client.get_latest_blockhash_with_commitment(client.commitment())?;
+            (blockhash, Some(last_valid))
+        }
+    };
+    apply_fees(client, &mut prepared, blockhash, &config.fees)?;
+
+    if config.estimate {
+        let estimate = cost::estimate_cost(client, &prepared.unsigned(blockhash)?, lookup_tables)?;
+        return Ok(TransactionOutcome::Estimated(Box::new(estimate)));
+    }
+    if config.dry_run {
+        let simulation =
+            simulation::simulate(client, &prepared.unsigned(blockhash)?, lookup_tables)?;
+        return Ok(TransactionOutcome::Simulated(Box::new(simulation)));
+    }
+    let transaction = prepared.sign(blockhash)?;
+
+    if let Some(outcome) = write_if_sign_only(&transaction, config)? {
+        return Ok(outcome);
+    }
+    on_signed(&transaction)?;
+    let signature = sender::send_and_confirm(
+        client,
+        |blockhash| {
+            let transaction = prepared.sign(blockhash)?;
+            on_signed(&transaction)?;
+            Ok(transaction)
+        },
+        transaction,
+        last_valid_block_height,
+        &config.send,
+    )?;
+
+    Ok(TransactionOutcome::Confirmed(signature))
+}
+
+/// Instructions and signers of a transaction with the nonce mode, the
+/// compute budget and the message version of a [`TransactionConfig`]
+/// applied, shared by the blocking and the [`crate::nonblocking`]
+/// pipeline.
+pub(crate) struct PreparedTransaction<'a> {
+    /// `advance_nonce_account`, which has to be the first instruction.
+    advance_nonce: Option<Instruction>,
+    compute_budget: Vec<Instruction>,
+    instructions: Vec<Instruction>,
+    payer: Pubkey,
+    signers: Vec<&'a dyn Signer>,
+    /// `Some` for a v0 message.
+    lookup_tables: Option<Vec<AddressLookupTableAccount>>,
+}
+
+impl<'a> PreparedTransaction<'a> {
+    pub(crate) fn new(
+        instructions: &[Instruction],
+        signers: &[&'a dyn Signer],
+        lookup_tables: &[AddressLookupTableAccount],
+        config: &'a TransactionConfig,
+    ) -> Self {
+        let payer = signers[0].pubkey();
+        let mut signers = signers.to_vec();
+
+        let advance_nonce = config.nonce.as_ref().map(|nonce| {
+            let authority = match &nonce.authority {
+                Some(authority) => {
+                    signers.push(authority.as_ref());
+                    authority.pubkey()
+                }
+                None => payer,
+            };
+            system_instruction::advance_nonce_account(&nonce.account, &authority)
+        });
+
+        let lookup_tables = (config.message_version == MessageVersion::V0
+            || !lookup_tables.is_empty())
+        .then(|| lookup_tables.to_vec());
+
+        PreparedTransaction {
+            advance_nonce,
+            compute_budget: Vec::new(),
+            instructions: instructions.to_vec(),
+            payer,
+            signers,
+            lookup_tables,
+        }
+    }
+
+    /// Sets the compute unit `limit` and `price` of the transaction.
+    pub(crate) fn set_compute_budget(&mut self, limit: Option<u32>, price: Option<u64>) {
+        self.compute_budget = fees::compute_budget_instructions(limit, price);
+    }
+
+    pub(crate) fn writable_accounts(&self) -> Vec<Pubkey> {
+        fees::writable_accounts(&self.all_instructions())
+    }
+
+    fn all_instructions(&self) -> Vec<Instruction> {
+        self.advance_nonce
+            .iter()
+            .chain(&self.compute_budget)
+            .chain(&self.instructions)
+            .cloned()
+            .collect()
+    }
+
+    fn message(&self, blockhash: Hash) -> Result<VersionedMessage, Error> {
+        let instructions = self.all_instructions();
+
+        Ok(match &self.lookup_tables {
+            None => VersionedMessage::Legacy(Message::new_with_blockhash(
+                &instructions,
+                Some(&self.payer),
+                &blockhash,
+            )),
+            Some(lookup_tables) => VersionedMessage::V0(
+                v0::Message::try_compile(&self.payer, &instructions, lookup_tables, blockhash)
+                    .map_err(LookupTableError::from)?,
+            ),
+        })
+    }
+
+    /// The transaction without signatures, for simulation.
+    pub(crate) fn unsigned(&self, blockhash: Hash) -> Result<VersionedTransaction, Error> {
+        let message = self.message(blockhash)?;
+
+        Ok(VersionedTransaction {
+            signatures: vec![
+                Signature::default();
+                message.header().num_required_signatures as usize
+            ],
+            message,
+        })
+    }
+
+    /// Serialized size of the transaction.
+    fn size(&self) -> Result<usize, Error> {
+        Ok(bincode::serialized_size(&self.unsigned(Hash::default())?)
+            .map_err(offline::OfflineError::from)? as usize)
+    }
+
+    pub(crate) fn sign(&self, blockhash: Hash) -> Result<VersionedTransaction, Error> {
+        let mut transaction = self.unsigned(blockhash)?;
+        offline::add_signatures(&mut transaction, &unique_signers(self.signers.clone()))?;
+
+        Ok(transaction)
+    }
+}
+
+/// Whether a transaction of `instructions` paid by `payer` fits into a
+/// packet, counting the compute budget instructions `config` adds.
+pub(crate) fn fits(
+    instructions: &[Instruction],
+    payer: &dyn Signer,
+    lookup_tables: &[AddressLookupTableAccount],
+    config: &TransactionConfig,
+) -> Result<bool, Error> {
+    let mut prepared = PreparedTransaction::new(instructions, &[payer], lookup_tables, config);
+    prepared.set_compute_budget(
+        (config.fees.compute_unit_limit != ComputeUnitLimit::Default).then_some(0),
+        (config.fees.priority_fee != PriorityFee::None).then_some(0),
+    );
+
+    // A legacy message cannot even be compiled with more than 256
+    // accounts, which are far too many for a packet anyway.
+    if prepared.lookup_tables.is_none() {
+        let all_instructions = prepared.all_instructions();
+        let mut accounts: Vec<&Pubkey> = all_instructions
+            .iter()
+            .flat_map(|ix| ix.accounts.iter().map(|meta| &meta.pubkey).chain([&ix.program_id]))
+            .collect();
+        accounts.sort_unstable();
+        accounts.dedup();
+        if accounts.len() * std::mem::size_of::<Pubkey>() > PACKET_DATA_SIZE {
+            return Ok(false);
+        }
+    }
+
+    match prepared.size() {
+        Ok(size) => Ok(size <= PACKET_DATA_SIZE),
+        Err(Error::LookupTable(LookupTableError::Compile(_))) => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+/// Sets the compute budget of `prepared` as `fees` says, asking the
+/// cluster for recent prioritization fees and simulating the transaction
+/// when needed.
+fn apply_fees(
+    client: &RpcClient,
+    prepared: &mut PreparedTransaction,
+    blockhash: Hash,
+    fees: &FeeConfig,
+) -> Result<(), Error> {
+    let price = match fees.priority_fee {
+        PriorityFee::None => None,
+        PriorityFee::Fixed(price) => Some(price),
+        PriorityFee::Percentile(percentile) => {
+            let recent = client.get_recent_prioritization_fees(&prepared.writable_accounts())?;
+            Some(fees::recent_fee_percentile(&recent, percentile))
+        }
+    };
+
+    let limit = match fees.compute_unit_limit {
+        ComputeUnitLimit::Default => None,
+        ComputeUnitLimit::Fixed(limit) => Some(limit),
+        ComputeUnitLimit::Simulate => {
+            prepared.set_compute_budget(Some(fees::MAX_COMPUTE_UNIT_LIMIT), price);
+            let result = client.simulate_transaction_with_config(
+                &prepared.unsigned(blockhash)?,
+                fees::simulation_config(client.commitment()),
+            )?;
+            Some(fees::simulated_limit(result.value, fees.compute_unit_margin)?)
+        }
+    };
+
+    prepared.set_compute_budget(limit, price);
+
+    Ok(())
+}
+
+/// In sign-only mode writes the transaction out and returns the outcome;
+/// otherwise checks it is ready to be sent.
+pub(crate) fn write_if_sign_only(
+    transaction: &VersionedTransaction,
+    config: &TransactionConfig,
+) -> Result<Option<TransactionOutcome>, Error> {
+    if let Some(path) = &config.sign_only {
+        offline::write_transaction(transaction, path)?;
+
+        return Ok(Some(TransactionOutcome::SignedOnly {
+            path: path.clone(),
+            missing: offline::missing_signers(transaction),
+        }));
+    }
+
+    offline::ensure_fully_signed(transaction)?;
+
+    Ok(None)
+}
+
+/// Returns the blockhash stored in the nonce account.
+pub fn nonce_blockhash(client: &RpcClient, account: &Pubkey) -> Result<Hash, Error> {
+    let account =
+        nonce_utils::get_account_with_commitment(client, account, client.commitment())?;
+
+    Ok(nonce_utils::data_from_account(&account)?.blockhash())
+}
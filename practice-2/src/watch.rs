@@ -0,0 +1,398 @@
+//! Streaming balances of accounts as they change.
+//!
+//! [`watch`] subscribes to every address with `accountSubscribe` and
+//! reports its SOL balance, or its token balance if it is a token account
+//! of SPL Token or Token-2022. A dropped websocket is reconnected with
+//! exponential backoff; while an address has no live subscription its
+//! balance is polled over RPC instead, so no change goes unnoticed for
+//! longer than [`WatchConfig::poll_interval`].
+//!
+//! Balances can be compared with [`Threshold`]s: crossing one is reported
+//! as a [`Crossing`], and [`hook_command`] turns it into a shell command
+//! to run.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::ControlFlow;
+use std::process::Command;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::pubsub_client::PubsubClient;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use crate::amount::{format_amount, format_sol, mint_decimals, SOL_DECIMALS};
+use crate::sender::backoff;
+use crate::simulation::token_account;
+use crate::{Amount, AmountError, ClusterConfig};
+
+/// How [`watch`] connects and how often it polls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchConfig {
+    pub websocket_url: String,
+    pub commitment: CommitmentConfig,
+    /// How often addresses without a live subscription are polled.
+    pub poll_interval: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub thresholds: Vec<Threshold>,
+}
+
+impl WatchConfig {
+    /// Watches `cluster` with its websocket URL and commitment.
+    pub fn new(cluster: &ClusterConfig) -> Self {
+        WatchConfig {
+            websocket_url: cluster.websocket_url.clone(),
+            commitment: cluster.commitment,
+            poll_interval: Duration::from_secs(5),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            thresholds: Vec::new(),
+        }
+    }
+
+    /// Wait before reconnect number `attempt`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        backoff(self.initial_backoff, self.max_backoff, attempt)
+    }
+}
+
+/// What an address holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Balance {
+    Sol(u64),
+    Token {
+        mint: Pubkey,
+        amount: u64,
+        decimals: u8,
+    },
+    /// The account does not exist (any more), e.g. a vault after its
+    /// offer was taken.
+    Closed,
+}
+
+impl Balance {
+    /// The balance in base units: lamports or base units of the mint.
+    pub fn base_units(&self) -> u64 {
+        match self {
+            Balance::Sol(lamports) => *lamports,
+            Balance::Token { amount, .. } => *amount,
+            Balance::Closed => 0,
+        }
+    }
+
+    /// Decimals of the balance, `None` for a closed account.
+    pub fn decimals(&self) -> Option<u8> {
+        match self {
+            Balance::Sol(_) => Some(SOL_DECIMALS),
+            Balance::Token { decimals, .. } => Some(*decimals),
+            Balance::Closed => None,
+        }
+    }
+}
+
+impl fmt::Display for Balance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Balance::Sol(lamports) => write!(f, "{} SOL", format_sol(*lamports)),
+            Balance::Token {
+                mint,
+                amount,
+                decimals,
+            } => write!(f, "{} of {mint}", format_amount(*amount, *decimals)),
+            Balance::Closed => write!(f, "closed"),
+        }
+    }
+}
+
+/// The balance of `account`, with `decimals` of the mint if it is a token
+/// account. An account without lamports does not exist.
+pub fn account_balance(account: Option<&Account>, decimals: impl FnOnce(&Pubkey) -> u8) -> Balance {
+    let Some(account) = account.filter(|account| account.lamports > 0) else {
+        return Balance::Closed;
+    };
+
+    match token_account(account) {
+        Some((mint, _, amount)) => Balance::Token {
+            mint,
+            amount,
+            decimals: decimals(&mint),
+        },
+        None => Balance::Sol(account.lamports),
+    }
+}
+
+/// A balance to be alerted about, in SOL or tokens of the watched account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threshold {
+    Above(Amount),
+    Below(Amount),
+}
+
+impl Threshold {
+    /// Whether a balance going from `previous` to `current` base units of
+    /// `decimals` crossed the threshold.
+    pub fn crossed(self, previous: u64, current: u64, decimals: u8) -> Result<bool, AmountError> {
+        Ok(match self {
+            Threshold::Above(limit) => {
+                let limit = limit.to_base_units(decimals)?;
+                previous <= limit && current > limit
+            }
+            Threshold::Below(limit) => {
+                let limit = limit.to_base_units(decimals)?;
+                previous >= limit && current < limit
+            }
+        })
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Threshold::Above(limit) => write!(f, "above {limit}"),
+            Threshold::Below(limit) => write!(f, "below {limit}"),
+        }
+    }
+}
+
+/// A balance that crossed a threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crossing {
+    pub address: Pubkey,
+    pub threshold: Threshold,
+    pub previous: Balance,
+    pub balance: Balance,
+}
+
+/// Where a balance came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Websocket,
+    Poll,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// The first balance of an address, or a change of it.
+    Balance {
+        address: Pubkey,
+        balance: Balance,
+        source: Source,
+    },
+    Crossed(Crossing),
+    Subscribed(Pubkey),
+    /// The subscription of an address dropped; it is polled until it
+    /// is back.
+    Unsubscribed {
+        address: Pubkey,
+        error: String,
+    },
+}
+
+/// The shell command `command` run with the crossing in its environment:
+/// `WATCH_ADDRESS`, `WATCH_THRESHOLD` (e.g. `below 1.5`), `WATCH_PREVIOUS`
+/// and `WATCH_BALANCE` (in SOL or tokens, like the threshold).
+pub fn hook_command(command: &str, crossing: &Crossing) -> Command {
+    let decimals = crossing
+        .balance
+        .decimals()
+        .or(crossing.previous.decimals())
+        .unwrap_or(SOL_DECIMALS);
+    let mut hook = Command::new("sh");
+    hook.arg("-c")
+        .arg(command)
+        .env("WATCH_ADDRESS", crossing.address.to_string())
+        .env("WATCH_THRESHOLD", crossing.threshold.to_string())
+        .env(
+            "WATCH_PREVIOUS",
+            format_amount(crossing.previous.base_units(), decimals),
+        )
+        .env(
+            "WATCH_BALANCE",
+            format_amount(crossing.balance.base_units(), decimals),
+        );
+
+    hook
+}
+
+enum Message {
+    Subscribed(Pubkey),
+    Account(Pubkey, Option<Account>),
+    Unsubscribed(Pubkey, String),
+}
+
+/// Watches the balances of `addresses`, passing every change to
+/// `on_event` until it breaks.
+pub fn watch(
+    client: &RpcClient,
+    addresses: &[Pubkey],
+    config: &WatchConfig,
+    mut on_event: impl FnMut(WatchEvent) -> Result<ControlFlow<()>, crate::Error>,
+) -> Result<(), crate::Error> {
+    let (sender, receiver) = mpsc::channel();
+    for address in addresses {
+        let (sender, address, config) = (sender.clone(), *address, config.clone());
+        thread::spawn(move || subscribe(address, &config, sender));
+    }
+
+    let mut state = WatchState {
+        client,
+        config,
+        balances: HashMap::new(),
+        decimals: HashMap::new(),
+    };
+    let mut subscribed = HashSet::new();
+    let mut next_poll = Instant::now();
+
+    loop {
+        let events =
+            match receiver.recv_timeout(next_poll.saturating_duration_since(Instant::now())) {
+                Ok(Message::Subscribed(address)) => {
+                    subscribed.insert(address);
+                    vec![WatchEvent::Subscribed(address)]
+                }
+                Ok(Message::Unsubscribed(address, error)) => {
+                    subscribed.remove(&address);
+                    vec![WatchEvent::Unsubscribed { address, error }]
+                }
+                Ok(Message::Account(address, account)) => {
+                    state.update(address, account.as_ref(), Source::Websocket)?
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    next_poll = Instant::now() + config.poll_interval;
+                    let polled: Vec<_> = addresses
+                        .iter()
+                        .filter(|address| !subscribed.contains(*address))
+                        .copied()
+                        .collect();
+                    state.poll(&polled)?
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            };
+
+        for event in events {
+            if on_event(event)?.is_break() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Last known balances and the decimals of their mints.
+struct WatchState<'a> {
+    client: &'a RpcClient,
+    config: &'a WatchConfig,
+    balances: HashMap<Pubkey, Balance>,
+    decimals: HashMap<Pubkey, u8>,
+}
+
+impl WatchState<'_> {
+    fn poll(&mut self, addresses: &[Pubkey]) -> Result<Vec<WatchEvent>, crate::Error> {
+        if addresses.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let accounts = self
+            .client
+            .get_multiple_accounts_with_commitment(addresses, self.config.commitment)?
+            .value;
+
+        let mut events = Vec::new();
+        for (address, account) in addresses.iter().zip(accounts) {
+            events.extend(self.update(*address, account.as_ref(), Source::Poll)?);
+        }
+
+        Ok(events)
+    }
+
+    /// Events for a new state of the account at `address`: none if its
+    /// balance did not change.
+    fn update(
+        &mut self,
+        address: Pubkey,
+        account: Option<&Account>,
+        source: Source,
+    ) -> Result<Vec<WatchEvent>, crate::Error> {
+        let mint = account.and_then(token_account).map(|(mint, _, _)| mint);
+        if let Some(mint) = mint.filter(|mint| !self.decimals.contains_key(mint)) {
+            self.decimals
+                .insert(mint, mint_decimals(self.client, &mint)?);
+        }
+        let balance = account_balance(account, |mint| self.decimals[mint]);
+
+        let previous = self.balances.insert(address, balance.clone());
+        if previous.as_ref() == Some(&balance) {
+            return Ok(Vec::new());
+        }
+
+        let mut events = vec![WatchEvent::Balance {
+            address,
+            balance: balance.clone(),
+            source,
+        }];
+        let Some(previous) = previous else {
+            return Ok(events);
+        };
+
+        let decimals = balance.decimals().or(previous.decimals());
+        for threshold in &self.config.thresholds {
+            let Some(decimals) = decimals else { break };
+            if threshold.crossed(previous.base_units(), balance.base_units(), decimals)? {
+                events.push(WatchEvent::Crossed(Crossing {
+                    address,
+                    threshold: *threshold,
+                    previous: previous.clone(),
+                    balance: balance.clone(),
+                }));
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+/// Keeps a subscription to `address` open, reconnecting when it drops,
+/// until the watcher is gone.
+fn subscribe(address: Pubkey, config: &WatchConfig, sender: Sender<Message>) {
+    let account_config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(config.commitment),
+        ..RpcAccountInfoConfig::default()
+    };
+    let mut attempt = 0;
+
+    loop {
+        let error = match PubsubClient::account_subscribe(
+            &config.websocket_url,
+            &address,
+            Some(account_config.clone()),
+        ) {
+            Ok((_subscription, notifications)) => {
+                attempt = 0;
+                if sender.send(Message::Subscribed(address)).is_err() {
+                    return;
+                }
+                for notification in notifications.iter() {
+                    let account = notification.value.decode::<Account>();
+                    if sender.send(Message::Account(address, account)).is_err() {
+                        return;
+                    }
+                }
+                "connection closed".to_string()
+            }
+            Err(error) => error.to_string(),
+        };
+
+        if sender.send(Message::Unsubscribed(address, error)).is_err() {
+            return;
+        }
+        attempt += 1;
+        sleep(config.backoff(attempt));
+    }
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use std::time::Duration;
+
+use solana_client::client_error::ClientError;
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::pubkey::Pubkey;
+
+use practice_2::airdrop::{airdrop, fund_wallets, is_rate_limited, split_amount};
+use practice_2::AirdropConfig;
+
+use common::localnet;
+
+#[test]
+fn splits_amounts_under_the_cap() {
+    let sol = LAMPORTS_PER_SOL;
+    assert_eq!(
+        split_amount(5 * sol, Some(2 * sol)),
+        vec![2 * sol, 2 * sol, sol]
+    );
+    assert_eq!(split_amount(4 * sol, Some(2 * sol)), vec![2 * sol, 2 * sol]);
+    assert_eq!(split_amount(sol / 2, Some(2 * sol)), vec![sol / 2]);
+    assert_eq!(split_amount(100 * sol, None), vec![100 * sol]);
+    assert_eq!(split_amount(0, Some(sol)), Vec::<u64>::new());
+}
+
+#[test]
+fn caps_of_public_faucets() {
+    let devnet = AirdropConfig::for_url("https://api.devnet.solana.com");
+    assert_eq!(devnet.max_per_request, Some(2 * LAMPORTS_PER_SOL));
+    let localnet = AirdropConfig::for_url("http://127.0.0.1:8899");
+    assert_eq!(localnet.max_per_request, None);
+
+    assert_eq!(devnet.backoff(1), Duration::from_secs(2));
+    assert_eq!(devnet.backoff(3), Duration::from_secs(8));
+    assert_eq!(devnet.backoff(10), Duration::from_secs(30));
+}
+
+#[test]
+fn recognizes_faucet_refusals() {
+    let response_error = |code, message: &str| -> ClientError {
+        RpcError::RpcResponseError {
+            code,
+            message: message.to_string(),
+            data: RpcResponseErrorData::Empty,
+        }
+        .into()
+    };
+
+    assert!(is_rate_limited(&response_error(
+        -32603,
+        "airdrop request failed. This can happen when the rate limit is reached."
+    )));
+    assert!(!is_rate_limited(&response_error(-32603, "Internal error")));
+    assert!(is_rate_limited(&response_error(
+        429,
+        "You've either reached your airdrop limit today or the airdrop faucet has run dry."
+    )));
+    assert!(!is_rate_limited(&response_error(-32602, "Invalid params")));
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn airdrop_is_confirmed_before_returning() {
+    let client = localnet();
+    let recipient = Pubkey::new_unique();
+    let config = AirdropConfig {
+        max_per_request: Some(LAMPORTS_PER_SOL),
+        ..AirdropConfig::default()
+    };
+
+    let result = airdrop(&client, &recipient, 5 * LAMPORTS_PER_SOL / 2, &config).unwrap();
+    assert_eq!(result.signatures.len(), 3);
+    assert_eq!(result.balance, 5 * LAMPORTS_PER_SOL / 2);
+    assert_eq!(client.get_balance(&recipient).unwrap(), result.balance);
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn tops_up_a_list_of_wallets() {
+    let client = localnet();
+    let wallets = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let config = AirdropConfig::default();
+    airdrop(&client, &wallets[0], LAMPORTS_PER_SOL, &config).unwrap();
+
+    let top_up = AirdropConfig {
+        top_up: true,
+        ..config
+    };
+    let results = fund_wallets(&client, &wallets, 2 * LAMPORTS_PER_SOL, &top_up);
+
+    let requested: Vec<_> = results
+        .into_iter()
+        .map(|(_, result)| result.unwrap().lamports)
+        .collect();
+    assert_eq!(requested, vec![LAMPORTS_PER_SOL, 2 * LAMPORTS_PER_SOL]);
+    for wallet in &wallets {
+        assert_eq!(client.get_balance(wallet).unwrap(), 2 * LAMPORTS_PER_SOL);
+    }
+}
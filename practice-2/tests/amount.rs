@@ -0,0 +1,93 @@
+mod common;
+
+use solana_sdk::signature::{Keypair, Signer};
+
+use practice_2::amount::{
+    format_amount, format_sol, mint_decimals, parse_amount, parse_sol, token_amount,
+};
+use practice_2::token::create_token_mint;
+use practice_2::{Amount, AmountError, TransactionConfig};
+
+use common::{funded_keypair, localnet};
+
+#[test]
+fn parses_decimal_amounts() {
+    assert_eq!(parse_sol("1.5"), Ok(1_500_000_000));
+    assert_eq!(parse_sol("0.000000001"), Ok(1));
+    assert_eq!(parse_sol(".25"), Ok(250_000_000));
+    assert_eq!(parse_sol("2."), Ok(2_000_000_000));
+    assert_eq!(parse_amount("9.99999999", 9), Ok(9_999_999_990));
+    assert_eq!(parse_amount("150", 0), Ok(150));
+    // Trailing zeros are not extra precision.
+    assert_eq!(parse_amount("1.500", 1), Ok(15));
+    assert_eq!(parse_amount("0007", 2), Ok(700));
+
+    for invalid in ["", ".", "-1", "+1", "1e9", "1.2.3", "1,5", " 1"] {
+        assert_eq!(
+            parse_sol(invalid),
+            Err(AmountError::Invalid(invalid.to_string()))
+        );
+    }
+}
+
+#[test]
+fn rejects_too_precise_and_too_large_amounts() {
+    assert!(matches!(
+        parse_sol("0.0000000001"),
+        Err(AmountError::TooPrecise { decimals: 9, .. })
+    ));
+    assert!(matches!(
+        parse_amount("1.5", 0),
+        Err(AmountError::TooPrecise { decimals: 0, .. })
+    ));
+
+    assert_eq!(parse_amount("18446744073709551615", 0), Ok(u64::MAX));
+    assert!(matches!(
+        parse_amount("18446744073709551616", 0),
+        Err(AmountError::Overflow { .. })
+    ));
+    assert!(matches!(
+        parse_sol("18446744074"),
+        Err(AmountError::Overflow { .. })
+    ));
+    assert!(matches!(
+        "1".repeat(50).parse::<Amount>(),
+        Err(AmountError::TooLarge(_))
+    ));
+    assert!(matches!(
+        parse_amount("1", 255),
+        Err(AmountError::Overflow { .. })
+    ));
+}
+
+#[test]
+fn formats_with_full_precision() {
+    assert_eq!(format_sol(1_500_000_000), "1.5");
+    assert_eq!(format_sol(1), "0.000000001");
+    assert_eq!(format_sol(0), "0");
+    assert_eq!(format_sol(u64::MAX), "18446744073.709551615");
+    assert_eq!(format_amount(9_999_999_990, 9), "9.99999999");
+    assert_eq!(format_amount(150, 0), "150");
+
+    for lamports in [0, 1, 10, 999_999_999, 1_000_000_000, u64::MAX] {
+        assert_eq!(parse_sol(&format_sol(lamports)), Ok(lamports));
+    }
+    assert_eq!("01.2500".parse::<Amount>().unwrap().to_string(), "1.25");
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn reads_decimals_of_the_mint() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 1);
+    let mint = Keypair::new();
+    create_token_mint(&client, &payer, &mint, 6, &TransactionConfig::default()).unwrap();
+
+    assert_eq!(mint_decimals(&client, &mint.pubkey()).unwrap(), 6);
+    let amount = "2.5".parse().unwrap();
+    assert_eq!(
+        token_amount(&client, &mint.pubkey(), amount).unwrap(),
+        2_500_000
+    );
+    assert!(mint_decimals(&client, &payer.pubkey()).is_err());
+}
@@ -0,0 +1,36 @@
+//! Helpers for tests that need a local validator, started with
+//! `solana-test-validator`. Such tests are `#[ignore]`d; run them with
+//! `cargo test -p practice-2 -- --ignored`.
+
+#![allow(dead_code)]
+
+use solana_client::rpc_client::RpcClient;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{Keypair, Signer};
+
+use practice_2::airdrop::airdrop;
+use practice_2::AirdropConfig;
+
+/// RPC URL of the local validator, `LOCALNET_URL` or the default port.
+pub fn localnet_url() -> String {
+    std::env::var("LOCALNET_URL").unwrap_or_else(|_| "http://127.0.0.1:8899".to_string())
+}
+
+pub fn localnet() -> RpcClient {
+    RpcClient::new_with_commitment(localnet_url(), CommitmentConfig::confirmed())
+}
+
+/// A new keypair holding `sol` airdropped SOL.
+pub fn funded_keypair(client: &RpcClient, sol: u64) -> Keypair {
+    let keypair = Keypair::new();
+    airdrop(
+        client,
+        &keypair.pubkey(),
+        sol * LAMPORTS_PER_SOL,
+        &AirdropConfig::default(),
+    )
+    .unwrap();
+
+    keypair
+}
@@ -0,0 +1,144 @@
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use practice_2::config::{
+    normalize_url, ClusterArgs, COMMITMENT_VAR, PROFILE_FILE_VAR, PROFILE_VAR, RPC_URL_VAR,
+    WEBSOCKET_URL_VAR,
+};
+use practice_2::{ClusterConfig, ConfigError};
+
+#[test]
+fn expands_cluster_monikers() {
+    for (moniker, url) in [
+        ("localnet", "http://127.0.0.1:8899"),
+        ("l", "http://127.0.0.1:8899"),
+        ("devnet", "https://api.devnet.solana.com"),
+        ("d", "https://api.devnet.solana.com"),
+        ("testnet", "https://api.testnet.solana.com"),
+        ("t", "https://api.testnet.solana.com"),
+        ("mainnet-beta", "https://api.mainnet-beta.solana.com"),
+        ("m", "https://api.mainnet-beta.solana.com"),
+        ("https://rpc.example.com", "https://rpc.example.com"),
+    ] {
+        assert_eq!(normalize_url(moniker), url);
+    }
+}
+
+// Resolving reads the process environment, the current directory and the
+// Solana CLI config under `HOME`, so every step runs in this one test.
+#[test]
+fn resolves_flags_then_env_then_profile_then_cli_config_then_default() {
+    let dir = std::env::temp_dir().join(format!("cluster-config-{}", std::process::id()));
+    let cli_config = dir.join(".config/solana/cli/config.yml");
+    std::fs::create_dir_all(cli_config.parent().unwrap()).unwrap();
+    std::env::set_var("HOME", &dir);
+    std::env::set_current_dir(&dir).unwrap();
+    for var in [
+        RPC_URL_VAR,
+        WEBSOCKET_URL_VAR,
+        COMMITMENT_VAR,
+        PROFILE_VAR,
+        PROFILE_FILE_VAR,
+    ] {
+        std::env::remove_var(var);
+    }
+    let resolve = |args: &ClusterArgs| ClusterConfig::resolve(args).unwrap();
+    let no_flags = ClusterArgs::default();
+
+    assert_eq!(
+        resolve(&no_flags),
+        ClusterConfig {
+            url: "https://api.devnet.solana.com".to_string(),
+            websocket_url: "wss://api.devnet.solana.com/".to_string(),
+            commitment: CommitmentConfig::confirmed(),
+        }
+    );
+
+    std::fs::write(
+        &cli_config,
+        "json_rpc_url: t\n\
+         websocket_url: wss://testnet.example.com/\n\
+         keypair_path: id.json\n\
+         commitment: finalized\n",
+    )
+    .unwrap();
+    assert_eq!(
+        resolve(&no_flags),
+        ClusterConfig {
+            url: "https://api.testnet.solana.com".to_string(),
+            websocket_url: "wss://testnet.example.com/".to_string(),
+            commitment: CommitmentConfig::finalized(),
+        }
+    );
+
+    std::fs::write(
+        dir.join("cluster.toml"),
+        "default = \"ci\"\n\
+         [profiles.ci]\n\
+         url = \"localnet\"\n\
+         commitment = \"processed\"\n\
+         [profiles.dev]\n\
+         url = \"devnet\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        resolve(&no_flags),
+        ClusterConfig {
+            url: "http://127.0.0.1:8899".to_string(),
+            websocket_url: "ws://127.0.0.1:8900/".to_string(),
+            commitment: CommitmentConfig::processed(),
+        }
+    );
+
+    // Settings a profile leaves out come from the CLI config.
+    std::env::set_var(PROFILE_VAR, "dev");
+    let dev = resolve(&no_flags);
+    assert_eq!(dev.url, "https://api.devnet.solana.com");
+    assert_eq!(dev.commitment, CommitmentConfig::finalized());
+
+    std::env::set_var(RPC_URL_VAR, "m");
+    std::env::set_var(COMMITMENT_VAR, "confirmed");
+    let env = resolve(&no_flags);
+    assert_eq!(env.url, "https://api.mainnet-beta.solana.com");
+    assert_eq!(env.commitment, CommitmentConfig::confirmed());
+
+    let flags = resolve(&ClusterArgs {
+        url: Some("https://rpc.example.com".to_string()),
+        ws_url: Some("wss://ws.example.com".to_string()),
+        commitment: Some("processed".to_string()),
+        profile: Some("ci".to_string()),
+    });
+    assert_eq!(
+        flags,
+        ClusterConfig {
+            url: "https://rpc.example.com".to_string(),
+            websocket_url: "wss://ws.example.com".to_string(),
+            commitment: CommitmentConfig::processed(),
+        }
+    );
+
+    let unknown = ClusterArgs {
+        profile: Some("staging".to_string()),
+        ..ClusterArgs::default()
+    };
+    assert!(matches!(
+        ClusterConfig::resolve(&unknown),
+        Err(ConfigError::UnknownProfile { .. })
+    ));
+    std::fs::remove_file(dir.join("cluster.toml")).unwrap();
+    assert!(matches!(
+        ClusterConfig::resolve(&unknown),
+        Err(ConfigError::NoProfileFile(_))
+    ));
+
+    std::env::remove_var(PROFILE_VAR);
+    std::env::set_var(COMMITMENT_VAR, "eventually");
+    assert!(matches!(
+        ClusterConfig::resolve(&no_flags),
+        Err(ConfigError::Commitment(_))
+    ));
+
+    for var in [RPC_URL_VAR, COMMITMENT_VAR] {
+        std::env::remove_var(var);
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
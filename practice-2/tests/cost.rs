@@ -0,0 +1,216 @@
+mod common;
+
+use solana_program::message::{Message, VersionedMessage};
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::system_instruction::transfer;
+use solana_sdk::account::{create_account_for_test, Account};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_associated_token_account::get_associated_token_address;
+
+use practice_2::cost::{
+    AccountKind, CostError, CostPlan, LAMPORTS_PER_SIGNATURE, METADATA_CREATE_FEE, METADATA_LEN,
+};
+use practice_2::escrow::{make_offer_instruction, EscrowProgram, OfferTerms};
+use practice_2::fees::compute_budget_instructions;
+use practice_2::metadata::{create_token_metadata_instruction, metadata_address, TokenMetadata};
+use practice_2::token::{
+    create_associated_token_account_instruction, create_token_mint, create_token_mint_instructions,
+};
+use practice_2::{TransactionConfig, TransactionOutcome};
+
+use common::{funded_keypair, localnet};
+
+/// Accounts for [`CostPlan::estimate`]: the rent sysvar, a fee payer with
+/// `balance` and nothing else existing.
+fn accounts(plan: &CostPlan, balance: u64) -> Vec<Option<Account>> {
+    let mut accounts = vec![
+        Some(create_account_for_test(&Rent::default())),
+        Some(Account::new(balance, 0, &Pubkey::default())),
+    ];
+    accounts.resize(plan.addresses().len(), None);
+
+    accounts
+}
+
+#[test]
+fn estimates_a_new_token() {
+    let payer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let rent = Rent::default();
+    let mint_rent = rent.minimum_balance(spl_token::state::Mint::LEN);
+    let metadata = TokenMetadata {
+        name: "NAZAR".to_string(),
+        symbol: "DEMCHUK".to_string(),
+        uri: "https://arweave.net/1234".to_string(),
+    };
+
+    let mut instructions = compute_budget_instructions(Some(300_000), Some(1_000));
+    instructions.extend(create_token_mint_instructions(&payer, &mint, 9, mint_rent).unwrap());
+    instructions.push(create_associated_token_account_instruction(
+        &mint, &payer, &payer,
+    ));
+    instructions.push(create_token_metadata_instruction(
+        &mint, &payer, metadata, false,
+    ));
+    let message = VersionedMessage::Legacy(Message::new(&instructions, Some(&payer)));
+
+    let plan = CostPlan::new(&message, &[]).unwrap();
+    let accounts = accounts(&plan, 1_000_000_000);
+    let estimate = plan.estimate(accounts, None).unwrap();
+
+    assert_eq!(estimate.signatures, 2);
+    assert_eq!(estimate.signature_fee, 2 * LAMPORTS_PER_SIGNATURE);
+    assert_eq!(estimate.priority_fee, 300);
+    assert_eq!(estimate.program_fees, METADATA_CREATE_FEE);
+
+    let deposits: Vec<_> = estimate
+        .rent
+        .iter()
+        .map(|deposit| (deposit.address, deposit.kind, deposit.lamports))
+        .collect();
+    assert_eq!(
+        deposits,
+        vec![
+            (mint, AccountKind::Mint, mint_rent),
+            (
+                get_associated_token_address(&payer, &mint),
+                AccountKind::TokenAccount,
+                rent.minimum_balance(spl_token::state::Account::LEN)
+            ),
+            (
+                metadata_address(&mint),
+                AccountKind::Metadata,
+                rent.minimum_balance(METADATA_LEN)
+            ),
+        ]
+    );
+    assert_eq!(
+        estimate.total(),
+        estimate.fees()
+            + deposits
+                .iter()
+                .map(|(_, _, lamports)| lamports)
+                .sum::<u64>()
+    );
+    assert!(estimate.is_covered());
+}
+
+#[test]
+fn warns_when_the_payer_cannot_cover() {
+    let maker = Pubkey::new_unique();
+    let terms = OfferTerms {
+        id: 1,
+        token_mint_a: Pubkey::new_unique(),
+        token_mint_b: Pubkey::new_unique(),
+        token_a_offered_amount: 10,
+        token_b_wanted_amount: 20,
+    };
+    let ix = make_offer_instruction(EscrowProgram::Escrow, &maker, &spl_token::id(), &terms);
+    let message = VersionedMessage::Legacy(Message::new(&[ix], Some(&maker)));
+
+    let plan = CostPlan::new(&message, &[]).unwrap();
+    let accounts = accounts(&plan, 10_000);
+    let estimate = plan.estimate(accounts, None).unwrap();
+
+    let kinds: Vec<_> = estimate.rent.iter().map(|deposit| deposit.kind).collect();
+    assert_eq!(kinds, vec![AccountKind::Offer, AccountKind::Vault]);
+    assert_eq!(estimate.rent[0].space, EscrowProgram::Escrow.offer_len());
+    assert_eq!(estimate.priority_fee, 0);
+    assert!(!estimate.is_covered());
+    assert!(estimate.to_string().contains("cannot cover"));
+}
+
+#[test]
+fn takes_the_signature_fee_from_the_cluster() {
+    let payer = Pubkey::new_unique();
+    let mut instructions = compute_budget_instructions(Some(100_000), Some(10_000));
+    instructions.push(transfer(&payer, &Pubkey::new_unique(), 1));
+    let message = VersionedMessage::Legacy(Message::new(&instructions, Some(&payer)));
+
+    // `getFeeForMessage` counts the priority fee too.
+    let plan = CostPlan::new(&message, &[]).unwrap();
+    let accounts = accounts(&plan, 1_000_000_000);
+    let estimate = plan.estimate(accounts, Some(7_000 + 1_000)).unwrap();
+    assert_eq!(estimate.priority_fee, 1_000);
+    assert_eq!(estimate.signature_fee, 7_000);
+    assert_eq!(estimate.total(), 8_000);
+}
+
+#[test]
+fn rejects_malformed_messages_and_accounts() {
+    let payer = Pubkey::new_unique();
+    let mut message = Message::new(&[transfer(&payer, &Pubkey::new_unique(), 1)], Some(&payer));
+
+    let plan = CostPlan::new(&VersionedMessage::Legacy(message.clone()), &[]).unwrap();
+    assert_eq!(
+        plan.estimate(vec![None], None).unwrap_err(),
+        CostError::AccountCount {
+            expected: 2,
+            actual: 1
+        }
+    );
+
+    message.instructions[0].program_id_index = 9;
+    assert_eq!(
+        CostPlan::new(&VersionedMessage::Legacy(message.clone()), &[]).err(),
+        Some(CostError::InvalidProgramIndex(0))
+    );
+
+    message.account_keys.clear();
+    message.instructions.clear();
+    assert_eq!(
+        CostPlan::new(&VersionedMessage::Legacy(message), &[]).err(),
+        Some(CostError::NoFeePayer)
+    );
+}
+
+/// Accounts that already exist cost nothing.
+#[test]
+fn skips_existing_accounts() {
+    let payer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let ix = create_associated_token_account_instruction(&mint, &payer, &payer);
+    let message = VersionedMessage::Legacy(Message::new(&[ix], Some(&payer)));
+
+    let plan = CostPlan::new(&message, &[]).unwrap();
+    let mut accounts = accounts(&plan, 1_000_000_000);
+    accounts[2] = Some(Account::new(2_039_280, 165, &spl_token::id()));
+
+    let estimate = plan.estimate(accounts, None).unwrap();
+    assert!(estimate.rent.is_empty());
+    assert_eq!(estimate.total(), LAMPORTS_PER_SIGNATURE);
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn estimate_matches_the_spent_lamports() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 1);
+    let estimate = TransactionConfig {
+        estimate: true,
+        ..TransactionConfig::default()
+    };
+
+    let outcome = create_token_mint(&client, &payer, &Keypair::new(), 9, &estimate).unwrap();
+    let TransactionOutcome::Estimated(cost) = outcome else {
+        panic!("expected an estimate, got {outcome:?}");
+    };
+    assert_eq!(cost.fee_payer, payer.pubkey());
+
+    let balance = client.get_balance(&payer.pubkey()).unwrap();
+    assert_eq!(cost.fee_payer_balance, balance);
+    create_token_mint(
+        &client,
+        &payer,
+        &Keypair::new(),
+        9,
+        &TransactionConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        balance - client.get_balance(&payer.pubkey()).unwrap(),
+        cost.total()
+    );
+}
@@ -0,0 +1,261 @@
+use base64::prelude::{Engine, BASE64_STANDARD};
+use mpl_token_metadata::instructions::{
+    CreateMetadataAccountV3, CreateMetadataAccountV3InstructionArgs,
+};
+use mpl_token_metadata::types::DataV2;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::bs58;
+use solana_sdk::message::Message;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+use practice_2::decoder::{decode_instructions, DecodedInstruction, TransactionSource};
+use practice_2::escrow::{
+    make_offer_instruction, take_offer_instruction, EscrowProgram, Offer, OfferTerms,
+};
+use practice_2::programs::KnownProgram;
+use practice_2::sol::send_sol_with_memo_instructions;
+
+fn decode(instruction: &Instruction) -> DecodedInstruction {
+    DecodedInstruction::new(
+        instruction.program_id,
+        instruction
+            .accounts
+            .iter()
+            .map(|account| Some(account.pubkey))
+            .collect(),
+        &instruction.data,
+    )
+}
+
+fn arg<'a>(instruction: &'a DecodedInstruction, name: &str) -> &'a str {
+    &instruction
+        .args
+        .iter()
+        .find(|(arg, _)| *arg == name)
+        .unwrap_or_else(|| panic!("no {name} in {instruction}"))
+        .1
+}
+
+fn role(instruction: &DecodedInstruction, role: &str) -> Pubkey {
+    instruction
+        .accounts
+        .iter()
+        .find(|account| account.role == Some(role))
+        .and_then(|account| account.address)
+        .unwrap_or_else(|| panic!("no {role} in {instruction}"))
+}
+
+#[test]
+fn decodes_a_transfer_with_a_memo() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let instructions = send_sol_with_memo_instructions(&sender, &recipient, 1_500_000, Some("hi"));
+    let message = Message::new(&instructions, Some(&sender));
+    let decoded = decode_instructions(
+        &VersionedTransaction::from(Transaction::new_unsigned(message.clone())).message,
+        &message.account_keys,
+    );
+
+    assert_eq!(decoded.len(), 2);
+    assert_eq!(decoded[0].program, Some(KnownProgram::System));
+    assert_eq!(decoded[0].name.as_deref(), Some("Transfer"));
+    assert_eq!(arg(&decoded[0], "lamports"), "0.0015 SOL");
+    assert_eq!(role(&decoded[0], "from"), sender);
+    assert_eq!(role(&decoded[0], "to"), recipient);
+
+    assert!(decoded[1].decoded);
+    assert_eq!(decoded[1].name, None);
+    assert_eq!(arg(&decoded[1], "text"), "hi");
+    assert!(decoded[1].to_string().starts_with("Memo\n    text: hi"));
+}
+
+#[test]
+fn decodes_token_instructions_of_both_programs() {
+    let (source, mint, destination, owner) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    for program in [spl_token::id(), spl_token_2022::id()] {
+        let instruction = spl_token_2022::instruction::transfer_checked(
+            &program,
+            &source,
+            &mint,
+            &destination,
+            &owner,
+            &[],
+            2_500_000,
+            6,
+        )
+        .unwrap();
+        let decoded = decode(&instruction);
+
+        assert_eq!(decoded.name.as_deref(), Some("TransferChecked"));
+        assert_eq!(arg(&decoded, "amount"), "2.5");
+        assert_eq!(role(&decoded, "destination"), destination);
+        assert_eq!(role(&decoded, "authority"), owner);
+    }
+
+    let instruction = spl_token_2022::instruction::initialize_mint_close_authority(
+        &spl_token_2022::id(),
+        &mint,
+        Some(&owner),
+    )
+    .unwrap();
+    let decoded = decode(&instruction);
+    assert_eq!(
+        decoded.name.as_deref(),
+        Some("InitializeMintCloseAuthority")
+    );
+    assert!(arg(&decoded, "args").contains(&owner.to_string()));
+}
+
+#[test]
+fn decodes_metadata_creation() {
+    let (metadata, mint, authority) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let instruction = CreateMetadataAccountV3 {
+        metadata,
+        mint,
+        mint_authority: authority,
+        payer: authority,
+        update_authority: (authority, true),
+        system_program: solana_program::system_program::id(),
+        rent: None,
+    }
+    .instruction(CreateMetadataAccountV3InstructionArgs {
+        data: DataV2 {
+            name: "NAZAR".to_string(),
+            symbol: "DEMCHUK".to_string(),
+            uri: "https://arweave.net/1234".to_string(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        },
+        is_mutable: true,
+        collection_details: None,
+    });
+    let decoded = decode(&instruction);
+
+    assert_eq!(decoded.name.as_deref(), Some("CreateMetadataAccountV3"));
+    assert_eq!(arg(&decoded, "name"), "NAZAR");
+    assert_eq!(arg(&decoded, "symbol"), "DEMCHUK");
+    assert_eq!(arg(&decoded, "uri"), "https://arweave.net/1234");
+    assert_eq!(arg(&decoded, "mutable"), "true");
+    assert_eq!(role(&decoded, "metadata"), metadata);
+    assert_eq!(role(&decoded, "mint"), mint);
+}
+
+#[test]
+fn decodes_escrow_offers_of_both_programs() {
+    let maker = Pubkey::new_unique();
+    let taker = Pubkey::new_unique();
+    let terms = OfferTerms {
+        id: 7,
+        token_mint_a: Pubkey::new_unique(),
+        token_mint_b: Pubkey::new_unique(),
+        token_a_offered_amount: 100,
+        token_b_wanted_amount: 50,
+    };
+    let offer = Offer {
+        id: 7,
+        maker,
+        token_mint_a: terms.token_mint_a,
+        token_mint_b: terms.token_mint_b,
+        token_a_offered_amount: None,
+        token_b_wanted_amount: 50,
+        bump: 255,
+    };
+
+    for program in [EscrowProgram::Escrow, EscrowProgram::EscrowApprove] {
+        let make = decode(&make_offer_instruction(
+            program,
+            &maker,
+            &spl_token::id(),
+            &terms,
+        ));
+        assert_eq!(make.name.as_deref(), Some("make_offer"));
+        assert_eq!(arg(&make, "id"), "7");
+        assert_eq!(arg(&make, "token A offered amount"), "100");
+        assert_eq!(arg(&make, "token B wanted amount"), "50");
+        assert_eq!(role(&make, "maker"), maker);
+        assert_eq!(role(&make, "token mint B"), terms.token_mint_b);
+        assert_eq!(
+            role(&make, "token program"),
+            spl_token::id(),
+            "{program:?}: {make}"
+        );
+
+        let take = decode(&take_offer_instruction(
+            program,
+            &taker,
+            &offer,
+            &spl_token::id(),
+        ));
+        assert_eq!(take.name.as_deref(), Some("take_offer"));
+        assert_eq!(role(&take, "taker"), taker);
+        assert_eq!(role(&take, "maker"), maker);
+        assert_eq!(
+            role(&take, "system program"),
+            solana_program::system_program::id(),
+            "{program:?}: {take}"
+        );
+    }
+}
+
+#[test]
+fn shows_unknown_instructions_as_raw_data() {
+    let program = Pubkey::new_unique();
+    let decoded = DecodedInstruction::new(program, vec![None], &[1, 2, 3]);
+
+    assert!(!decoded.decoded);
+    assert_eq!(
+        decoded.to_string(),
+        format!(
+            "{program}: unknown instruction\n    account 0: (from a lookup table)\n    data: {}",
+            bs58::encode([1, 2, 3]).into_string()
+        )
+    );
+}
+
+#[test]
+fn parses_signatures_and_wire_transactions() {
+    let signature = Signature::from([7; 64]);
+    assert_eq!(
+        signature.to_string().parse::<TransactionSource>().unwrap(),
+        TransactionSource::Signature(signature)
+    );
+
+    let payer = Pubkey::new_unique();
+    let message = Message::new(
+        &send_sol_with_memo_instructions(&payer, &Pubkey::new_unique(), 1, None),
+        Some(&payer),
+    );
+    let transaction = VersionedTransaction::from(Transaction::new_unsigned(message));
+    let bytes = bincode::serialize(&transaction).unwrap();
+
+    for encoded in [
+        BASE64_STANDARD.encode(&bytes),
+        bs58::encode(&bytes).into_string(),
+    ] {
+        assert_eq!(
+            encoded.parse::<TransactionSource>().unwrap(),
+            TransactionSource::Wire(transaction.clone())
+        );
+    }
+
+    assert!("not a transaction".parse::<TransactionSource>().is_err());
+
+    let mut unsigned = transaction;
+    unsigned.signatures.clear();
+    let encoded = BASE64_STANDARD.encode(bincode::serialize(&unsigned).unwrap());
+    assert!(encoded.parse::<TransactionSource>().is_err());
+}
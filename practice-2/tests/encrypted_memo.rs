@@ -0,0 +1,151 @@
+mod common;
+
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use practice_2::encrypted_memo::{
+    decrypt_history, decrypt_memo, decrypt_sent_memo, encrypt_memo, is_encrypted,
+};
+use practice_2::history::HistoryConfig;
+use practice_2::sol::{send_sol_with_encrypted_memo, send_sol_with_memo};
+use practice_2::{MemoError, TransactionConfig, TransactionOutcome};
+
+use common::{funded_keypair, localnet};
+
+#[test]
+fn only_the_recipient_reads_the_memo() {
+    let sender = Keypair::new();
+    let recipient = Keypair::new();
+
+    let envelope = encrypt_memo(&sender, &recipient.pubkey(), "invoice 2024-117").unwrap();
+    assert!(envelope.starts_with("enc:1:"), "{envelope}");
+    assert!(is_encrypted(&envelope));
+    assert!(!envelope.contains("invoice"));
+    assert_ne!(
+        envelope,
+        encrypt_memo(&sender, &recipient.pubkey(), "invoice 2024-117").unwrap()
+    );
+
+    assert_eq!(
+        decrypt_memo(&recipient, &sender.pubkey(), &envelope).unwrap(),
+        "invoice 2024-117"
+    );
+    // Someone else, or the right recipient told the wrong sender.
+    assert_eq!(
+        decrypt_memo(&Keypair::new(), &sender.pubkey(), &envelope),
+        Err(MemoError::Decrypt)
+    );
+    assert_eq!(
+        decrypt_memo(&recipient, &Keypair::new().pubkey(), &envelope),
+        Err(MemoError::Decrypt)
+    );
+}
+
+#[test]
+fn the_sender_reads_back_what_it_sent() {
+    let sender = Keypair::new();
+    let recipient = Keypair::new();
+    let envelope = encrypt_memo(&sender, &recipient.pubkey(), "invoice 2024-117").unwrap();
+
+    assert_eq!(
+        decrypt_sent_memo(&sender, &recipient.pubkey(), &envelope).unwrap(),
+        "invoice 2024-117"
+    );
+    assert_eq!(
+        decrypt_sent_memo(&sender, &Keypair::new().pubkey(), &envelope),
+        Err(MemoError::Decrypt)
+    );
+    // The recipient cannot pass the memo off as one it sent.
+    assert_eq!(
+        decrypt_sent_memo(&recipient, &sender.pubkey(), &envelope),
+        Err(MemoError::Decrypt)
+    );
+}
+
+#[test]
+fn rejects_altered_and_unknown_envelopes() {
+    let sender = Keypair::new();
+    let recipient = Keypair::new();
+    let envelope = encrypt_memo(&sender, &recipient.pubkey(), "hi").unwrap();
+    let decrypt = |envelope: &str| decrypt_memo(&recipient, &sender.pubkey(), envelope);
+
+    let mut altered = envelope.clone().into_bytes();
+    let last = altered.len() - 3;
+    altered[last] = if altered[last] == b'A' { b'B' } else { b'A' };
+    assert_eq!(
+        decrypt(std::str::from_utf8(&altered).unwrap()),
+        Err(MemoError::Decrypt)
+    );
+
+    assert_eq!(
+        decrypt(&envelope.replacen("enc:1:", "enc:2:", 1)),
+        Err(MemoError::UnsupportedVersion("2".to_string()))
+    );
+    assert_eq!(decrypt("Hello, Nazar"), Err(MemoError::NotEnvelope));
+    assert!(matches!(
+        decrypt("enc:1:AAAA"),
+        Err(MemoError::Malformed(_))
+    ));
+    assert!(matches!(decrypt("enc:1:???"), Err(MemoError::Malformed(_))));
+}
+
+#[test]
+fn refuses_recipients_without_an_ed25519_key() {
+    let (pda, _) = Pubkey::find_program_address(&[b"vault"], &spl_memo::id());
+
+    assert_eq!(
+        encrypt_memo(&Keypair::new(), &pda, "hi"),
+        Err(MemoError::NotEd25519(pda))
+    );
+    // The identity point is on the curve, but of small order.
+    let mut identity = [0u8; 32];
+    identity[0] = 1;
+    let identity = Pubkey::from(identity);
+    assert_eq!(
+        encrypt_memo(&Keypair::new(), &identity, "hi"),
+        Err(MemoError::NotEd25519(identity))
+    );
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn decrypts_memos_from_the_history() {
+    let client = localnet();
+    let sender = funded_keypair(&client, 1);
+    let recipient = Keypair::new();
+    let config = TransactionConfig::default();
+
+    send_sol_with_memo(
+        &client,
+        &sender,
+        &recipient.pubkey(),
+        1_000_000,
+        Some("public"),
+        &config,
+    )
+    .unwrap();
+    let TransactionOutcome::Confirmed(signature) = send_sol_with_encrypted_memo(
+        &client,
+        &sender,
+        &recipient.pubkey(),
+        1_000_000,
+        "invoice 7",
+        &config,
+    )
+    .unwrap() else {
+        panic!("not sent");
+    };
+
+    let memos = decrypt_history(&client, &recipient, &HistoryConfig::default()).unwrap();
+    assert_eq!(memos.len(), 1);
+    assert_eq!(memos[0].signature, signature);
+    assert_eq!(memos[0].sender, sender.pubkey());
+    assert_eq!(memos[0].recipient, recipient.pubkey());
+    assert_eq!(memos[0].memo.as_deref(), Ok("invoice 7"));
+
+    let sent = decrypt_history(&client, &sender, &HistoryConfig::default()).unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].signature, signature);
+    assert_eq!(sent[0].recipient, recipient.pubkey());
+    assert_eq!(sent[0].memo.as_deref(), Ok("invoice 7"));
+}
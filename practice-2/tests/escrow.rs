@@ -0,0 +1,111 @@
+use solana_program::pubkey::Pubkey;
+
+use practice_2::escrow::{
+    anchor_discriminator, decode_offer, make_offer_instruction, offer_address,
+    take_offer_instruction, vault_address, EscrowProgram, Offer, OfferTerms,
+};
+
+/// Discriminators as in the IDL Anchor generates for the programs.
+#[test]
+fn anchor_discriminators() {
+    assert_eq!(
+        anchor_discriminator("global:make_offer"),
+        [214, 98, 97, 35, 59, 12, 44, 178]
+    );
+    assert_eq!(
+        anchor_discriminator("global:take_offer"),
+        [128, 156, 242, 207, 237, 192, 103, 240]
+    );
+    assert_eq!(
+        anchor_discriminator("account:Offer"),
+        [215, 88, 60, 71, 170, 162, 73, 229]
+    );
+}
+
+#[test]
+fn offer_instructions() {
+    let maker = Pubkey::new_unique();
+    let taker = Pubkey::new_unique();
+    let token_program = spl_token_2022::id();
+    let terms = OfferTerms {
+        id: 7,
+        token_mint_a: Pubkey::new_unique(),
+        token_mint_b: Pubkey::new_unique(),
+        token_a_offered_amount: 10,
+        token_b_wanted_amount: 20,
+    };
+
+    let ix = make_offer_instruction(EscrowProgram::Escrow, &maker, &token_program, &terms);
+    let offer = offer_address(EscrowProgram::Escrow, &maker, 7);
+    assert_eq!(ix.program_id, EscrowProgram::Escrow.id());
+    assert_eq!(ix.accounts.len(), 9);
+    assert_eq!(ix.accounts[4].pubkey, offer);
+    assert_eq!(
+        ix.accounts[5].pubkey,
+        vault_address(&offer, &terms.token_mint_a, &token_program)
+    );
+    assert_eq!(&ix.data[8..16], &7u64.to_le_bytes());
+    assert_eq!(&ix.data[16..24], &10u64.to_le_bytes());
+    assert_eq!(&ix.data[24..], &20u64.to_le_bytes());
+
+    // No vault: the offered tokens stay with the maker.
+    let ix = make_offer_instruction(EscrowProgram::EscrowApprove, &maker, &token_program, &terms);
+    assert_eq!(ix.accounts.len(), 8);
+    assert_ne!(
+        offer_address(EscrowProgram::EscrowApprove, &maker, 7),
+        offer
+    );
+
+    let open = Offer {
+        id: 7,
+        maker,
+        token_mint_a: terms.token_mint_a,
+        token_mint_b: terms.token_mint_b,
+        token_a_offered_amount: None,
+        token_b_wanted_amount: 20,
+        bump: 255,
+    };
+    let ix = take_offer_instruction(EscrowProgram::Escrow, &taker, &open, &token_program);
+    assert_eq!(ix.accounts.len(), 12);
+    assert!(ix.accounts[0].is_signer);
+    assert_eq!(ix.accounts[7].pubkey, offer);
+    assert_eq!(ix.data, anchor_discriminator("global:take_offer"));
+}
+
+#[test]
+fn decodes_offers() {
+    let maker = Pubkey::new_unique();
+    let mint_a = Pubkey::new_unique();
+    let mint_b = Pubkey::new_unique();
+    let mut data = anchor_discriminator("account:Offer").to_vec();
+    data.extend(3u64.to_le_bytes());
+    data.extend(maker.to_bytes());
+    data.extend(mint_a.to_bytes());
+    data.extend(mint_b.to_bytes());
+    let mut approve_data = data.clone();
+    data.extend(20u64.to_le_bytes());
+    data.push(254);
+    approve_data.extend(10u64.to_le_bytes());
+    approve_data.extend(20u64.to_le_bytes());
+    approve_data.push(254);
+
+    let offer = decode_offer(EscrowProgram::Escrow, &data).unwrap();
+    assert_eq!(
+        offer,
+        Offer {
+            id: 3,
+            maker,
+            token_mint_a: mint_a,
+            token_mint_b: mint_b,
+            token_a_offered_amount: None,
+            token_b_wanted_amount: 20,
+            bump: 254,
+        }
+    );
+    let offer = decode_offer(EscrowProgram::EscrowApprove, &approve_data).unwrap();
+    assert_eq!(offer.token_a_offered_amount, Some(10));
+    assert_eq!(offer.token_b_wanted_amount, 20);
+
+    assert_eq!(decode_offer(EscrowProgram::EscrowApprove, &data), None);
+    assert_eq!(decode_offer(EscrowProgram::Escrow, &data[1..]), None);
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+use solana_sdk::signature::Signer;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::UiTransactionEncoding;
+
+use practice_2::fees::{limit_with_margin, percentile, ComputeUnitLimit, PriorityFee};
+use practice_2::sol::send_sol_with_memo;
+use practice_2::{FeeConfig, TransactionConfig, TransactionOutcome};
+
+use common::{funded_keypair, localnet};
+
+#[test]
+fn parses_fee_flags() {
+    assert_eq!("none".parse::<PriorityFee>().unwrap(), PriorityFee::None);
+    assert_eq!(
+        "5000".parse::<PriorityFee>().unwrap(),
+        PriorityFee::Fixed(5000)
+    );
+    assert_eq!(
+        "p75".parse::<PriorityFee>().unwrap(),
+        PriorityFee::Percentile(75)
+    );
+    assert!("p101".parse::<PriorityFee>().is_err());
+    assert!("fast".parse::<PriorityFee>().is_err());
+
+    assert_eq!(
+        "auto".parse::<ComputeUnitLimit>().unwrap(),
+        ComputeUnitLimit::Simulate
+    );
+    assert_eq!(
+        "300".parse::<ComputeUnitLimit>().unwrap(),
+        ComputeUnitLimit::Fixed(300)
+    );
+    assert!("1400001".parse::<ComputeUnitLimit>().is_err());
+}
+
+#[test]
+fn percentile_and_margin() {
+    let fees = [10, 0, 30, 20];
+
+    assert_eq!(percentile(&fees, 0), 0);
+    assert_eq!(percentile(&fees, 50), 10);
+    assert_eq!(percentile(&fees, 75), 20);
+    assert_eq!(percentile(&fees, 100), 30);
+    assert_eq!(percentile(&[], 75), 0);
+
+    assert_eq!(limit_with_margin(1000, 10), 1100);
+    assert_eq!(limit_with_margin(1001, 10), 1102);
+    assert_eq!(limit_with_margin(2_000_000, 10), 1_400_000);
+}
+
+/// The compute budget instructions of the confirmed transaction and the
+/// compute units it used.
+fn compute_budget_of(
+    client: &solana_client::rpc_client::RpcClient,
+    outcome: &TransactionOutcome,
+) -> (Vec<ComputeBudgetInstruction>, u64) {
+    let TransactionOutcome::Confirmed(signature) = outcome else {
+        panic!("not sent: {outcome}");
+    };
+    let confirmed = client
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(client.commitment()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .unwrap();
+    let transaction = confirmed.transaction.transaction.decode().unwrap();
+    let keys = transaction.message.static_account_keys();
+
+    let instructions = transaction
+        .message
+        .instructions()
+        .iter()
+        .filter(|ix| keys[ix.program_id_index as usize] == compute_budget::id())
+        .map(|ix| solana_sdk::borsh1::try_from_slice_unchecked(&ix.data).unwrap())
+        .collect();
+    let OptionSerializer::Some(units) = confirmed.transaction.meta.unwrap().compute_units_consumed
+    else {
+        panic!("no compute units reported");
+    };
+
+    (instructions, units)
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn fixed_price_and_simulated_limit() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 1);
+    let config = TransactionConfig {
+        fees: FeeConfig {
+            priority_fee: PriorityFee::Fixed(1000),
+            compute_unit_limit: ComputeUnitLimit::Simulate,
+            ..FeeConfig::default()
+        },
+        ..TransactionConfig::default()
+    };
+
+    let outcome = send_sol_with_memo(
+        &client,
+        &payer,
+        &Pubkey::new_unique(),
+        1_000_000,
+        Some("priority"),
+        &config,
+    )
+    .unwrap();
+
+    let (instructions, units) = compute_budget_of(&client, &outcome);
+    let [ComputeBudgetInstruction::SetComputeUnitLimit(limit), ComputeBudgetInstruction::SetComputeUnitPrice(1000)] =
+        instructions[..]
+    else {
+        panic!("unexpected compute budget: {instructions:?}");
+    };
+    assert!(
+        u64::from(limit) >= units,
+        "limit {limit} below the {units} units used"
+    );
+    assert_eq!(limit, limit_with_margin(units, 10));
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn percentile_price_from_recent_fees() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 1);
+    let config = TransactionConfig {
+        fees: FeeConfig {
+            priority_fee: PriorityFee::Percentile(75),
+            ..FeeConfig::default()
+        },
+        ..TransactionConfig::default()
+    };
+
+    let outcome = send_sol_with_memo(&client, &payer, &payer.pubkey(), 1, None, &config).unwrap();
+
+    let (instructions, _) = compute_budget_of(&client, &outcome);
+    assert!(matches!(
+        instructions[..],
+        [ComputeBudgetInstruction::SetComputeUnitPrice(_)]
+    ));
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn default_adds_no_compute_budget() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 1);
+
+    let outcome = send_sol_with_memo(
+        &client,
+        &payer,
+        &payer.pubkey(),
+        1,
+        None,
+        &TransactionConfig::default(),
+    )
+    .unwrap();
+
+    assert!(compute_budget_of(&client, &outcome).0.is_empty());
+}
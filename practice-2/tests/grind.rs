@@ -0,0 +1,71 @@
+use solana_sdk::signature::Signer;
+
+use practice_2::grind::{GrindEvent, Grinder, VanityPattern};
+use practice_2::GrindError;
+
+const ADDRESS: &str = "5vftMkHL72JaJG6ExQfGAsT2uGVHpRR7oTNUPMs68Y2N";
+
+fn pattern(prefix: Option<&str>, suffix: Option<&str>, ignore_case: bool) -> VanityPattern {
+    VanityPattern::new(
+        prefix.map(String::from),
+        suffix.map(String::from),
+        ignore_case,
+    )
+    .unwrap()
+}
+
+#[test]
+fn matches_prefix_and_suffix() {
+    assert!(pattern(Some("5vft"), None, false).matches(ADDRESS));
+    assert!(pattern(None, Some("8Y2N"), false).matches(ADDRESS));
+    assert!(pattern(Some("5vft"), Some("Y2N"), false).matches(ADDRESS));
+
+    assert!(!pattern(Some("5vfT"), None, false).matches(ADDRESS));
+    assert!(!pattern(Some("5vft"), Some("Y2M"), false).matches(ADDRESS));
+    assert!(!pattern(Some(&format!("{ADDRESS}1")), None, false).matches(ADDRESS));
+}
+
+#[test]
+fn ignores_case_when_asked() {
+    assert!(pattern(Some("5VFTmk"), None, true).matches(ADDRESS));
+    assert!(pattern(None, Some("8y2n"), true).matches(ADDRESS));
+    assert!(!pattern(Some("5VFTmk"), None, false).matches(ADDRESS));
+}
+
+#[test]
+fn rejects_patterns_that_can_never_match() {
+    assert!(matches!(
+        VanityPattern::new(None, None, false),
+        Err(GrindError::EmptyPattern)
+    ));
+
+    for (prefix, ch) in [("S0L", '0'), ("Ol", 'O'), ("bIg", 'I'), ("so_", '_')] {
+        match VanityPattern::new(Some(prefix.to_string()), None, false) {
+            Err(GrindError::NotBase58 { ch: found, .. }) => assert_eq!(found, ch),
+            other => panic!("{prefix}: {other:?}"),
+        }
+    }
+
+    // `l` is not base58, but `L` is.
+    assert!(VanityPattern::new(Some("sol".to_string()), None, false).is_err());
+    assert!(VanityPattern::new(Some("sol".to_string()), None, true).is_ok());
+    // Neither `0` nor an upper case `o` is.
+    assert!(VanityPattern::new(None, Some("0".to_string()), true).is_err());
+}
+
+#[test]
+fn grinds_matching_keypairs() {
+    let grinder = Grinder::new(pattern(Some("A"), None, false)).threads(2);
+    let mut found = Vec::new();
+    grinder
+        .grind(2, |event| {
+            if let GrindEvent::Match(keypair) = event {
+                found.push(keypair.pubkey().to_string());
+            }
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(found.len(), 2);
+    assert!(found.iter().all(|address| address.starts_with('A')));
+}
@@ -0,0 +1,243 @@
+mod common;
+
+use solana_account_decoder::parse_token::UiTokenAmount;
+use solana_program::instruction::{AccountMeta, CompiledInstruction, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::message::Message;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError, VersionedTransaction};
+use solana_transaction_status::{
+    InnerInstruction, InnerInstructions, TransactionStatusMeta, TransactionTokenBalance,
+    UiTransactionStatusMeta,
+};
+
+use practice_2::history::{get_history, write_csv, HistoryConfig, HistoryEntry, TokenDelta};
+use practice_2::sol::{send_sol_with_memo, send_sol_with_memo_instructions};
+use practice_2::TransactionConfig;
+
+use common::{funded_keypair, localnet};
+
+const FEE: u64 = 5_000;
+
+fn token_balance(index: u8, mint: &Pubkey, owner: &Pubkey, amount: u64) -> TransactionTokenBalance {
+    TransactionTokenBalance {
+        account_index: index,
+        mint: mint.to_string(),
+        ui_token_amount: UiTokenAmount {
+            ui_amount: None,
+            decimals: 6,
+            amount: amount.to_string(),
+            ui_amount_string: String::new(),
+        },
+        owner: owner.to_string(),
+        program_id: spl_token::id().to_string(),
+    }
+}
+
+/// A transfer with a memo from `sender` to `recipient`, the memo program
+/// invoked once more from an inner instruction.
+fn transfer(sender: &Pubkey, recipient: &Pubkey) -> (VersionedTransaction, TransactionStatusMeta) {
+    let instructions = send_sol_with_memo_instructions(sender, recipient, 1_500, Some("rent"));
+    let message = Message::new(&instructions, Some(sender));
+    let memo_index = message
+        .account_keys
+        .iter()
+        .position(|key| *key == spl_memo::id())
+        .unwrap() as u8;
+    let transaction = VersionedTransaction::from(Transaction::new_unsigned(message));
+
+    let meta = TransactionStatusMeta {
+        fee: FEE,
+        pre_balances: vec![10_000, 0, 1, 1],
+        post_balances: vec![10_000 - 1_500 - FEE, 1_500, 1, 1],
+        inner_instructions: Some(vec![InnerInstructions {
+            index: 1,
+            instructions: vec![InnerInstruction {
+                instruction: CompiledInstruction::new_from_raw_parts(
+                    memo_index,
+                    b"inner".to_vec(),
+                    Vec::new(),
+                ),
+                stack_height: Some(2),
+            }],
+        }]),
+        ..TransactionStatusMeta::default()
+    };
+
+    (transaction, meta)
+}
+
+#[test]
+fn decodes_balance_changes_memos_and_programs() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let (transaction, meta) = transfer(&sender, &recipient);
+    let meta = UiTransactionStatusMeta::from(meta);
+
+    let sent = HistoryEntry::new(
+        &sender,
+        Signature::default(),
+        7,
+        Some(0),
+        &transaction,
+        &meta,
+    );
+    assert_eq!(sent.slot, 7);
+    assert_eq!(sent.fee_payer, sender);
+    assert_eq!(sent.sol_delta, -6_500);
+    assert_eq!(sent.memos, ["rent", "inner"]);
+    assert_eq!(
+        sent.programs,
+        [solana_program::system_program::id(), spl_memo::id()]
+    );
+    assert_eq!(sent.program_names(), ["System", "Memo"]);
+    assert_eq!(sent.transfer_recipient, Some(recipient));
+
+    let received = HistoryEntry::new(
+        &recipient,
+        Signature::default(),
+        7,
+        None,
+        &transaction,
+        &meta,
+    );
+    assert_eq!(received.sol_delta, 1_500);
+
+    let text = sent.to_string();
+    assert!(text.contains("1970-01-01 00:00:00"), "{text}");
+    assert!(text.contains("SOL: -0.0000065"), "{text}");
+    assert!(text.contains("memo: \"rent\""), "{text}");
+}
+
+#[test]
+fn keeps_token_changes_of_the_owner() {
+    let owner = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let accounts = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let instruction = Instruction::new_with_bytes(
+        spl_token::id(),
+        &[],
+        accounts
+            .iter()
+            .map(|account| AccountMeta::new(*account, false))
+            .collect(),
+    );
+    let message = Message::new(&[instruction], Some(&owner));
+    let transaction = VersionedTransaction::from(Transaction::new_unsigned(message));
+    // Tokens move from an account of `owner` (index 1) to one of `other`
+    // (index 2); the other account of `owner` (index 3) is unchanged.
+    let meta = UiTransactionStatusMeta::from(TransactionStatusMeta {
+        pre_token_balances: Some(vec![
+            token_balance(1, &mint, &owner, 5_000_000),
+            token_balance(3, &mint, &owner, 1),
+        ]),
+        post_token_balances: Some(vec![
+            token_balance(1, &mint, &owner, 2_500_000),
+            token_balance(2, &mint, &other, 2_500_000),
+            token_balance(3, &mint, &owner, 1),
+        ]),
+        ..TransactionStatusMeta::default()
+    });
+
+    let entry = HistoryEntry::new(&owner, Signature::default(), 1, None, &transaction, &meta);
+    assert_eq!(
+        entry.token_deltas,
+        [TokenDelta {
+            account: accounts[0],
+            mint,
+            decimals: 6,
+            delta: -2_500_000,
+        }]
+    );
+    assert!(entry
+        .to_string()
+        .contains(&format!("tokens: -2.5 of {mint}")));
+
+    let entry = HistoryEntry::new(&other, Signature::default(), 1, None, &transaction, &meta);
+    assert_eq!(entry.token_deltas[0].delta, 2_500_000);
+    assert_eq!(entry.token_deltas[0].account, accounts[1]);
+}
+
+#[test]
+fn exports_csv() {
+    let sender = Pubkey::new_unique();
+    let (transaction, mut meta) = transfer(&sender, &Pubkey::new_unique());
+    meta.status = Err(TransactionError::InstructionError(
+        0,
+        InstructionError::InsufficientFunds,
+    ));
+    let meta = UiTransactionStatusMeta::from(meta);
+    let entry = HistoryEntry::new(
+        &sender,
+        Signature::default(),
+        3,
+        Some(86_400),
+        &transaction,
+        &meta,
+    );
+
+    let mut csv = Vec::new();
+    write_csv(&[entry], &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<_> = csv.lines().collect();
+
+    assert_eq!(
+        lines[0],
+        "signature,slot,time,status,error,fee,sol_delta,token_deltas,memos,programs"
+    );
+    assert!(
+        lines[1].starts_with(&format!(
+            "{},3,1970-01-02 00:00:00,failed,",
+            Signature::default()
+        )),
+        "{}",
+        lines[1]
+    );
+    assert!(
+        lines[1].ends_with(",5000,-0.0000065,,rent;inner,System;Memo"),
+        "{}",
+        lines[1]
+    );
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn history_of_a_transfer_with_a_memo() {
+    let client = localnet();
+    let sender = funded_keypair(&client, 1);
+    let recipient = Keypair::new();
+
+    send_sol_with_memo(
+        &client,
+        &sender,
+        &recipient.pubkey(),
+        1_000_000,
+        Some("history"),
+        &TransactionConfig::default(),
+    )
+    .unwrap();
+
+    let history = get_history(&client, &recipient.pubkey(), &HistoryConfig::default()).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].sol_delta, 1_000_000);
+    assert_eq!(history[0].memos, ["history"]);
+    assert_eq!(history[0].fee_payer, sender.pubkey());
+
+    let page = get_history(
+        &client,
+        &sender.pubkey(),
+        &HistoryConfig {
+            limit: 1,
+            ..HistoryConfig::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].signature, history[0].signature);
+}
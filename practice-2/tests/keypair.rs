@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use solana_sdk::signature::{Keypair, Signer};
+
+use practice_2::keypair::{write_keypair_file, KEYPAIR_VAR, SECRET_KEY_VAR};
+use practice_2::{KeypairError, KeypairSource};
+
+#[test]
+fn parses_keypair_sources() {
+    let parse = |s: &str| s.parse::<KeypairSource>().unwrap();
+
+    assert_eq!(parse("-"), KeypairSource::Stdin);
+    assert_eq!(parse("stdin"), KeypairSource::Stdin);
+    assert_eq!(parse("cli-config"), KeypairSource::CliConfig);
+    assert_eq!(
+        parse("env:BOB_SECRET_KEY"),
+        KeypairSource::Env("BOB_SECRET_KEY".to_string())
+    );
+    assert_eq!(
+        parse("keys/alice.json"),
+        KeypairSource::File(PathBuf::from("keys/alice.json"))
+    );
+    assert!(matches!(
+        "env:".parse::<KeypairSource>(),
+        Err(KeypairError::EmptyVarName(_))
+    ));
+}
+
+// Discovery reads the process environment and the current directory, so
+// every step runs in this one test.
+#[test]
+fn discovers_keypair_variable_then_secret_key_then_cli_config() {
+    let dir = std::env::temp_dir().join(format!("keypair-discover-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // Away from the `.env` of the package.
+    std::env::set_current_dir(&dir).unwrap();
+    std::env::remove_var(KEYPAIR_VAR);
+    std::env::remove_var(SECRET_KEY_VAR);
+
+    assert_eq!(KeypairSource::discover().unwrap(), KeypairSource::CliConfig);
+
+    std::env::set_var(SECRET_KEY_VAR, "[]");
+    assert_eq!(
+        KeypairSource::discover().unwrap(),
+        KeypairSource::Env(SECRET_KEY_VAR.to_string())
+    );
+
+    std::env::set_var(KEYPAIR_VAR, "env:BOB_SECRET_KEY");
+    assert_eq!(
+        KeypairSource::discover().unwrap(),
+        KeypairSource::Env("BOB_SECRET_KEY".to_string())
+    );
+
+    std::env::set_var(KEYPAIR_VAR, "env:");
+    assert!(matches!(
+        KeypairSource::discover(),
+        Err(KeypairError::EmptyVarName(_))
+    ));
+
+    std::env::remove_var(KEYPAIR_VAR);
+    std::env::remove_var(SECRET_KEY_VAR);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn loads_keypair_files_and_rejects_malformed_ones() {
+    let keypair = Keypair::new();
+    let path = std::env::temp_dir().join(format!("keypair-{}.json", keypair.pubkey()));
+
+    write_keypair_file(&keypair, &path).unwrap();
+    let loaded = KeypairSource::File(path.clone()).load().unwrap();
+    assert_eq!(loaded.pubkey(), keypair.pubkey());
+
+    std::fs::write(&path, "[1, 2, 3").unwrap();
+    assert!(matches!(
+        KeypairSource::File(path.clone()).load(),
+        Err(KeypairError::Format { .. })
+    ));
+
+    std::fs::write(&path, "[1, 2, 3]").unwrap();
+    assert!(matches!(
+        KeypairSource::File(path.clone()).load(),
+        Err(KeypairError::Length { len: 3, .. })
+    ));
+
+    std::fs::remove_file(path).unwrap();
+    assert!(matches!(
+        KeypairSource::File(PathBuf::from("no/such/keypair.json")).load(),
+        Err(KeypairError::Io { .. })
+    ));
+}
@@ -0,0 +1,51 @@
+use solana_sdk::signature::{Keypair, Signer};
+
+use practice_2::{Keystore, KeystoreError};
+
+#[test]
+fn decrypts_only_with_the_password() {
+    let keypair = Keypair::new();
+    let keystore = Keystore::encrypt(&keypair, "correct horse").unwrap();
+    assert_eq!(keystore.pubkey, keypair.pubkey().to_string());
+
+    let json = serde_json::to_string(&keystore).unwrap();
+    assert!(Keystore::detect(&json));
+    assert!(!json.contains(&keypair.to_base58_string()));
+
+    let decrypted = Keystore::from_json(&json)
+        .unwrap()
+        .decrypt("correct horse")
+        .unwrap();
+    assert_eq!(decrypted.to_bytes(), keypair.to_bytes());
+
+    assert!(matches!(
+        keystore.decrypt("battery staple"),
+        Err(KeystoreError::Decrypt)
+    ));
+}
+
+#[test]
+fn replaces_the_file_readable_only_by_its_owner() {
+    let keypair = Keypair::new();
+    let path = std::env::temp_dir().join(format!("keystore-{}.json", keypair.pubkey()));
+    std::fs::write(&path, "old contents").unwrap();
+
+    let keystore = Keystore::encrypt(&keypair, "password").unwrap();
+    keystore.write(&path).unwrap();
+
+    assert_eq!(
+        Keystore::read(&path).unwrap().ciphertext,
+        keystore.ciphertext
+    );
+    let mut temporary = path.clone().into_os_string();
+    temporary.push(".tmp");
+    assert!(!std::path::Path::new(&temporary).exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    std::fs::remove_file(path).unwrap();
+}
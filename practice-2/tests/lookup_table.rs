@@ -0,0 +1,170 @@
+mod common;
+
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::transfer;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::{Keypair, Signer};
+use spl_associated_token_account::get_associated_token_address;
+
+use practice_2::lookup_table::{
+    create_lookup_table, deactivate_lookup_table, extend_lookup_table, get_lookup_table_info,
+    wait_for_lookup_table, LookupTableError, EXTEND_CHUNK,
+};
+use practice_2::offline::{add_signatures, ensure_fully_signed, missing_signers, read_transaction};
+use practice_2::token::{create_token_mint, mint_tokens_batch};
+use practice_2::transaction::{process_transaction, MessageVersion};
+use practice_2::{BatchError, Error, SignerSource, TransactionConfig, TransactionOutcome};
+
+use common::{funded_keypair, localnet};
+
+/// A v0 transaction signed in two steps, without a cluster.
+#[test]
+fn v0_transaction_signed_offline() {
+    let client = RpcClient::new("http://127.0.0.1:1".to_string());
+    let payer = Keypair::new();
+    let sender = Keypair::new();
+    let path = std::env::temp_dir().join(format!("v0-{}.tx", payer.pubkey()));
+    let config = TransactionConfig {
+        blockhash: Some(Hash::new_unique()),
+        sign_only: Some(path.clone()),
+        message_version: MessageVersion::V0,
+        ..TransactionConfig::default()
+    };
+    let placeholder = payer.pubkey().to_string().parse::<SignerSource>().unwrap();
+    let placeholder = placeholder.signer().unwrap();
+
+    let ix = transfer(&sender.pubkey(), &Pubkey::new_unique(), 1);
+    let outcome =
+        process_transaction(&client, &[ix], &[placeholder.as_ref(), &sender], &config).unwrap();
+    assert_eq!(
+        outcome,
+        TransactionOutcome::SignedOnly {
+            path: path.clone(),
+            missing: vec![payer.pubkey()],
+        }
+    );
+
+    let mut transaction = read_transaction(&path).unwrap();
+    assert!(transaction.message.address_table_lookups().is_some());
+    add_signatures(&mut transaction, &[&payer]).unwrap();
+
+    assert!(missing_signers(&transaction).is_empty());
+    ensure_fully_signed(&transaction).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn refuses_to_sign_only_several_extend_transactions() {
+    let client = RpcClient::new("http://127.0.0.1:1".to_string());
+    let payer = Keypair::new();
+    let config = TransactionConfig {
+        blockhash: Some(Hash::new_unique()),
+        sign_only: Some(std::env::temp_dir().join(format!("extend-{}.tx", payer.pubkey()))),
+        ..TransactionConfig::default()
+    };
+    let addresses: Vec<Pubkey> = (0..=EXTEND_CHUNK).map(|_| Pubkey::new_unique()).collect();
+
+    let result = extend_lookup_table(
+        &client,
+        &payer,
+        &Pubkey::new_unique(),
+        &payer,
+        &addresses,
+        &config,
+    );
+    assert!(matches!(
+        result,
+        Err(Error::LookupTable(LookupTableError::SignOnlyChunks(21)))
+    ));
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn lookup_table_lifecycle() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 1);
+    let config = TransactionConfig::default();
+    let recipients: Vec<Pubkey> = (0..30).map(|_| Pubkey::new_unique()).collect();
+
+    let (table, _) = create_lookup_table(&client, &payer, &payer.pubkey(), &config).unwrap();
+    extend_lookup_table(&client, &payer, &table, &payer, &recipients, &config).unwrap();
+    wait_for_lookup_table(&client, &table).unwrap();
+
+    let info = get_lookup_table_info(&client, &table).unwrap();
+    assert_eq!(info.authority, Some(payer.pubkey()));
+    assert_eq!(info.addresses, recipients);
+    assert_eq!(info.deactivation_slot, None);
+
+    // 30 transfers are too many for a legacy message.
+    let rent = client.get_minimum_balance_for_rent_exemption(0).unwrap();
+    let instructions: Vec<_> = recipients
+        .iter()
+        .map(|recipient| transfer(&payer.pubkey(), recipient, rent))
+        .collect();
+    let with_table = TransactionConfig {
+        lookup_tables: vec![table],
+        ..TransactionConfig::default()
+    };
+    process_transaction(&client, &instructions, &[&payer], &with_table).unwrap();
+    for recipient in &recipients {
+        assert_eq!(client.get_balance(recipient).unwrap(), rent);
+    }
+
+    deactivate_lookup_table(&client, &payer, &table, &payer, &config).unwrap();
+    let info = get_lookup_table_info(&client, &table).unwrap();
+    assert!(info.deactivation_slot.is_some());
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn batch_mint_uses_a_lookup_table_when_needed() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 2);
+    let mint = Keypair::new();
+    let config = TransactionConfig::default();
+    create_token_mint(&client, &payer, &mint, 0, &config).unwrap();
+
+    let few: Vec<(Pubkey, u64)> = (1..=2)
+        .map(|amount| (Pubkey::new_unique(), amount))
+        .collect();
+    let batch = mint_tokens_batch(&client, &mint.pubkey(), &payer, &few, &config).unwrap();
+    assert_eq!(batch.lookup_table, None);
+    assert_eq!(batch.outcomes.len(), 1);
+
+    let many: Vec<(Pubkey, u64)> = (1..=40)
+        .map(|amount| (Pubkey::new_unique(), amount))
+        .collect();
+    let batch = mint_tokens_batch(&client, &mint.pubkey(), &payer, &many, &config).unwrap();
+    assert!(batch.lookup_table.is_some());
+    for (owner, amount) in &many {
+        let ata = get_associated_token_address(owner, &mint.pubkey());
+        let balance = client.get_token_account_balance(&ata).unwrap();
+        assert_eq!(balance.amount, amount.to_string());
+    }
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn failed_batch_mint_reports_its_lookup_table() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 2);
+    let mint = Keypair::new();
+    let config = TransactionConfig::default();
+    create_token_mint(&client, &payer, &mint, 0, &config).unwrap();
+
+    // The supply overflows in the last transaction only.
+    let mut recipients: Vec<(Pubkey, u64)> = (0..40).map(|_| (Pubkey::new_unique(), 1)).collect();
+    recipients.push((Pubkey::new_unique(), u64::MAX));
+
+    match mint_tokens_batch(&client, &mint.pubkey(), &payer, &recipients, &config) {
+        Err(Error::Batch(BatchError {
+            lookup_table, sent, ..
+        })) => {
+            assert!(!sent.is_empty());
+            let info = get_lookup_table_info(&client, &lookup_table).unwrap();
+            assert_eq!(info.authority, Some(payer.pubkey()));
+        }
+        other => panic!("{other:?}"),
+    }
+}
@@ -0,0 +1,62 @@
+use solana_sdk::signature::Signer;
+
+use practice_2::mnemonic::{format_path, parse_mnemonic, DerivationTemplate, SeedWallet};
+use practice_2::MnemonicError;
+
+/// Seed phrase of the Solana Cookbook example on restoring BIP44 keypairs.
+const PHRASE: &str = "neither lonely flavor argue grass remind eye tag avocado spot unusual intact";
+
+#[test]
+fn derives_the_addresses_wallets_derive() {
+    let wallet = SeedWallet::new(&parse_mnemonic(PHRASE).unwrap(), "");
+
+    let accounts: Vec<_> = wallet
+        .accounts(&DerivationTemplate::default(), 2)
+        .unwrap()
+        .iter()
+        .map(|(path, keypair)| (format_path(path), keypair.pubkey().to_string()))
+        .collect();
+    assert_eq!(
+        accounts,
+        [
+            (
+                "m/44'/501'/0'/0'".to_string(),
+                "5vftMkHL72JaJG6ExQfGAsT2uGVHpRR7oTNUPMs68Y2N".to_string()
+            ),
+            (
+                "m/44'/501'/1'/0'".to_string(),
+                "GcXbfQ5yY3uxCyBNDPBbR5FjumHf89E7YHXuULfGDBBv".to_string()
+            ),
+        ]
+    );
+
+    // A passphrase is a different wallet.
+    let other = SeedWallet::new(&parse_mnemonic(PHRASE).unwrap(), "passphrase");
+    let path = DerivationTemplate::default().path(0).unwrap();
+    assert_ne!(
+        other.keypair(&path).unwrap().pubkey(),
+        wallet.keypair(&path).unwrap().pubkey()
+    );
+}
+
+#[test]
+fn puts_the_account_index_for_n() {
+    let template: DerivationTemplate = "m/44'/501'/0'/n'".parse().unwrap();
+    assert!(template.has_account_index());
+    assert_eq!(format_path(&template.path(7).unwrap()), "m/44'/501'/0'/7'");
+    assert_eq!(
+        format_path(&DerivationTemplate::default().path(12).unwrap()),
+        "m/44'/501'/12'/0'"
+    );
+
+    let fixed: DerivationTemplate = "m/44'/501'".parse().unwrap();
+    assert!(!fixed.has_account_index());
+    let wallet = SeedWallet::new(&parse_mnemonic(PHRASE).unwrap(), "");
+    assert_eq!(wallet.accounts(&fixed, 1).unwrap().len(), 1);
+    assert!(matches!(
+        wallet.accounts(&fixed, 2),
+        Err(MnemonicError::NoAccountIndex(_))
+    ));
+
+    assert!("m/44'/501'/x'".parse::<DerivationTemplate>().is_err());
+}
@@ -0,0 +1,30 @@
+use std::future::Future;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+
+use practice_2::nonblocking::{create_token_mint, mint_tokens, send_sol_with_memo};
+use practice_2::{SignerSource, TransactionConfig};
+
+fn assert_send<F: Future + Send>(_: F) {}
+
+/// The async helpers have to be spawnable on a multi-threaded runtime,
+/// also with signers opened from a [`SignerSource`].
+#[test]
+fn async_helpers_are_send() {
+    let client = RpcClient::new("http://127.0.0.1:8899".to_string());
+    let config = TransactionConfig::default();
+    let keypair = Keypair::new();
+    let signer = Pubkey::new_unique()
+        .to_string()
+        .parse::<SignerSource>()
+        .unwrap()
+        .signer()
+        .unwrap();
+    let mint = Pubkey::new_unique();
+
+    assert_send(send_sol_with_memo(&client, signer.as_ref(), &mint, 1, Some("memo"), &config));
+    assert_send(create_token_mint(&client, &keypair, signer.as_ref(), 9, &config));
+    assert_send(mint_tokens(&client, &mint, &keypair, &mint, 1, &config));
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::transfer;
+use solana_sdk::signature::{Keypair, Signer};
+
+use practice_2::nonce::{
+    advance_nonce_account, create_nonce_account, get_nonce_info, nonce_rent,
+    withdraw_nonce_account,
+};
+use practice_2::transaction::{process_transaction, NonceConfig};
+use practice_2::{TransactionConfig, TransactionOutcome};
+
+use common::{funded_keypair, localnet};
+
+#[test]
+#[ignore = "needs a local validator"]
+fn nonce_account_lifecycle() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 2);
+    let nonce = Keypair::new();
+    let rent = nonce_rent(&client).unwrap();
+
+    create_nonce_account(
+        &client,
+        &payer,
+        &nonce,
+        &payer.pubkey(),
+        rent,
+        &TransactionConfig::default(),
+    )
+    .unwrap();
+
+    let created = get_nonce_info(&client, &nonce.pubkey()).unwrap();
+    assert_eq!(created.authority, payer.pubkey());
+    assert_eq!(created.balance, rent);
+
+    // The stored nonce only changes once a new blockhash is available.
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    advance_nonce_account(
+        &client,
+        &payer,
+        &nonce.pubkey(),
+        &payer,
+        &TransactionConfig::default(),
+    )
+    .unwrap();
+    let advanced = get_nonce_info(&client, &nonce.pubkey()).unwrap();
+    assert_ne!(advanced.blockhash, created.blockhash);
+
+    let recipient = Pubkey::new_unique();
+    withdraw_nonce_account(
+        &client,
+        &payer,
+        &nonce.pubkey(),
+        &payer,
+        &recipient,
+        rent,
+        &TransactionConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(client.get_balance(&recipient).unwrap(), rent);
+    assert!(get_nonce_info(&client, &nonce.pubkey()).is_err());
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn nonce_mode_advances_nonce_first() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 2);
+    let nonce = Keypair::new();
+
+    create_nonce_account(
+        &client,
+        &payer,
+        &nonce,
+        &payer.pubkey(),
+        nonce_rent(&client).unwrap(),
+        &TransactionConfig::default(),
+    )
+    .unwrap();
+    let before = get_nonce_info(&client, &nonce.pubkey()).unwrap();
+
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    let config = TransactionConfig {
+        nonce: Some(NonceConfig {
+            account: nonce.pubkey(),
+            authority: None,
+        }),
+        ..TransactionConfig::default()
+    };
+    let recipient = Pubkey::new_unique();
+    let outcome = process_transaction(
+        &client,
+        &[transfer(&payer.pubkey(), &recipient, 1_000_000)],
+        &[&payer],
+        &config,
+    )
+    .unwrap();
+
+    assert!(matches!(outcome, TransactionOutcome::Confirmed(_)));
+
+    assert_eq!(client.get_balance(&recipient).unwrap(), 1_000_000);
+    let after = get_nonce_info(&client, &nonce.pubkey()).unwrap();
+    assert_ne!(after.blockhash, before.blockhash);
+}
@@ -0,0 +1,47 @@
+use solana_program::message::Message;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::transfer;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+use practice_2::offline::{decode_transaction, encode_transaction, missing_signers};
+use practice_2::OfflineError;
+
+fn unsigned() -> VersionedTransaction {
+    let payer = Pubkey::new_unique();
+    let message = Message::new(&[transfer(&payer, &Pubkey::new_unique(), 1)], Some(&payer));
+
+    Transaction::new_unsigned(message).into()
+}
+
+#[test]
+fn round_trips_unsigned_transactions() {
+    let transaction = unsigned();
+
+    let decoded = decode_transaction(&encode_transaction(&transaction).unwrap()).unwrap();
+    assert_eq!(decoded, transaction);
+    assert_eq!(
+        missing_signers(&decoded),
+        [decoded.message.static_account_keys()[0]]
+    );
+}
+
+#[test]
+fn rejects_transactions_inconsistent_with_their_message() {
+    let mut no_signatures = unsigned();
+    no_signatures.signatures.clear();
+
+    let mut no_signers = unsigned();
+    no_signers.signatures.clear();
+    let VersionedMessage::Legacy(message) = &mut no_signers.message else {
+        unreachable!("built as legacy");
+    };
+    message.header.num_required_signatures = 0;
+
+    for transaction in [no_signatures, no_signers] {
+        assert!(matches!(
+            decode_transaction(&encode_transaction(&transaction).unwrap()),
+            Err(OfflineError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,216 @@
+mod common;
+
+use solana_client::rpc_client::RpcClient;
+use solana_program::hash::Hash;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+
+use practice_2::payout::{
+    read_payouts, send_payouts, Payout, PayoutState, PayoutStatus, SentTransaction, Tally,
+};
+use practice_2::report::PayoutFileReport;
+use practice_2::{Error, PayoutError, TransactionConfig};
+
+use common::{funded_keypair, localnet};
+
+fn payouts(recipients: &[Pubkey]) -> Vec<Payout> {
+    recipients
+        .iter()
+        .enumerate()
+        .map(|(index, recipient)| Payout {
+            line: index as u64 + 2,
+            recipient: *recipient,
+            lamports: 1_000_000 * (index as u64 + 1),
+            memo: (index == 0).then(|| "first".to_string()),
+        })
+        .collect()
+}
+
+#[test]
+fn reads_rows_with_optional_memos() {
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let csv = format!("recipient,amount,memo\n{first}, 1.5 ,rent\n{second},0.000000001\n");
+
+    assert_eq!(
+        read_payouts(csv.as_bytes()).unwrap(),
+        [
+            Payout {
+                line: 2,
+                recipient: first,
+                lamports: 1_500_000_000,
+                memo: Some("rent".to_string()),
+            },
+            Payout {
+                line: 3,
+                recipient: second,
+                lamports: 1,
+                memo: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn rejects_invalid_rows_with_their_line() {
+    let recipient = Pubkey::new_unique();
+    for (row, expected) in [
+        ("nobody,1".to_string(), "invalid recipient"),
+        (format!("{recipient},0.0000000001"), "decimal places"),
+        (format!("{recipient},0"), "must be positive"),
+        (format!("{recipient},18446744073"), "too large"),
+    ] {
+        let csv = format!("recipient,amount\n{recipient},1\n{row}\n");
+        match read_payouts(csv.as_bytes()) {
+            Err(Error::Payout(PayoutError::InvalidRow { line, message })) => {
+                assert_eq!(line, 3, "{row}");
+                assert!(message.contains(expected), "{row}: {message}");
+            }
+            other => panic!("{row}: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn state_resumes_only_for_the_same_payouts() {
+    let recipients = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let payouts = payouts(&recipients);
+    let path = std::env::temp_dir().join(format!("payout-{}.json", recipients[0]));
+
+    let mut state = PayoutState::load(&path, &payouts).unwrap();
+    assert_eq!(state, PayoutState::new(&payouts));
+    state.payouts[0].status = PayoutStatus::Paid;
+    state.payouts[0].signature = Some("paid".to_string());
+    state.write(&path).unwrap();
+
+    let resumed = PayoutState::load(&path, &payouts).unwrap();
+    assert_eq!(resumed, state);
+    assert_eq!(resumed.unsent(), [1]);
+
+    let mut changed = payouts.clone();
+    changed[1].lamports += 1;
+    assert!(matches!(
+        PayoutState::load(&path, &changed),
+        Err(Error::Payout(PayoutError::StateMismatch { row: 2 }))
+    ));
+    assert!(matches!(
+        PayoutState::load(&path, &payouts[..1]),
+        Err(Error::Payout(PayoutError::StateMismatch { row: 2 }))
+    ));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn reports_what_was_not_paid() {
+    let recipients = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let mut state = PayoutState::new(&payouts(&recipients));
+    state.payouts[0].status = PayoutStatus::Paid;
+    state.payouts[0].signature = Some("one".to_string());
+    state.payouts[1].status = PayoutStatus::Sent;
+    state.payouts[1].transactions.push(SentTransaction {
+        signature: "two".to_string(),
+        blockhash: Hash::new_unique().to_string(),
+    });
+    state.payouts[1].error = Some("timed out".to_string());
+
+    let report = state.report();
+    assert_eq!(
+        report.requested,
+        Tally {
+            count: 3,
+            lamports: 6_000_000
+        }
+    );
+    assert_eq!(
+        report.paid,
+        Tally {
+            count: 1,
+            lamports: 1_000_000
+        }
+    );
+    assert_eq!(report.unknown.count, 1);
+    assert_eq!(report.pending.count, 1);
+    assert_eq!(report.transactions, 1);
+    assert!(!report.is_complete());
+
+    let text = report.to_string();
+    assert!(
+        text.contains("Paid: 1 payout, 0.001 SOL in 1 transactions"),
+        "{text}"
+    );
+    assert!(
+        text.contains(&format!(
+            "line 3: 0.002 SOL to {}, not known yet: timed out",
+            recipients[1]
+        )),
+        "{text}"
+    );
+    assert!(text.contains("line 4: 0.003 SOL"), "{text}");
+
+    let json = serde_json::to_value(PayoutFileReport::new("sol payout", &report)).unwrap();
+    assert_eq!(json["status"], "incomplete");
+    assert_eq!(json["paid"]["lamports"], 1_000_000);
+    assert_eq!(json["unpaid"][0]["line"], 3);
+    assert_eq!(json["unpaid"][0]["status"], "sent");
+    assert_eq!(json["unpaid"][0]["error"], "timed out");
+}
+
+#[test]
+fn refuses_sign_only_payouts() {
+    let client = RpcClient::new("http://127.0.0.1:1".to_string());
+    let payer = Keypair::new();
+    let config = TransactionConfig {
+        sign_only: Some(std::env::temp_dir().join("payout.tx")),
+        ..TransactionConfig::default()
+    };
+
+    let result = send_payouts(
+        &client,
+        &payer,
+        &payouts(&[Pubkey::new_unique()]),
+        &std::env::temp_dir().join(format!("payout-{}.json", payer.pubkey())),
+        2,
+        &config,
+        |_| {},
+    );
+    assert!(matches!(
+        result,
+        Err(Error::Payout(PayoutError::Unsupported))
+    ));
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn pays_everyone_once() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 2);
+    let recipients: Vec<_> = (0..30).map(|_| Keypair::new().pubkey()).collect();
+    let payouts = payouts(&recipients);
+    let path = std::env::temp_dir().join(format!("payout-{}.json", payer.pubkey()));
+    let config = TransactionConfig::default();
+
+    let (report, _) = send_payouts(&client, &payer, &payouts, &path, 4, &config, |_| {}).unwrap();
+    assert!(report.is_complete(), "{report}");
+    assert!(report.transactions > 1 && report.transactions < payouts.len());
+
+    // Resuming a finished run sends nothing.
+    let balance = client.get_balance(&payer.pubkey()).unwrap();
+    let (report, _) = send_payouts(&client, &payer, &payouts, &path, 4, &config, |_| {}).unwrap();
+    assert!(report.is_complete());
+    assert_eq!(client.get_balance(&payer.pubkey()).unwrap(), balance);
+
+    for payout in &payouts {
+        assert_eq!(
+            client.get_balance(&payout.recipient).unwrap(),
+            payout.lamports
+        );
+    }
+    assert!(balance < 2 * LAMPORTS_PER_SOL);
+
+    std::fs::remove_file(path).unwrap();
+}
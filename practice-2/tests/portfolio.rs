@@ -0,0 +1,241 @@
+mod common;
+
+use std::collections::HashMap;
+
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use solana_sdk::signature::{Keypair, Signer};
+use spl_token_2022::extension::transfer_fee::TransferFeeAmount;
+use spl_token_2022::extension::{
+    BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+};
+use spl_token_2022::state::AccountState;
+
+use practice_2::metadata::{create_token_metadata, metadata_address, TokenMetadata};
+use practice_2::portfolio::{get_portfolio, related_addresses, Delegation, Portfolio};
+use practice_2::token::{create_associated_token_account, create_token_mint, mint_tokens};
+use practice_2::TransactionConfig;
+
+use common::{funded_keypair, localnet};
+
+const RENT: u64 = 2_039_280;
+
+fn token_account(owner: &Pubkey, mint: &Pubkey, amount: u64) -> spl_token::state::Account {
+    spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }
+}
+
+fn packed<T: Pack>(state: T, program: Pubkey) -> Account {
+    let mut data = vec![0; T::LEN];
+    state.pack_into_slice(&mut data);
+
+    Account {
+        lamports: RENT,
+        data,
+        owner: program,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn mint_account(decimals: u8) -> Account {
+    let mint = spl_token::state::Mint {
+        decimals,
+        is_initialized: true,
+        ..spl_token::state::Mint::default()
+    };
+
+    packed(mint, spl_token::id())
+}
+
+/// Metadata account data as the Token Metadata program writes it, with the
+/// name and symbol padded with zeros as in older accounts.
+fn metadata_account(mint: &Pubkey, name: &str, symbol: &str) -> Account {
+    let string = |data: &mut Vec<u8>, value: &str, len: usize| {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.resize(len, 0);
+        data.extend((bytes.len() as u32).to_le_bytes());
+        data.extend(bytes);
+    };
+    let mut data = vec![4];
+    data.extend(Pubkey::new_unique().to_bytes());
+    data.extend(mint.to_bytes());
+    string(&mut data, name, 32);
+    string(&mut data, symbol, 10);
+    string(&mut data, "https://arweave.net/1234", 200);
+    data.extend([0, 0]); // seller fee basis points
+    data.extend([0, 0, 1]); // no creators, primary sale, mutable
+    data.extend([0; 6]); // no edition nonce, standard, collection, uses...
+    data.resize(679, 0);
+
+    Account {
+        lamports: 5_616_720,
+        data,
+        owner: mpl_token_metadata::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+#[test]
+fn lists_holdings_with_metadata_and_flags() {
+    let owner = Pubkey::new_unique();
+    let (named, plain, fee_mint) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let delegate = Pubkey::new_unique();
+
+    let holding = Pubkey::new_unique();
+    let mut delegated = token_account(&owner, &named, 1_500);
+    delegated.delegate = COption::Some(delegate);
+    delegated.delegated_amount = 500;
+    let empty = Pubkey::new_unique();
+    let frozen = Pubkey::new_unique();
+    let mut frozen_state = token_account(&owner, &plain, 0);
+    frozen_state.state = spl_token::state::AccountState::Frozen;
+
+    // A Token-2022 account with withheld transfer fees cannot be closed.
+    let with_fees = Pubkey::new_unique();
+    let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&[
+        ExtensionType::TransferFeeAmount,
+    ])
+    .unwrap();
+    let mut data = vec![0; len];
+    let mut state =
+        StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack_uninitialized(&mut data)
+            .unwrap();
+    state.base = spl_token_2022::state::Account {
+        mint: fee_mint,
+        owner,
+        state: AccountState::Initialized,
+        ..spl_token_2022::state::Account::default()
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    state
+        .init_extension::<TransferFeeAmount>(true)
+        .unwrap()
+        .withheld_amount = 7.into();
+
+    let token_accounts = vec![
+        (
+            empty,
+            packed(token_account(&owner, &plain, 0), spl_token::id()),
+        ),
+        (holding, packed(delegated, spl_token::id())),
+        (frozen, packed(frozen_state, spl_token::id())),
+        (
+            with_fees,
+            Account {
+                lamports: RENT,
+                data,
+                owner: spl_token_2022::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        ),
+    ];
+    assert_eq!(related_addresses(&token_accounts).len(), 6);
+
+    let accounts = HashMap::from([
+        (named, mint_account(2)),
+        (plain, mint_account(0)),
+        (
+            metadata_address(&named),
+            metadata_account(&named, "NAZAR", "DEMCHUK"),
+        ),
+    ]);
+    let portfolio = Portfolio::new(owner, 1_500_000_000, &token_accounts, &accounts);
+
+    let first = &portfolio.tokens[0];
+    assert_eq!(first.address, holding);
+    assert_eq!(first.ui_amount(), "15");
+    assert_eq!(
+        first.metadata,
+        Some(TokenMetadata {
+            name: "NAZAR".to_string(),
+            symbol: "DEMCHUK".to_string(),
+            uri: "https://arweave.net/1234".to_string(),
+        })
+    );
+    assert_eq!(
+        first.delegation,
+        Some(Delegation {
+            delegate,
+            amount: 500
+        })
+    );
+
+    let by_address = |address| {
+        portfolio
+            .tokens
+            .iter()
+            .find(|holding| holding.address == address)
+            .unwrap()
+    };
+    assert!(by_address(frozen).frozen);
+    assert!(!by_address(frozen).is_closable());
+    assert_eq!(by_address(with_fees).withheld_fees, 7);
+    assert_eq!(by_address(with_fees).decimals, None);
+    assert!(!by_address(with_fees).is_closable());
+
+    let closable: Vec<_> = portfolio
+        .closable()
+        .map(|holding| holding.address)
+        .collect();
+    assert_eq!(closable, vec![empty]);
+    assert_eq!(portfolio.reclaimable_rent(), RENT);
+
+    let text = portfolio.to_string();
+    assert!(text.contains("Owner"));
+    assert!(text.contains("1.5 SOL"));
+    assert!(text.contains("15 DEMCHUK (NAZAR)"));
+    assert!(text.contains("5 delegated to"));
+    assert!(text.contains("can be closed: 1"));
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn portfolio_of_a_new_wallet() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 1);
+    let config = TransactionConfig::default();
+    let mint = Keypair::new();
+    create_token_mint(&client, &payer, &mint, 6, &config).unwrap();
+    create_associated_token_account(&client, &mint.pubkey(), &payer.pubkey(), &payer, &config)
+        .unwrap();
+    create_token_metadata(
+        &client,
+        &mint.pubkey(),
+        &payer,
+        TokenMetadata {
+            name: "NAZAR".to_string(),
+            symbol: "DEMCHUK".to_string(),
+            uri: "https://arweave.net/1234".to_string(),
+        },
+        true,
+        &config,
+    )
+    .unwrap();
+
+    let portfolio = get_portfolio(&client, &payer.pubkey()).unwrap();
+    assert_eq!(portfolio.tokens.len(), 1);
+    assert!(portfolio.tokens[0].is_closable());
+    assert_eq!(portfolio.tokens[0].decimals, Some(6));
+
+    let ata = portfolio.tokens[0].address;
+    mint_tokens(&client, &mint.pubkey(), &payer, &ata, 2_500_000, &config).unwrap();
+    let portfolio = get_portfolio(&client, &payer.pubkey()).unwrap();
+    assert_eq!(portfolio.tokens[0].ui_amount(), "2.5");
+    assert_eq!(portfolio.tokens[0].label(), "DEMCHUK");
+    assert_eq!(portfolio.reclaimable_rent(), 0);
+}
@@ -0,0 +1,62 @@
+use std::thread;
+
+use solana_program::message::Message;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::transfer;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use tiny_http::Server;
+
+use practice_2::remote_signer::{serve, RemoteSigner};
+use practice_2::SignerSource;
+
+/// Starts the stand-in signing service on a free port.
+fn start_server(keypair: Keypair, token: Option<&'static str>) -> String {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+
+    thread::spawn(move || serve(&server, &keypair, token, |_, _| {}));
+
+    url
+}
+
+#[test]
+fn remote_signer_signs_transactions() {
+    let keypair = Keypair::new();
+    let pubkey = keypair.pubkey();
+    let url = start_server(keypair, None);
+
+    let signer = url.parse::<SignerSource>().unwrap().signer().unwrap();
+    assert_eq!(signer.pubkey(), pubkey);
+
+    let message = Message::new(&[transfer(&pubkey, &Pubkey::new_unique(), 1)], Some(&pubkey));
+    let tx = Transaction::new(&[signer.as_ref()], message, Hash::new_unique());
+
+    tx.verify().unwrap();
+}
+
+#[test]
+fn remote_signer_requires_token() {
+    let url = start_server(Keypair::new(), Some("secret"));
+
+    assert!(RemoteSigner::connect_with_token(&url, None).is_err());
+    assert!(RemoteSigner::connect_with_token(&url, Some("wrong".to_string())).is_err());
+
+    let signer = RemoteSigner::connect_with_token(&url, Some("secret".to_string())).unwrap();
+    let signature = signer.try_sign_message(b"hello").unwrap();
+    assert!(signature.verify(signer.pubkey().as_ref(), b"hello"));
+}
+
+#[test]
+fn presigned_signature_is_used_as_is() {
+    let keypair = Keypair::new();
+    let signature = keypair.sign_message(b"message");
+
+    let source = format!("{}={}", keypair.pubkey(), signature);
+    let signer = source.parse::<SignerSource>().unwrap().signer().unwrap();
+
+    assert_eq!(signer.pubkey(), keypair.pubkey());
+    assert_eq!(signer.try_sign_message(b"message").unwrap(), signature);
+    assert!(signer.try_sign_message(b"other message").is_err());
+}
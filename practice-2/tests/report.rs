@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::signature::Signature;
+
+use practice_2::report::{Report, ReportStatus};
+use practice_2::TransactionOutcome;
+
+#[test]
+fn reports_a_sent_transaction_without_its_details() {
+    let mocks = HashMap::from([(RpcRequest::GetTransaction, json!("not a transaction"))]);
+    let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+    let signature = Signature::new_unique();
+
+    let report = Report::new(
+        &client,
+        "sol transfer",
+        &TransactionOutcome::Confirmed(signature),
+        &[],
+    );
+
+    assert_eq!(report.status, ReportStatus::Confirmed);
+    assert_eq!(report.signature, Some(signature.to_string()));
+    assert_eq!(report.slot, None);
+    assert_eq!(report.fee, None);
+    assert!(report.balances.is_empty());
+    let warning = report.warning.as_deref().unwrap();
+    assert!(
+        warning.starts_with("transaction details unavailable"),
+        "{warning}"
+    );
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["signature"], signature.to_string());
+    assert!(json["slot"].is_null());
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY;
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+use practice_2::sender::{classify, ErrorClass};
+use practice_2::SendConfig;
+
+fn response_error(code: i64) -> ClientError {
+    ClientErrorKind::RpcError(RpcError::RpcResponseError {
+        code,
+        message: String::new(),
+        data: RpcResponseErrorData::Empty,
+    })
+    .into()
+}
+
+#[test]
+fn classifies_rpc_errors() {
+    let cases = [
+        (
+            ClientErrorKind::from(TransactionError::BlockhashNotFound).into(),
+            ErrorClass::BlockhashNotFound,
+        ),
+        (
+            ClientErrorKind::from(TransactionError::AlreadyProcessed).into(),
+            ErrorClass::AlreadyProcessed,
+        ),
+        (
+            ClientErrorKind::from(TransactionError::WouldExceedMaxBlockCostLimit).into(),
+            ErrorClass::Retry,
+        ),
+        (
+            ClientErrorKind::from(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(1),
+            ))
+            .into(),
+            ErrorClass::Fatal,
+        ),
+        (
+            ClientErrorKind::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset)).into(),
+            ErrorClass::Retry,
+        ),
+        (
+            response_error(JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY),
+            ErrorClass::Retry,
+        ),
+        (response_error(-32602), ErrorClass::Fatal),
+    ];
+
+    for (error, class) in cases {
+        assert_eq!(classify(&error), class, "{error}");
+    }
+}
+
+#[test]
+fn backoff_doubles_up_to_the_limit() {
+    let config = SendConfig::default();
+
+    let waits: Vec<Duration> = (1..=6).map(|attempt| config.backoff(attempt)).collect();
+
+    assert_eq!(waits, [1, 2, 4, 8, 8, 8].map(Duration::from_secs).to_vec());
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use solana_program::hash::Hash;
+use solana_program::message::{v0, Message, VersionedMessage};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::transfer;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::signature::{Keypair, Signer};
+use spl_associated_token_account::get_associated_token_address;
+
+use practice_2::simulation::writable_accounts;
+use practice_2::token::{create_associated_token_account, create_token_mint, mint_tokens};
+use practice_2::{TransactionConfig, TransactionOutcome};
+
+use common::{funded_keypair, localnet};
+
+#[test]
+fn writable_accounts_of_messages() {
+    let payer = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let ix = transfer(&payer, &recipient, 1);
+
+    let legacy = VersionedMessage::Legacy(Message::new(std::slice::from_ref(&ix), Some(&payer)));
+    assert_eq!(writable_accounts(&legacy, &[]), vec![payer, recipient]);
+
+    // The recipient is loaded from the table.
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: vec![Pubkey::new_unique(), recipient],
+    };
+    let message =
+        v0::Message::try_compile(&payer, &[ix], std::slice::from_ref(&table), Hash::default())
+            .unwrap();
+    let v0 = VersionedMessage::V0(message);
+    assert_eq!(writable_accounts(&v0, &[table]), vec![payer, recipient]);
+    assert_eq!(writable_accounts(&v0, &[]), vec![payer]);
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn dry_run_sends_nothing() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 2);
+    let mint = Keypair::new();
+    let config = TransactionConfig::default();
+    let dry_run = TransactionConfig {
+        dry_run: true,
+        ..TransactionConfig::default()
+    };
+
+    create_token_mint(&client, &payer, &mint, 2, &config).unwrap();
+    let ata = get_associated_token_address(&payer.pubkey(), &mint.pubkey());
+    create_associated_token_account(&client, &mint.pubkey(), &payer.pubkey(), &payer, &config)
+        .unwrap();
+    let balance = client.get_balance(&payer.pubkey()).unwrap();
+
+    let outcome = mint_tokens(&client, &mint.pubkey(), &payer, &ata, 150, &dry_run).unwrap();
+    let TransactionOutcome::Simulated(simulation) = outcome else {
+        panic!("expected a simulation, got {outcome:?}");
+    };
+    assert_eq!(simulation.error, None);
+    assert!(simulation.units_consumed.unwrap() > 0);
+    assert!(!simulation.logs.is_empty());
+
+    let token = simulation
+        .accounts
+        .iter()
+        .find(|account| account.address == ata)
+        .and_then(|account| account.token.clone())
+        .unwrap();
+    assert_eq!((token.pre, token.post), (Some(0), Some(150)));
+    assert_eq!(token.decimals, 2);
+
+    // Nothing changed on chain.
+    assert_eq!(client.get_balance(&payer.pubkey()).unwrap(), balance);
+    let amount = client.get_token_account_balance(&ata).unwrap().amount;
+    assert_eq!(amount, "0");
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn dry_run_reports_failures() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 1);
+    let dry_run = TransactionConfig {
+        dry_run: true,
+        ..TransactionConfig::default()
+    };
+
+    // More than the payer has.
+    let ix = transfer(&payer.pubkey(), &Pubkey::new_unique(), 5_000_000_000);
+    let outcome =
+        practice_2::transaction::process_transaction(&client, &[ix], &[&payer], &dry_run).unwrap();
+
+    let TransactionOutcome::Simulated(simulation) = outcome else {
+        panic!("expected a simulation, got {outcome:?}");
+    };
+    assert!(simulation.error.is_some());
+}
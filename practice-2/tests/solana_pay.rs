@@ -0,0 +1,275 @@
+mod common;
+
+use solana_program::pubkey::Pubkey;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::message::Message;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError, VersionedTransaction};
+use solana_transaction_status::{TransactionStatusMeta, UiTransactionStatusMeta};
+
+use practice_2::history::{transaction_keys, HistoryEntry};
+use practice_2::solana_pay::{check_payment, find_payment, pay, pay_instructions, TransferRequest};
+use practice_2::{Error, PayError, TransactionConfig};
+
+use common::{funded_keypair, localnet};
+
+fn request(recipient: Pubkey) -> TransferRequest {
+    TransferRequest {
+        amount: Some("0.5".parse().unwrap()),
+        references: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        label: Some("Coffee & Co".to_string()),
+        message: Some("Order #42".to_string()),
+        memo: Some("inv 42".to_string()),
+        ..TransferRequest::new(recipient)
+    }
+}
+
+#[test]
+fn writes_and_parses_urls() {
+    let request = request(Pubkey::new_unique());
+    let url = request.to_string();
+
+    assert_eq!(
+        url,
+        format!(
+            "solana:{}?amount=0.5&reference={}&reference={}&label=Coffee+%26+Co\
+             &message=Order+%2342&memo=inv+42",
+            request.recipient, request.references[0], request.references[1]
+        )
+    );
+    assert_eq!(url.parse::<TransferRequest>().unwrap(), request);
+
+    let recipient = Pubkey::new_unique();
+    assert_eq!(
+        TransferRequest::new(recipient).to_string(),
+        format!("solana:{recipient}")
+    );
+}
+
+#[test]
+fn parses_urls_of_other_wallets() {
+    let mint = Pubkey::new_unique();
+    let url = format!(
+        "solana:mvines9iiHiQTysrwkJjGf2gb9Ex9jXJX8ns3qwf2kN?amount=0.01&spl-token={mint}\
+         &message=Thanks%20for%20all%20the%20fish&unknown=field"
+    );
+
+    let request: TransferRequest = url.parse().unwrap();
+    assert_eq!(
+        request.recipient.to_string(),
+        "mvines9iiHiQTysrwkJjGf2gb9Ex9jXJX8ns3qwf2kN"
+    );
+    assert_eq!(request.amount, Some("0.01".parse().unwrap()));
+    assert_eq!(request.spl_token, Some(mint));
+    assert_eq!(request.message.as_deref(), Some("Thanks for all the fish"));
+    assert_eq!(request.references, []);
+    assert_eq!(request.currency(), mint.to_string());
+}
+
+#[test]
+fn rejects_invalid_urls() {
+    let recipient = Pubkey::new_unique();
+    for url in [
+        format!("https://{recipient}"),
+        format!("solana:{recipient}?amount=1&amount=2"),
+        format!("solana:{recipient}?reference=nobody"),
+        "solana:https%3A%2F%2Fshop.example%2Fpay".to_string(),
+        "solana:nobody".to_string(),
+    ] {
+        assert!(
+            matches!(
+                url.parse::<TransferRequest>(),
+                Err(Error::Pay(PayError::InvalidUrl(_)))
+            ),
+            "{url}"
+        );
+    }
+    for amount in ["1e3", "-1", "1,5"] {
+        assert!(
+            matches!(
+                format!("solana:{recipient}?amount={amount}").parse::<TransferRequest>(),
+                Err(Error::Amount(_))
+            ),
+            "{amount}"
+        );
+    }
+}
+
+#[test]
+fn puts_the_memo_first_and_references_on_the_transfer() {
+    let payer = Pubkey::new_unique();
+    let request = request(Pubkey::new_unique());
+
+    let instructions = pay_instructions(&payer, &request, None).unwrap();
+    assert_eq!(instructions.len(), 2);
+    assert_eq!(instructions[0].program_id, spl_memo::id());
+    assert_eq!(instructions[0].data, b"inv 42");
+
+    let transfer = &instructions[1];
+    assert_eq!(transfer.program_id, solana_program::system_program::id());
+    assert_eq!(transfer.accounts[1].pubkey, request.recipient);
+    for (account, reference) in transfer.accounts[2..].iter().zip(&request.references) {
+        assert_eq!(account.pubkey, *reference);
+        assert!(!account.is_signer && !account.is_writable);
+    }
+
+    let mint = Pubkey::new_unique();
+    let tokens = TransferRequest {
+        spl_token: Some(mint),
+        memo: None,
+        ..request.clone()
+    };
+    let instructions = pay_instructions(&payer, &tokens, Some((spl_token_2022::id(), 6))).unwrap();
+    assert_eq!(instructions.len(), 1);
+    assert_eq!(instructions[0].program_id, spl_token_2022::id());
+    assert_eq!(
+        instructions[0].accounts[2].pubkey,
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &request.recipient,
+            &mint,
+            &spl_token_2022::id()
+        )
+    );
+    assert_eq!(instructions[0].accounts.len(), 4 + request.references.len());
+
+    let no_amount = TransferRequest {
+        amount: None,
+        ..request
+    };
+    assert!(matches!(
+        pay_instructions(&payer, &no_amount, None),
+        Err(Error::Pay(PayError::NoAmount))
+    ));
+}
+
+/// The transaction paying `request` from `payer`, and a status meta where
+/// the recipient received `received` lamports.
+fn paid(
+    payer: &Pubkey,
+    request: &TransferRequest,
+    received: u64,
+) -> (VersionedTransaction, TransactionStatusMeta) {
+    let message = Message::new(
+        &pay_instructions(payer, request, None).unwrap(),
+        Some(payer),
+    );
+    let recipient = message
+        .account_keys
+        .iter()
+        .position(|key| *key == request.recipient)
+        .unwrap();
+    let mut pre_balances = vec![1_000_000_000; message.account_keys.len()];
+    pre_balances[recipient] = 0;
+    let mut post_balances = pre_balances.clone();
+    post_balances[recipient] = received;
+
+    let meta = TransactionStatusMeta {
+        fee: 5_000,
+        pre_balances,
+        post_balances,
+        ..TransactionStatusMeta::default()
+    };
+
+    (
+        VersionedTransaction::from(Transaction::new_unsigned(message)),
+        meta,
+    )
+}
+
+fn check(
+    request: &TransferRequest,
+    transaction: &VersionedTransaction,
+    meta: TransactionStatusMeta,
+) -> Result<u64, PayError> {
+    let meta = UiTransactionStatusMeta::from(meta);
+    let entry = HistoryEntry::new(
+        &request.recipient,
+        Signature::default(),
+        1,
+        None,
+        transaction,
+        &meta,
+    );
+
+    check_payment(request, 9, &entry, &transaction_keys(transaction, &meta))
+        .map(|payment| payment.received)
+}
+
+#[test]
+fn checks_what_the_recipient_received() {
+    let payer = Pubkey::new_unique();
+    let request = request(Pubkey::new_unique());
+    let mismatch = |result: Result<u64, PayError>| match result {
+        Err(PayError::Mismatch { reason, .. }) => reason,
+        other => panic!("{other:?}"),
+    };
+
+    let (transaction, meta) = paid(&payer, &request, 500_000_000);
+    assert_eq!(
+        check(&request, &transaction, meta.clone()).unwrap(),
+        500_000_000
+    );
+
+    let (short, short_meta) = paid(&payer, &request, 499_999_999);
+    assert!(mismatch(check(&request, &short, short_meta)).contains("received 0.499999999 SOL"));
+
+    let tokens = TransferRequest {
+        spl_token: Some(Pubkey::new_unique()),
+        ..request.clone()
+    };
+    assert!(mismatch(check(&tokens, &transaction, meta.clone())).contains("received 0 "));
+
+    let other_reference = TransferRequest {
+        references: vec![Pubkey::new_unique()],
+        ..request.clone()
+    };
+    assert!(mismatch(check(&other_reference, &transaction, meta.clone())).contains("refer"));
+
+    let other_memo = TransferRequest {
+        memo: Some("inv 43".to_string()),
+        ..request.clone()
+    };
+    assert!(mismatch(check(&other_memo, &transaction, meta.clone())).contains("memo"));
+
+    let failed = TransactionStatusMeta {
+        status: Err(TransactionError::InstructionError(
+            1,
+            InstructionError::InsufficientFunds,
+        )),
+        ..meta
+    };
+    assert!(mismatch(check(&request, &transaction, failed)).contains("failed"));
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn pays_and_finds_the_payment() {
+    let client = localnet();
+    let payer = funded_keypair(&client, 1);
+    let request = TransferRequest {
+        amount: Some("0.01".parse().unwrap()),
+        references: vec![Keypair::new().pubkey()],
+        memo: Some("order 7".to_string()),
+        ..TransferRequest::new(Keypair::new().pubkey())
+    };
+
+    assert!(matches!(
+        find_payment(&client, &request),
+        Err(Error::Pay(PayError::NotFound(_)))
+    ));
+
+    pay(&client, &payer, &request, &TransactionConfig::default()).unwrap();
+
+    let payment = find_payment(&client, &request).unwrap();
+    assert_eq!(payment.payer, payer.pubkey());
+    assert_eq!(payment.received, 10_000_000);
+
+    let more = TransferRequest {
+        amount: Some("0.02".parse().unwrap()),
+        ..request
+    };
+    assert!(matches!(
+        find_payment(&client, &more),
+        Err(Error::Pay(PayError::Mismatch { .. }))
+    ));
+}
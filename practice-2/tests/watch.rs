@@ -0,0 +1,148 @@
+mod common;
+
+use std::ops::ControlFlow;
+use std::thread;
+use std::time::Duration;
+
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+
+use practice_2::airdrop::airdrop;
+use practice_2::watch::{
+    account_balance, hook_command, watch, Balance, Crossing, Source, Threshold, WatchConfig,
+    WatchEvent,
+};
+use practice_2::{AirdropConfig, ClusterConfig};
+
+use common::{localnet, localnet_url};
+
+#[test]
+fn balances_of_accounts() {
+    let wallet = Account::new(1_500_000_000, 0, &solana_program::system_program::id());
+    assert_eq!(
+        account_balance(Some(&wallet), |_| unreachable!()),
+        Balance::Sol(1_500_000_000)
+    );
+    assert_eq!(account_balance(None, |_| 0), Balance::Closed);
+
+    let mint = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint,
+        owner: Pubkey::new_unique(),
+        amount: 2_500,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }
+    .pack_into_slice(&mut data);
+    let vault = Account {
+        lamports: 2_039_280,
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+    let balance = account_balance(Some(&vault), |_| 2);
+    assert_eq!(
+        balance,
+        Balance::Token {
+            mint,
+            amount: 2_500,
+            decimals: 2
+        }
+    );
+    assert_eq!(balance.to_string(), format!("25 of {mint}"));
+}
+
+#[test]
+fn thresholds_fire_on_crossing_only() {
+    let below = Threshold::Below("1.5".parse().unwrap());
+    assert!(below.crossed(2_000_000_000, 1_000_000_000, 9).unwrap());
+    assert!(below.crossed(1_500_000_000, 1_499_999_999, 9).unwrap());
+    assert!(!below.crossed(1_000_000_000, 900_000_000, 9).unwrap());
+    assert!(!below.crossed(1_000_000_000, 2_000_000_000, 9).unwrap());
+
+    let above = Threshold::Above("10".parse().unwrap());
+    assert!(above.crossed(500, 1_001, 2).unwrap());
+    assert!(!above.crossed(1_000, 1_000, 2).unwrap());
+    assert!(!above.crossed(2_000, 3_000, 2).unwrap());
+
+    // A threshold finer than the mint cannot be compared.
+    assert!(Threshold::Above("0.5".parse().unwrap())
+        .crossed(0, 1, 0)
+        .is_err());
+}
+
+#[test]
+fn hook_sees_the_crossing() {
+    let address = Pubkey::new_unique();
+    let crossing = Crossing {
+        address,
+        threshold: Threshold::Below("1".parse().unwrap()),
+        previous: Balance::Sol(1_200_000_000),
+        balance: Balance::Closed,
+    };
+
+    let output = hook_command(
+        "echo \"$WATCH_ADDRESS $WATCH_THRESHOLD $WATCH_PREVIOUS $WATCH_BALANCE\"",
+        &crossing,
+    )
+    .output()
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        format!("{address} below 1 1.2 0")
+    );
+}
+
+#[test]
+#[ignore = "needs a local validator"]
+fn streams_balance_changes() {
+    let client = localnet();
+    let wallet = Pubkey::new_unique();
+    let cluster = ClusterConfig {
+        url: localnet_url(),
+        websocket_url: solana_cli_config::Config::compute_websocket_url(&localnet_url()),
+        commitment: client.commitment(),
+    };
+    let config = WatchConfig {
+        thresholds: vec![Threshold::Above("1".parse().unwrap())],
+        ..WatchConfig::new(&cluster)
+    };
+
+    let funder = thread::spawn(move || {
+        thread::sleep(Duration::from_secs(2));
+        airdrop(
+            &localnet(),
+            &wallet,
+            2 * LAMPORTS_PER_SOL,
+            &AirdropConfig::default(),
+        )
+        .unwrap();
+    });
+
+    let mut events = Vec::new();
+    watch(&client, &[wallet], &config, |event| {
+        let crossed = matches!(event, WatchEvent::Crossed(_));
+        events.push(event);
+        Ok(if crossed {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        })
+    })
+    .unwrap();
+    funder.join().unwrap();
+
+    assert!(events.contains(&WatchEvent::Balance {
+        address: wallet,
+        balance: Balance::Closed,
+        source: Source::Poll,
+    }));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        WatchEvent::Balance { balance: Balance::Sol(lamports), .. } if *lamports == 2 * LAMPORTS_PER_SOL
+    )));
+}